@@ -0,0 +1,37 @@
+use aoer_plotty_rs::context::Context;
+use aoer_plotty_rs::geo_types::svg::Arrangement;
+use aoer_plotty_rs::prelude::Hatches;
+use geo_types::{coord, Rect};
+use nannou::noise::{NoiseFn, Perlin};
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Fills some shapes with hatch lines that follow a Perlin noise flow field.
+fn main() {
+    let perlin = Perlin::new();
+    // Noise is -1..1, so this gives us a full turn either way, changing slowly
+    // over about 50mm.
+    let field = Arc::new(move |x: f64, y: f64| perlin.get([x / 50.0, y / 50.0]) * 2.0 * PI);
+
+    let mut ctx = Context::new();
+    ctx.stroke("black")
+        .fill("blue")
+        .pen(0.5)
+        .pattern(Hatches::field(field, 0.25))
+        .hatch(0.0)
+        .circle(30.0, 30.0, 25.0)
+        .fill("red")
+        .star_poly(5, 75.0, 70.0, 10.0, 25.0, 0.0)
+        .rect(5.0, 65.0, 45.0, 95.0);
+
+    let svg = ctx
+        .to_svg(&Arrangement::<f64>::unit(&Rect::<f64>::new(
+            coord! {x:0.0, y:0.0},
+            coord! {x:100.0, y:100.0},
+        )))
+        .unwrap();
+    // Write it out to /images/$THIS_EXAMPLE_FILE.svg
+    let fname = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
+    svg::save(format!("images/{}.svg", fname), &svg).unwrap();
+}
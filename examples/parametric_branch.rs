@@ -0,0 +1,60 @@
+use aoer_plotty_rs::context::Context;
+use aoer_plotty_rs::geo_types::svg::Arrangement;
+use aoer_plotty_rs::l_system::{ParametricLSystem, ParametricRule};
+use aoer_plotty_rs::turtle::{degrees, Turtle, TurtleTrait};
+use geo_types::{coord, Geometry, Rect};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A tapering branch from a parametric L-system. Every generation, F(length, width)
+/// sprouts two side branches that are shorter and skinnier than their parent.
+fn main() {
+    let grow: ParametricRule = Arc::new(|p: &[f64]| {
+        let (length, width) = (p[0], p.get(1).cloned().unwrap_or(1.0));
+        vec![
+            ('!', vec![width]),
+            ('F', vec![length * 0.5, width]),
+            ('[', vec![]),
+            ('+', vec![degrees(30.0)]),
+            ('F', vec![length * 0.6, width * 0.6]),
+            (']', vec![]),
+            ('[', vec![]),
+            ('-', vec![degrees(25.0)]),
+            ('F', vec![length * 0.7, width * 0.6]),
+            (']', vec![]),
+            ('!', vec![width * 0.8]),
+            ('F', vec![length * 0.5, width * 0.8]),
+        ]
+    });
+    let branch = ParametricLSystem {
+        axiom: vec![('F', vec![40.0, 2.0])],
+        rules: HashMap::from([('F', grow)]),
+    };
+
+    // Point the turtle up the page (SVG y is down) and walk it.
+    let turtle = Turtle::new()
+        .set_heading(degrees(-90.0))
+        .pen_down()
+        .walk_parametric(&branch.expand(5));
+
+    // Each pen width gets its own stroke, so the branch tapers.
+    let mut ctx = Context::new();
+    ctx.stroke("black");
+    for (color, width, lines) in turtle.to_layers() {
+        ctx.stroke(&color)
+            .pen(width)
+            .geometry(&Geometry::MultiLineString(lines));
+    }
+
+    let svg = ctx
+        .to_svg(&Arrangement::FitCenterMargin(
+            10.0,
+            Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 200.0, y: 200.0}),
+            false,
+        ))
+        .unwrap();
+    // Write it out to /images/$THIS_EXAMPLE_FILE.svg
+    let fname = Path::new(file!()).file_stem().unwrap().to_str().unwrap();
+    svg::save(format!("images/{}.svg", fname), &svg).unwrap();
+}
@@ -0,0 +1,239 @@
+use geo::prelude::EuclideanLength;
+use geo_types::{coord, Coordinate, LineString, MultiLineString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Post-processes the lines an operation renders to, after hatching, simplifying
+/// and dashing. Set one with [`crate::context::Context::stroke_filter`] or
+/// [`crate::context::Context::hatch_filter`]. They get shared between threads
+/// while rendering, hence Send + Sync.
+pub trait LineFilter: Send + Sync {
+    fn apply(&self, lines: &MultiLineString<f64>) -> MultiLineString<f64>;
+}
+
+/// Makes lines look hand drawn. Each line is chopped into pieces about `segment`
+/// mm long, every point gets nudged up to `roughness` mm in a random direction,
+/// and the ends overshoot a little, like a quick pencil stroke. Seeded, so the
+/// same drawing wobbles the same way every time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SketchyLineFilter {
+    pub roughness: f64,
+    pub segment: f64,
+    pub seed: u64,
+}
+
+impl SketchyLineFilter {
+    pub fn new(roughness: f64, segment: f64, seed: u64) -> SketchyLineFilter {
+        SketchyLineFilter {
+            roughness,
+            segment: segment.max(0.01),
+            seed,
+        }
+    }
+
+    fn sketch(&self, line: &LineString<f64>, rng: &mut StdRng) -> LineString<f64> {
+        if line.0.len() < 2 {
+            return line.clone();
+        }
+        let mut jitter = |c: Coordinate<f64>| {
+            let (angle, distance) = (
+                rng.gen_range(0.0..std::f64::consts::TAU),
+                rng.gen_range(0.0..=self.roughness),
+            );
+            coord! {x: c.x + distance * angle.cos(), y: c.y + distance * angle.sin()}
+        };
+        let overshoot = |from: Coordinate<f64>, to: Coordinate<f64>| {
+            let length = (to.x - from.x).hypot(to.y - from.y);
+            if length == 0.0 {
+                to
+            } else {
+                to + (to - from) * (self.roughness / length)
+            }
+        };
+        let mut out = vec![line.0[0]];
+        for pair in line.0.windows(2) {
+            let length = (pair[1].x - pair[0].x).hypot(pair[1].y - pair[0].y);
+            let pieces = (length / self.segment).ceil().max(1.0) as usize;
+            for piece in 1..=pieces {
+                let t = piece as f64 / pieces as f64;
+                out.push(pair[0] + (pair[1] - pair[0]) * t);
+            }
+        }
+        let last = out.len() - 1;
+        out[0] = overshoot(out[1], out[0]);
+        out[last] = overshoot(out[last - 1], out[last]);
+        let out: Vec<Coordinate<f64>> = out.into_iter().map(&mut jitter).collect();
+        LineString::new(out)
+    }
+}
+
+impl LineFilter for SketchyLineFilter {
+    fn apply(&self, lines: &MultiLineString<f64>) -> MultiLineString<f64> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        MultiLineString::new(
+            lines
+                .0
+                .iter()
+                .map(|line| self.sketch(line, &mut rng))
+                .collect(),
+        )
+    }
+}
+
+/// Hand-inked wobble. Lines get resampled and each point pushed sideways (relative
+/// to the line) by a sine wave `frequency` waves per mm, plus a bit of seeded noise,
+/// never more than `amplitude` mm all up. Each line gets its own phase so parallel
+/// hatches don't wobble in lockstep. Same seed, same wobble.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WobbleLineFilter {
+    pub amplitude: f64,
+    pub frequency: f64,
+    pub seed: u64,
+}
+
+impl WobbleLineFilter {
+    /// How much of the amplitude is noise rather than sine wave.
+    const NOISE: f64 = 0.3;
+
+    pub fn new(amplitude: f64, frequency: f64, seed: u64) -> WobbleLineFilter {
+        WobbleLineFilter {
+            amplitude,
+            frequency: frequency.max(0.001),
+            seed,
+        }
+    }
+
+    fn wobble(&self, line: &LineString<f64>, rng: &mut StdRng) -> LineString<f64> {
+        if line.0.len() < 2 {
+            return line.clone();
+        }
+        // Eight samples a wave is plenty smooth at plotter scales.
+        let step = 1.0 / (self.frequency * 8.0);
+        let phase = rng.gen_range(0.0..std::f64::consts::TAU);
+        let mut offset = |distance: f64| {
+            let wave = (std::f64::consts::TAU * self.frequency * distance + phase).sin();
+            let noise = rng.gen_range(-1.0..=1.0);
+            self.amplitude * ((1.0 - Self::NOISE) * wave + Self::NOISE * noise)
+        };
+        let mut out = vec![];
+        let mut travelled = 0.0;
+        for pair in line.0.windows(2) {
+            let delta = pair[1] - pair[0];
+            let length = delta.x.hypot(delta.y);
+            if length == 0.0 {
+                continue;
+            }
+            let normal = coord! {x: -delta.y / length, y: delta.x / length};
+            let pieces = (length / step).ceil() as usize;
+            // Segments share end points, so only the first one gets its start point.
+            let first = if out.is_empty() { 0 } else { 1 };
+            for piece in first..=pieces {
+                let t = piece as f64 / pieces as f64;
+                out.push(pair[0] + delta * t + normal * offset(travelled + length * t));
+            }
+            travelled += length;
+        }
+        if out.len() < 2 {
+            return line.clone();
+        }
+        LineString::new(out)
+    }
+}
+
+impl LineFilter for WobbleLineFilter {
+    fn apply(&self, lines: &MultiLineString<f64>) -> MultiLineString<f64> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        MultiLineString::new(
+            lines
+                .0
+                .iter()
+                .map(|line| self.wobble(line, &mut rng))
+                .collect(),
+        )
+    }
+}
+
+/// Despeckler. Throws away any line shorter (all up, not per segment) than
+/// `min_length` mm, like the little slivers hatch clipping leaves in corners,
+/// which cost a whole pen lift to draw a dot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinLengthFilter {
+    pub min_length: f64,
+}
+
+impl MinLengthFilter {
+    pub fn new(min_length: f64) -> MinLengthFilter {
+        MinLengthFilter { min_length }
+    }
+}
+
+impl LineFilter for MinLengthFilter {
+    fn apply(&self, lines: &MultiLineString<f64>) -> MultiLineString<f64> {
+        MultiLineString::new(
+            lines
+                .0
+                .iter()
+                .filter(|line| line.euclidean_length() >= self.min_length)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_min_length() {
+        let lines = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0}]),
+            LineString::new(vec![coord! {x: 0.0, y: 1.0}, coord! {x: 0.1, y: 1.0}]),
+            LineString::new(vec![
+                coord! {x: 0.0, y: 2.0},
+                coord! {x: 0.3, y: 2.0},
+                coord! {x: 0.3, y: 2.3},
+            ]),
+        ]);
+        let filtered = MinLengthFilter::new(0.5).apply(&lines);
+        assert_eq!(filtered.0, vec![lines.0[0].clone(), lines.0[2].clone()]);
+    }
+
+    #[test]
+    fn test_wobble() {
+        let lines = MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 10.0, y: 0.0},
+            coord! {x: 10.0, y: 10.0},
+        ])]);
+        let filter = WobbleLineFilter::new(0.2, 0.5, 7);
+        let wobbled = filter.apply(&lines);
+        let line = &wobbled.0[0].0;
+        assert!(line.len() > 3);
+        let near = |a: Coordinate<f64>, b: Coordinate<f64>| (a.x - b.x).hypot(a.y - b.y) <= 0.2;
+        assert!(near(line[0], coord! {x: 0.0, y: 0.0}));
+        assert!(near(line[line.len() - 1], coord! {x: 10.0, y: 10.0}));
+        for c in line {
+            assert!(c.y.abs() <= 0.2 + 1e-9 || (c.x - 10.0).abs() <= 0.2 + 1e-9);
+        }
+        assert_eq!(wobbled, filter.apply(&lines));
+        assert_ne!(wobbled, WobbleLineFilter::new(0.2, 0.5, 8).apply(&lines));
+    }
+
+    #[test]
+    fn test_sketchy() {
+        let lines = MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 10.0, y: 0.0},
+        ])]);
+        let filter = SketchyLineFilter::new(0.5, 1.0, 42);
+        let sketched = filter.apply(&lines);
+        assert_eq!(sketched.0[0].0.len(), 11);
+        for c in &sketched.0[0].0 {
+            assert!(c.y.abs() <= 0.5 + 1e-9);
+            assert!(c.x > -1.0 - 1e-9 && c.x < 11.0 + 1e-9);
+        }
+        assert_eq!(sketched, filter.apply(&lines));
+        assert_ne!(sketched, SketchyLineFilter::new(0.5, 1.0, 43).apply(&lines));
+    }
+}
@@ -4,17 +4,22 @@ use embed_doc_image::embed_doc_image;
 use std::error::Error;
 
 use crate::errors::ContextError;
+use crate::gcode::{post_with_pens, PenConfig, PostGeometrySource, PostOptions};
+use crate::geo_types::buffer::Buffer;
 use crate::geo_types::clip::{try_to_geos_geometry, LineClip};
 use crate::geo_types::{shapes, ToGeos};
+use crate::optimizer::{OptimizationStrategy, Optimizer};
 use crate::prelude::{Arrangement, Hatches, ToSvg};
 use cubic_spline::{Points, SplineOpts};
 use font_kit::font::Font;
 use font_kit::hinting::HintingOptions;
+use geo::line_interpolate_point::LineInterpolatePoint;
 use geo::map_coords::MapCoords;
-use geo::prelude::BoundingRect;
+use geo::prelude::{BoundingRect, EuclideanDistance, EuclideanLength};
+use geo::simplify::Simplify;
 use geo_types::{
-    coord, Coordinate, Geometry, GeometryCollection, LineString, MultiLineString, Point, Polygon,
-    Rect,
+    coord, Coordinate, Geometry, GeometryCollection, LineString, MultiLineString, MultiPolygon,
+    Point, Polygon, Rect,
 };
 use geos::{Geom, GeometryTypes};
 pub use kurbo::BezPath;
@@ -22,9 +27,13 @@ use kurbo::PathEl;
 pub use kurbo::Point as BezPoint;
 use nalgebra::{Affine2, Matrix3};
 use nannou::prelude::PI_F64;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::f64::consts::PI;
 use std::sync::Arc;
 use svg::Document;
+use tera::Tera;
 
 pub mod operation;
 
@@ -36,9 +45,28 @@ use glyph_proxy::GlyphProxy;
 
 pub mod typography;
 
+pub mod line_filter;
+
+use line_filter::LineFilter;
+
+pub mod pen_registry;
+
+use pen_registry::PenRegistry;
+
+pub mod plot_report;
+
+use plot_report::{LayerStats, PlotReport};
+
+use crate::geo_types::fillet::Fillet;
 use crate::geo_types::flatten::FlattenPolygons;
 use typography::Typography;
 
+/// An SVG node, with its pen color and the named layer it came from.
+type SvgNode = (String, Option<String>, Box<dyn svg::Node>);
+
+/// How long (mm) the corner registration marks on tiled pages are.
+const REGISTRATION_MARK_SIZE: f64 = 5.0;
+
 /// # Context
 ///
 /// A Context is a _drawing_ context, used to perform operations against a
@@ -102,12 +130,19 @@ use typography::Typography;
 /// ;
 /// ```
 /// ![context_basic][context_basic]
+///
+/// Contexts serialize (with serde) so you can cache a drawing on disk. Fonts
+/// can't be saved though, so a reloaded context gets the default font back, and
+/// field hatches come back flat (see [`crate::geo_types::hatch::FieldHatch`]).
 #[embed_doc_image("context_basic", "images/context_basic.png")]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Context {
     operations: Vec<Operation>,
     accuracy: f64,
+    #[serde(skip, default = "Context::reload_font")]
     font: Option<Font>,
+    #[serde(skip)]
+    default_font: Option<Font>,
     transformation: Option<Affine2<f64>>,
     stroke_color: String,
     outline_stroke: Option<f64>,
@@ -116,10 +151,32 @@ pub struct Context {
     line_cap: String,
     pen_width: f64,
     mask: Option<Geometry<f64>>,
+    #[serde(default)]
+    mask_line_width: Option<f64>,
     clip_previous: bool,
     hatch_pattern: Hatches,
     hatch_angle: f64,
+    #[serde(default)]
+    hatch_scale: Option<f64>,
+    guide: bool,
+    auto_hatch_step: Option<f64>,
+    hatch_bump: bool,
+    dash: Option<Vec<f64>>,
+    dash_fills: bool,
+    stroke_dasharray: Option<String>,
+    #[serde(skip)]
+    stroke_filter: Option<Arc<Box<dyn LineFilter>>>,
+    #[serde(skip)]
+    hatch_filter: Option<Arc<Box<dyn LineFilter>>>,
+    #[serde(default)]
+    pens: PenRegistry,
     stack: Vec<Context>,
+    layer: Option<(String, usize)>,
+    #[serde(skip)]
+    bounds_cache: Cell<Option<Rect<f64>>>,
+    #[cfg(test)]
+    #[serde(skip)]
+    bounds_computed: Cell<usize>,
 }
 
 impl Context {
@@ -136,6 +193,48 @@ impl Context {
         Font::from_bytes(Arc::new(font_data), 0).unwrap() // We know this font is OK
     }
 
+    fn reload_font() -> Option<Font> {
+        Some(Context::default_font())
+    }
+
+    /// Sets your favorite single line font as this context's default, instead of
+    /// the bundled one. [`Context::glyph`] draws with it, and any [`Typography`]
+    /// you hand to this context ([`Context::typography`], [`Context::paragraph`],
+    /// etc.) uses it too, unless you gave that Typography a font of its own.
+    pub fn set_default_font(&mut self, font: Font) -> &mut Self {
+        self.font = Some(font.clone());
+        self.default_font = Some(font);
+        self
+    }
+
+    /// The typography, with our default font swapped in if it hasn't got its own.
+    fn with_default_font(&self, typography: &Typography) -> Typography {
+        let mut typography = typography.clone();
+        if let (Some(font), false) = (&self.default_font, typography.custom_font) {
+            typography.font(font);
+        }
+        typography
+    }
+
+    /// Swaps this context's font (used by [`Context::glyph`]) for the font file at
+    /// `path`. Single line fonts plot nicely as is; regular outline fonts work too,
+    /// but their glyphs are closed shapes, so they'll get outlined and filled with
+    /// whatever hatch pattern is current.
+    pub fn font_from_path(&mut self, path: &std::path::Path) -> Result<&mut Self, ContextError> {
+        let bytes = std::fs::read(path)
+            .map_err(|err| ContextError::FontLoadError(format!("{}: {}", path.display(), err)))?;
+        self.font_from_bytes(bytes)
+    }
+
+    /// Same as [`Context::font_from_path`], but for font data you've already got (say,
+    /// from `include_bytes!`). Only the first face of a font collection is used.
+    pub fn font_from_bytes(&mut self, bytes: Vec<u8>) -> Result<&mut Self, ContextError> {
+        let font = Font::from_bytes(Arc::new(bytes), 0)
+            .map_err(|err| ContextError::FontLoadError(err.to_string()))?;
+        self.font = Some(font);
+        Ok(self)
+    }
+
     /// Finalize Arrangement
     pub fn finalize_arrangement(&self, arrangement: &Arrangement<f64>) -> Arrangement<f64> {
         if let Ok(bounds) = self.bounds() {
@@ -145,6 +244,50 @@ impl Context {
         }
     }
 
+    /// Builds a unit [`Arrangement`] whose viewbox matches an existing SVG document,
+    /// so that anything you draw registers exactly with the imported artwork. Uses
+    /// the `viewBox` if there is one, otherwise falls back on `width`/`height`
+    /// (units like "mm" are ignored, so make sure they're what you expect).
+    pub fn arrangement_from_svg(&self, svg_text: &str) -> Result<Arrangement<f64>, ContextError> {
+        let parse_err = |msg: &str| ContextError::SvgParseError(msg.to_string());
+        let parser = svg::read(svg_text).or(Err(parse_err("Unreadable svg")))?;
+        for event in parser {
+            if let svg::parser::Event::Tag("svg", _, attributes) = event {
+                if let Some(viewbox) = attributes.get("viewBox") {
+                    let vals: Vec<f64> = viewbox
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|s| !s.is_empty())
+                        .map(|s| s.parse::<f64>())
+                        .collect::<Result<Vec<f64>, _>>()
+                        .or(Err(parse_err("Invalid viewBox")))?;
+                    if vals.len() != 4 {
+                        return Err(parse_err("viewBox needs exactly 4 values"));
+                    }
+                    return Ok(Arrangement::unit(&Context::viewbox(
+                        vals[0],
+                        vals[1],
+                        vals[0] + vals[2],
+                        vals[1] + vals[3],
+                    )));
+                }
+                let dimension = |name: &str| -> Result<f64, ContextError> {
+                    attributes
+                        .get(name)
+                        .ok_or(parse_err("Missing viewBox and width/height"))?
+                        .trim_end_matches(|c: char| c.is_alphabetic() || c == '%')
+                        .trim()
+                        .parse::<f64>()
+                        .or(Err(parse_err("Invalid width/height")))
+                };
+                let (width, height) = (dimension("width")?, dimension("height")?);
+                return Ok(Arrangement::unit(&Context::viewbox(
+                    0.0, 0.0, width, height,
+                )));
+            }
+        }
+        Err(parse_err("No svg element found"))
+    }
+
     /// Viewbox helper. Useful to create an arbitrary viewbox for
     /// your SVGs.
     pub fn viewbox(x0: f64, y0: f64, x1: f64, y1: f64) -> Rect<f64> {
@@ -190,6 +333,7 @@ impl Context {
             accuracy: 0.1, // 0.1mm should be close enough for anybody
             transformation: None,
             font: Some(Context::default_font()),
+            default_font: None,
             stroke_color: "black".to_string(),
             outline_stroke: None,
             fill_color: "black".to_string(),
@@ -197,23 +341,63 @@ impl Context {
             line_cap: "round".to_string(),
             pen_width: 0.5,
             mask: None,
+            mask_line_width: None,
             clip_previous: false,
             hatch_pattern: Hatches::line(),
             hatch_angle: 0.0,
+            hatch_scale: None,
+            guide: false,
+            auto_hatch_step: None,
+            hatch_bump: false,
+            dash: None,
+            dash_fills: false,
+            stroke_dasharray: None,
+            stroke_filter: None,
+            hatch_filter: None,
+            pens: PenRegistry::new(),
             stack: vec![],
+            layer: None,
+            bounds_cache: Cell::new(None),
+            #[cfg(test)]
+            bounds_computed: Cell::new(0),
         }
     }
 
+    /// Throws away everything drawn (and the push/pop stack, and any open layer) but
+    /// keeps the pen, colors, font, hatching and transform, so you can reuse one
+    /// context per frame of an animation.
+    pub fn clear(&mut self) -> &mut Self {
+        self.operations.clear();
+        self.stack.clear();
+        self.layer = None;
+        self.bounds_cache.set(None);
+        self
+    }
+
+    /// How many operations have been drawn so far.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
     /// Bounds returns a Rect defining the bounds of all operations drawn on the context.
-    /// Note: Since this has to iterate over ALL geometry in the drawing, it's kind of expensive.
-    /// I'll probably cache this per operation at some point, but for now it's pricy.
+    /// Note: Since this has to iterate over ALL geometry in the drawing, it's kind of expensive,
+    /// so the result is cached until the next operation is added or removed.
     pub fn bounds(&self) -> Result<Rect<f64>, Box<dyn Error>> {
+        if let Some(bounds) = self.bounds_cache.get() {
+            return Ok(bounds);
+        }
+        #[cfg(test)]
+        self.bounds_computed.set(self.bounds_computed.get() + 1);
         let mut pmin = Point::new(f64::MAX, f64::MAX);
         let mut pmax = Point::new(f64::MIN, f64::MIN);
         for operation in &self.operations {
             let tmp_bounds = operation.content.bounding_rect();
             if let Some(bounds) = tmp_bounds {
-                pmin = Point::new(pmin.y().min(bounds.min().y), pmin.y().min(bounds.min().y));
+                pmin = Point::new(pmin.x().min(bounds.min().x), pmin.y().min(bounds.min().y));
                 pmax = Point::new(pmax.x().max(bounds.max().x), pmax.y().max(bounds.max().y));
             }
         }
@@ -223,10 +407,36 @@ impl Context {
                 found: "Empty context",
             }))
         } else {
-            Ok(Rect::new(pmin.0, pmax.0))
+            let bounds = Rect::new(pmin.0, pmax.0);
+            self.bounds_cache.set(Some(bounds));
+            Ok(bounds)
         }
     }
 
+    /// Debugging helper which draws a rectangle around everything drawn so far
+    /// (see [`Context::bounds`]). With no style it's drawn as a guide, so it shows up
+    /// in the SVG but never gets plotted. Pass Some(color) to draw a real frame in
+    /// that color instead. Ignores the current transform/mask, since the bounds
+    /// are already in drawing space.
+    pub fn draw_bounds(&mut self, style: Option<&str>) -> Result<&mut Self, ContextError> {
+        let bounds = self.bounds().or(Err(ContextError::EmptyContext))?;
+        let mut frame = Context::new();
+        frame
+            .pen(self.pen_width)
+            .pattern(Hatches::none())
+            .stroke(style.unwrap_or(&self.stroke_color))
+            .guide(style.is_none())
+            .rect(
+                bounds.min().x,
+                bounds.min().y,
+                bounds.max().x,
+                bounds.max().y,
+            );
+        self.operations.append(&mut frame.operations);
+        self.bounds_cache.set(None);
+        Ok(self)
+    }
+
     /// Masks any further operations with a clipping polygon. Only items
     /// inside the clipping poly will be used.
     pub fn mask_poly(
@@ -259,18 +469,46 @@ impl Context {
         )
     }
 
-    /// Sets the mask to Geometry, or None.
+    /// Sets the mask to Geometry, or None. Masks need some area to clip with, so
+    /// points and lines get buffered into a strip [`Context::mask_line_width`] wide
+    /// first (after the transform, so the width is in drawing mm).
     pub fn set_mask(&mut self, mask: &Option<Geometry<f64>>) -> &mut Self {
         self.mask = match mask {
-            Some(maskgeo) => Some(match &self.transformation {
-                Some(affine) => maskgeo.map_coords(|xy| Operation::xform_coord(xy, affine)),
-                None => maskgeo.clone(),
-            }),
+            Some(maskgeo) => {
+                let maskgeo = match &self.transformation {
+                    Some(affine) => maskgeo.map_coords(|xy| Operation::xform_coord(xy, affine)),
+                    None => maskgeo.clone(),
+                };
+                Some(self.areal_mask(maskgeo))
+            }
             None => mask.clone(),
         };
         self
     }
 
+    /// How wide a strip a line or point mask turns into. None (the default) means
+    /// the current pen width.
+    pub fn mask_line_width(&mut self, width: Option<f64>) -> &mut Self {
+        self.mask_line_width = width;
+        self
+    }
+
+    /// Buffers non-areal masks into polygons. If the buffer fails we end up with an
+    /// empty mask, which masks everything, same as an empty polygon would.
+    fn areal_mask(&self, mask: Geometry<f64>) -> Geometry<f64> {
+        let mask = match mask {
+            Geometry::Line(line) => Geometry::LineString(line.into()),
+            mask => mask,
+        };
+        match mask {
+            Geometry::Point(_) | Geometry::LineString(_) | Geometry::MultiLineString(_) => {
+                let radius = self.mask_line_width.unwrap_or(self.pen_width) / 2.0;
+                Geometry::MultiPolygon(mask.buffer(radius).unwrap_or(MultiPolygon::new(vec![])))
+            }
+            mask => mask,
+        }
+    }
+
     /// Pushes the current context onto the stack.
     pub fn push(&mut self) -> &mut Self {
         self.stack.push(Self {
@@ -280,6 +518,7 @@ impl Context {
                 Some(font) => Some(font.clone()),
                 None => None,
             },
+            default_font: self.default_font.clone(),
             transformation: match self.transformation.clone() {
                 Some(transformation) => Some(transformation),
                 None => None,
@@ -291,10 +530,25 @@ impl Context {
             line_cap: self.line_cap.clone(),
             pen_width: self.pen_width.clone(),
             mask: self.mask.clone(),
+            mask_line_width: self.mask_line_width,
             clip_previous: self.clip_previous.clone(),
             hatch_pattern: self.hatch_pattern.clone(),
             hatch_angle: self.hatch_angle,
+            hatch_scale: self.hatch_scale,
+            guide: self.guide,
+            auto_hatch_step: self.auto_hatch_step,
+            hatch_bump: false,
+            dash: self.dash.clone(),
+            dash_fills: self.dash_fills,
+            stroke_dasharray: self.stroke_dasharray.clone(),
+            stroke_filter: self.stroke_filter.clone(),
+            hatch_filter: self.hatch_filter.clone(),
+            pens: PenRegistry::new(),
             stack: vec![],
+            layer: None,
+            bounds_cache: Cell::new(None),
+            #[cfg(test)]
+            bounds_computed: Cell::new(0),
         });
         self
     }
@@ -313,8 +567,17 @@ impl Context {
         self.line_join = other.line_join.clone();
         self.line_cap = other.line_cap.clone();
         self.pen_width = other.pen_width.clone();
+        self.mask_line_width = other.mask_line_width;
         self.hatch_angle = other.hatch_angle;
+        self.hatch_scale = other.hatch_scale;
         self.clip_previous = other.clip_previous.clone();
+        self.guide = other.guide;
+        self.auto_hatch_step = other.auto_hatch_step;
+        self.dash = other.dash.clone();
+        self.dash_fills = other.dash_fills;
+        self.stroke_dasharray = other.stroke_dasharray.clone();
+        self.stroke_filter = other.stroke_filter.clone();
+        self.hatch_filter = other.hatch_filter.clone();
         Ok(self)
     }
 
@@ -352,6 +615,12 @@ impl Context {
     /// Adds any arbitrary Geometry type (geo_types geometry)
     fn add_operation(&mut self, geometry: Geometry<f64>) {
         let geometry = geometry.flatten();
+        if let (Some(step), Some(last)) = (self.auto_hatch_step, self.operations.last()) {
+            if self.hatch_bump || last.fill_color != self.fill_color {
+                self.hatch_angle += step;
+            }
+        }
+        self.hatch_bump = false;
         let op = Operation {
             content: geometry,
             rendered: (MultiLineString::new(vec![]), MultiLineString::new(vec![])),
@@ -367,9 +636,20 @@ impl Context {
             clip_previous: self.clip_previous.clone(),
             hatch_pattern: self.hatch_pattern.clone(),
             hatch_angle: self.hatch_angle,
+            hatch_scale: self.hatch_scale,
+            guide: self.guide,
+            stroke_gradient: None,
+            dash: self.dash.clone(),
+            dash_fills: self.dash_fills,
+            stroke_dasharray: self.stroke_dasharray.clone(),
+            layer: None,
+            stitched: None,
+            stroke_filter: self.stroke_filter.clone(),
+            hatch_filter: self.hatch_filter.clone(),
         };
         let op = op.render();
         self.operations.push(op);
+        self.bounds_cache.set(None);
     }
 
     /// Adds a geometry to the operations list. Has some checking to make it safe
@@ -447,7 +727,7 @@ impl Context {
         y0: f64,
         typography: &Typography,
     ) -> &mut Self {
-        let typ = typography.clone();
+        let typ = self.with_default_font(typography);
         let geo = typ
             .render(text, self.accuracy)
             .unwrap_or(Geometry::GeometryCollection(GeometryCollection(vec![])))
@@ -457,6 +737,128 @@ impl Context {
         self
     }
 
+    /// Splits text into lines no wider than `width` when rendered with `typography`.
+    /// Newlines always start a new line (so blank lines stay blank), and words that
+    /// are wider than `width` on their own get a line to themselves.
+    fn wrap_text(&self, text: &str, width: f64, typography: &Typography) -> Vec<String> {
+        let width_of = |line: &String| -> f64 {
+            typography
+                .render(line, self.accuracy)
+                .ok()
+                .and_then(|geo| geo.bounding_rect())
+                .map(|bounds| bounds.width())
+                .unwrap_or(0.0)
+        };
+        let mut lines: Vec<String> = vec![];
+        for text_line in text.lines() {
+            let mut current = String::new();
+            for word in text_line.split_whitespace() {
+                let candidate = if current.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", current, word)
+                };
+                if !current.is_empty() && width_of(&candidate) > width {
+                    lines.push(current);
+                    current = word.to_string();
+                } else {
+                    current = candidate;
+                }
+            }
+            lines.push(current);
+        }
+        lines
+    }
+
+    /// Draws a block of text, word wrapped to fit the width of `box_`, one line
+    /// per typography line height going down the page. Words that are wider than
+    /// the box on their own just hang out the side, and any lines that would
+    /// start below the bottom of the box are dropped. Newlines force a break.
+    pub fn paragraph(&mut self, text: &str, box_: Rect<f64>, typography: &Typography) -> &mut Self {
+        let typography = &self.with_default_font(typography);
+        let lines = self.wrap_text(text, box_.width(), typography);
+        let x0 = match typography.align {
+            typography::TextAlignment::Left => box_.min().x,
+            typography::TextAlignment::Center => box_.center().x,
+            typography::TextAlignment::Right => box_.max().x,
+        };
+        let line_height = typography.line_height();
+        let mut baseline = box_.min().y + line_height;
+        for line in lines {
+            if baseline > box_.max().y {
+                break;
+            }
+            self.typography(&line, x0, baseline, typography);
+            baseline += line_height;
+        }
+        self
+    }
+
+    /// Like [`Context::paragraph`], but with no bottom: wraps the text at `width` and
+    /// keeps going down the page for as many lines as it takes. The first baseline is
+    /// at `y`, and the typography's alignment is relative to the `width` wide column
+    /// starting at `x`. Newlines force a break.
+    pub fn text_block(
+        &mut self,
+        text: &str,
+        x: f64,
+        y: f64,
+        width: f64,
+        typography: &Typography,
+    ) -> &mut Self {
+        let typography = &self.with_default_font(typography);
+        let x0 = match typography.align {
+            typography::TextAlignment::Left => x,
+            typography::TextAlignment::Center => x + width / 2.0,
+            typography::TextAlignment::Right => x + width,
+        };
+        let line_height = typography.line_height();
+        for (i, line) in self.wrap_text(text, width, typography).iter().enumerate() {
+            self.typography(line, x0, y + i as f64 * line_height, typography);
+        }
+        self
+    }
+
+    /// Sets text along `path`, like around the edge of a circle. Each glyph sits on the
+    /// path with its baseline along the local direction of the path, spaced by its
+    /// (kerned) advance. Always starts at the beginning of the path, whatever the
+    /// alignment says, and anything that would run off the end is dropped.
+    pub fn text_on_path(
+        &mut self,
+        text: &str,
+        path: &LineString<f64>,
+        typography: &Typography,
+    ) -> &mut Self {
+        let length = path.euclidean_length();
+        let glyphs = match self
+            .with_default_font(typography)
+            .layout(text, self.accuracy)
+        {
+            Ok(glyphs) if length > 0.0 => glyphs,
+            _ => return self,
+        };
+        let point_at = |distance: f64| path.line_interpolate_point(distance / length);
+        let mut distance = 0.0;
+        for (_c, glyph, advance) in glyphs {
+            if distance + advance > length {
+                break;
+            }
+            let (start, end) = match (point_at(distance), point_at(distance + advance)) {
+                (Some(start), Some(end)) => (start, end),
+                _ => break,
+            };
+            let angle = (end.y() - start.y()).atan2(end.x() - start.x());
+            let (sin, cos) = angle.sin_cos();
+            // Font y is up, so flip it before turning the glyph to follow the path.
+            let glyph = glyph.map_coords(|(x, y)| {
+                (start.x() + x * cos + y * sin, start.y() + x * sin - y * cos)
+            });
+            self.geometry(&glyph);
+            distance += advance;
+        }
+        self
+    }
+
     /// Glyph
     /// Draws a single glyph on the Context, at 0,0
     pub fn glyph(&mut self, glyph: char, close: bool) -> &mut Self {
@@ -547,6 +949,42 @@ impl Context {
         self
     }
 
+    /// Draws a single cubic bezier from p0 to p3, pulled toward p1 and p2. It's
+    /// flattened to `accuracy` and drawn as a line, never filled.
+    pub fn cubic_bezier(
+        &mut self,
+        p0: (f64, f64),
+        p1: (f64, f64),
+        p2: (f64, f64),
+        p3: (f64, f64),
+    ) -> &mut Self {
+        let mut bezier = BezPath::new();
+        bezier.move_to(p0);
+        bezier.curve_to(p1, p2, p3);
+        self.bezier_line(&bezier)
+    }
+
+    /// Same as [`Context::cubic_bezier`], but a quadratic with one control point.
+    pub fn quad_bezier(&mut self, p0: (f64, f64), p1: (f64, f64), p2: (f64, f64)) -> &mut Self {
+        let mut bezier = BezPath::new();
+        bezier.move_to(p0);
+        bezier.quad_to(p1, p2);
+        self.bezier_line(&bezier)
+    }
+
+    /// Flattens a single open bezier into a LineString operation.
+    fn bezier_line(&mut self, bezier: &BezPath) -> &mut Self {
+        let mut line: Vec<Coordinate<f64>> = vec![];
+        bezier.flatten(self.accuracy, |el| match el {
+            PathEl::MoveTo(pos) | PathEl::LineTo(pos) => line.push(coord! {x: pos.x, y: pos.y}),
+            _ => {}
+        });
+        if line.len() > 1 {
+            self.add_operation(Geometry::LineString(LineString::new(line)));
+        }
+        self
+    }
+
     /// Generates a spline from a set of points and renders as a
     /// multi line string. Doesn't do errors very well, just
     /// silently fails to draw.
@@ -580,6 +1018,52 @@ impl Context {
         }
     }
 
+    /// Draws a Catmull-Rom curve through every one of `points`, with `segments` line
+    /// segments between each pair. Unlike [`Context::spline`] there are no extra
+    /// control points to pass; the open ends just carry on straight. When `closed`,
+    /// the curve wraps back around to the first point (it's still just a line
+    /// though, not a fillable polygon). Silently draws nothing for fewer than two
+    /// points.
+    pub fn catmull_rom(&mut self, points: &[(f64, f64)], segments: u32, closed: bool) -> &mut Self {
+        let n = points.len();
+        if n < 2 {
+            return self;
+        }
+        let at = |i: isize| -> (f64, f64) {
+            let reflect = |a: (f64, f64), b: (f64, f64)| (2.0 * a.0 - b.0, 2.0 * a.1 - b.1);
+            if closed {
+                points[i.rem_euclid(n as isize) as usize]
+            } else if i < 0 {
+                reflect(points[0], points[1])
+            } else if i as usize >= n {
+                reflect(points[n - 1], points[n - 2])
+            } else {
+                points[i as usize]
+            }
+        };
+        let segments = segments.max(1);
+        let spans = if closed { n } else { n - 1 };
+        let mut line = vec![coord! {x: points[0].0, y: points[0].1}];
+        for span in 0..spans as isize {
+            let (p0, p1, p2, p3) = (at(span - 1), at(span), at(span + 1), at(span + 2));
+            let interpolate = |t: f64, a: f64, b: f64, c: f64, d: f64| {
+                0.5 * (2.0 * b
+                    + (c - a) * t
+                    + (2.0 * a - 5.0 * b + 4.0 * c - d) * t * t
+                    + (3.0 * b - a - 3.0 * c + d) * t * t * t)
+            };
+            for step in 1..=segments {
+                let t = f64::from(step) / f64::from(segments);
+                line.push(coord! {
+                    x: interpolate(t, p0.0, p1.0, p2.0, p3.0),
+                    y: interpolate(t, p0.1, p1.1, p2.1, p3.1),
+                });
+            }
+        }
+        self.add_operation(Geometry::LineString(LineString::new(line)));
+        self
+    }
+
     /// centerpoint arc
     /// Draw an arc around x0,y0 with the given radius, from deg0 to deg1. Arcs will always be
     /// coords oriented clockwise from "north" on an SVG. ie: 45 to 135 will be NE to SE.
@@ -606,6 +1090,33 @@ impl Context {
         self
     }
 
+    /// A rectangle with rounded corners. The radius is clamped to half the shorter
+    /// side, so you can't turn it inside out. Corners are quarter arcs, thinned out
+    /// to the context accuracy.
+    pub fn rounded_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, radius: f64) -> &mut Self {
+        let (x0, x1) = (x0.min(x1), x0.max(x1));
+        let (y0, y1) = (y0.min(y1), y0.max(y1));
+        let radius = radius.max(0.0).min((x1 - x0).min(y1 - y0) / 2.0);
+        // Compass degrees, and each arc gets walked backwards so we go around the
+        // same way rect() does: top left, top right, bottom right, bottom left.
+        let corners = [
+            (x0 + radius, y0 + radius, 180.0, 270.0),
+            (x1 - radius, y0 + radius, 90.0, 180.0),
+            (x1 - radius, y1 - radius, 0.0, 90.0),
+            (x0 + radius, y1 - radius, -90.0, 0.0),
+        ];
+        let mut exterior = LineString::<f64>::new(vec![]);
+        for (cx, cy, deg0, deg1) in corners {
+            let mut arc = shapes::arc_center(cx, cy, radius, deg0, deg1).simplify(&self.accuracy);
+            arc.0.reverse();
+            exterior.0.append(&mut arc.0);
+        }
+        exterior.0.dedup();
+        exterior.close();
+        self.add_operation(Geometry::Polygon(Polygon::new(exterior, vec![])));
+        self
+    }
+
     /// Draws a polygon
     pub fn poly(
         &mut self,
@@ -636,6 +1147,14 @@ impl Context {
         self
     }
 
+    /// Draws an ellipse centered on cx,cy with the given x and y radii, rotated
+    /// clockwise by `rotation` compass degrees. Segment count follows the context
+    /// accuracy, so it's as smooth as it needs to be and no smoother.
+    pub fn ellipse(&mut self, cx: f64, cy: f64, rx: f64, ry: f64, rotation: f64) -> &mut Self {
+        self.add_operation(shapes::ellipse(cx, cy, rx, ry, rotation, self.accuracy));
+        self
+    }
+
     /// Circumscribed regular polygon. The vertices of the polygon will be situated on a
     /// circle defined by the given radius. Polygon will be centered at x,y.
     pub fn regular_poly(
@@ -697,6 +1216,151 @@ impl Context {
         self
     }
 
+    /// Stamps everything drawn in `other` into this context, each operation keeping its
+    /// own colors, hatching and so on. With a transform, the operations (and their
+    /// masks) get moved by it and re-hatched, as if they'd been drawn here under that
+    /// transform. Operations that had [`Context::clip`] turned on still clip whatever
+    /// came before them, and that now includes everything already drawn in here.
+    pub fn append(&mut self, other: &Context, transform: Option<&Affine2<f64>>) -> &mut Self {
+        for op in &other.operations {
+            let mut op = op.clone();
+            if let Some(tx) = transform {
+                // The content already has the op's own transform baked in, so we only
+                // render with the new one, but remember the whole thing.
+                let own = op.transformation.take();
+                op.mask = op
+                    .mask
+                    .map(|mask| mask.map_coords(|xy| Operation::xform_coord(xy, tx)));
+                op.transformation = Some(*tx);
+                op = op.render();
+                op.transformation = Some(match own {
+                    Some(own) => tx * own,
+                    None => *tx,
+                });
+            }
+            self.operations.push(op);
+        }
+        self.bounds_cache.set(None);
+        self
+    }
+
+    /// Recolors everything already drawn: any stroke or fill that is `from` becomes
+    /// `to`, so you can try a new palette without re-running the whole script.
+    /// Colors match if they're the same string (ignoring case), or parse to the
+    /// same rgb, so "red" matches "#ff0000". Returns how many operations changed.
+    pub fn replace_color(&mut self, from: &str, to: &str) -> usize {
+        let from_rgb = Self::parse_color(from);
+        let matches = |color: &str| {
+            color.trim().eq_ignore_ascii_case(from.trim())
+                || (from_rgb.is_some() && Self::parse_color(color) == from_rgb)
+        };
+        let mut changed = 0;
+        for op in self.operations.iter_mut() {
+            let stroke = matches(&op.stroke_color);
+            let fill = matches(&op.fill_color);
+            if stroke {
+                op.stroke_color = to.to_string();
+            }
+            if fill {
+                op.fill_color = to.to_string();
+            }
+            if stroke || fill {
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// PREVIEW ONLY: colors the strokes of the last operation with a gradient
+    /// running from `from` to `to` along the drawing order, which makes it
+    /// really easy to see which way the pen is going to travel. The plotter
+    /// doesn't care; it just gets one pen. Colors must be hex (#rgb/#rrggbb) or
+    /// one of the basic named colors, otherwise the plain stroke color is used.
+    pub fn stroke_gradient(&mut self, from: &str, to: &str) -> &mut Self {
+        if let Some(op) = self.operations.last_mut() {
+            op.stroke_gradient = Some((from.to_string(), to.to_string()));
+        }
+        self
+    }
+
+    /// Parses a hex or basic named color into rgb bytes. Used for gradients.
+    fn parse_color(color: &str) -> Option<(u8, u8, u8)> {
+        let color = color.trim().to_lowercase();
+        if let Some(hex) = color.strip_prefix('#') {
+            let hex = match hex.len() {
+                3 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+                6 => hex.to_string(),
+                _ => return None,
+            };
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some((channel(0)?, channel(2)?, channel(4)?));
+        }
+        let named = match color.as_str() {
+            "black" => nannou::color::BLACK,
+            "white" => nannou::color::WHITE,
+            "red" => nannou::color::RED,
+            "green" => nannou::color::GREEN,
+            "blue" => nannou::color::BLUE,
+            "yellow" => nannou::color::YELLOW,
+            "cyan" => nannou::color::CYAN,
+            "magenta" => nannou::color::MAGENTA,
+            "orange" => nannou::color::ORANGE,
+            "purple" => nannou::color::PURPLE,
+            "pink" => nannou::color::PINK,
+            "brown" => nannou::color::BROWN,
+            "gray" | "grey" => nannou::color::GRAY,
+            _ => return None,
+        };
+        Some((named.red, named.green, named.blue))
+    }
+
+    /// Splits already arranged lines into individual segments, each colored by
+    /// how far along the total drawn length it sits.
+    fn gradient_paths(
+        lines: &MultiLineString<f64>,
+        from: (u8, u8, u8),
+        to: (u8, u8, u8),
+    ) -> Vec<svg::node::element::Path> {
+        let total: f64 = lines
+            .0
+            .iter()
+            .flat_map(|line| line.lines())
+            .map(|seg| seg.dx().hypot(seg.dy()))
+            .sum();
+        let lerp = |a: u8, b: u8, t: f64| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        let mut travelled = 0.0;
+        let mut paths = vec![];
+        for seg in lines.0.iter().flat_map(|line| line.lines()) {
+            let length = seg.dx().hypot(seg.dy());
+            let t = if total > 0.0 {
+                (travelled + length / 2.0) / total
+            } else {
+                0.0
+            };
+            travelled += length;
+            let data = MultiLineString::new(vec![LineString::from(vec![seg.start, seg.end])])
+                .to_path_data();
+            paths.push(svg::node::element::Path::new().set("d", data).set(
+                "stroke",
+                format!(
+                    "#{:02x}{:02x}{:02x}",
+                    lerp(from.0, to.0, t),
+                    lerp(from.1, to.1, t),
+                    lerp(from.2, to.2, t)
+                ),
+            ));
+        }
+        paths
+    }
+
+    /// Marks subsequent operations as guides: construction lines, crop marks and
+    /// the like. They show up in the SVG as a dashed gray layer, but they're left
+    /// out of [`Context::to_layers`], so they never make it to the plotter.
+    pub fn guide(&mut self, enabled: bool) -> &mut Self {
+        self.guide = enabled;
+        self
+    }
+
     /// Sets the stroke color
     pub fn stroke(&mut self, color: &str) -> &mut Self {
         self.stroke_color = color.to_string();
@@ -711,12 +1375,32 @@ impl Context {
 
     /// Sets the hatch state, either None for no hatching or
     /// Some(angle) to set a hatching angle. Will use the current
-    /// pen width as the spacing between hatch lines.
+    /// pen width as the spacing between hatch lines, unless you've
+    /// set a [`Context::hatch_scale`].
     pub fn hatch(&mut self, angle: f64) -> &mut Self {
         self.hatch_angle = angle;
         self
     }
 
+    /// Spacing (in mm) between hatch lines for the following operations, so a fine
+    /// pen can still get a loose fill. None goes back to using the pen width.
+    pub fn hatch_scale(&mut self, scale: Option<f64>) -> &mut Self {
+        self.hatch_scale = scale;
+        self
+    }
+
+    /// Stacking hatched layers at the same angle gives you moiré, so this bumps
+    /// the hatch angle by `step_degrees` every time a new fill color or layer
+    /// starts. A step of 0 turns it back off (leaving the angle wherever it got to).
+    pub fn auto_rotate_hatch(&mut self, step_degrees: f64) -> &mut Self {
+        self.auto_hatch_step = if step_degrees == 0.0 {
+            None
+        } else {
+            Some(step_degrees)
+        };
+        self
+    }
+
     /// Sets the pen width
     pub fn pen(&mut self, width: f64) -> &mut Self {
         self.pen_width = width;
@@ -738,6 +1422,55 @@ impl Context {
         self
     }
 
+    /// Sets the line join, using the SVG names: "round", "miter" or "bevel".
+    /// Goes into the SVG, and also shapes the corners when outline is on.
+    pub fn line_join(&mut self, join: &str) -> &mut Self {
+        self.line_join = join.to_string();
+        self
+    }
+
+    /// Sets the line cap, using the SVG names: "round", "butt" or "square".
+    /// Goes into the SVG, and also shapes the line ends when outline is on.
+    pub fn line_cap(&mut self, cap: &str) -> &mut Self {
+        self.line_cap = cap.to_string();
+        self
+    }
+
+    /// Dashed lines! The pattern is on/off lengths in mm, like an SVG dasharray,
+    /// so Some(vec![2.0, 1.0]) draws 2mm, lifts for 1mm, and so on. None goes back
+    /// to solid lines. Only the strokes get dashed unless you turn on dash_fills.
+    pub fn dash(&mut self, pattern: Option<Vec<f64>>) -> &mut Self {
+        self.dash = pattern;
+        self
+    }
+
+    /// PREVIEW ONLY: sets an SVG stroke-dasharray (like "4,2") on the outlines, so
+    /// they look dashed on screen. The plotter still draws them solid; use
+    /// [`crate::context::Context::dash`] if you want real dashes. None turns it off.
+    pub fn stroke_dasharray(&mut self, dasharray: Option<&str>) -> &mut Self {
+        self.stroke_dasharray = dasharray.map(|d| d.to_string());
+        self
+    }
+
+    /// Runs the outlines of following operations through a [`LineFilter`] (like
+    /// [`line_filter::SketchyLineFilter`]) after they're rendered. None turns it off.
+    pub fn stroke_filter(&mut self, filter: Option<Arc<Box<dyn LineFilter>>>) -> &mut Self {
+        self.stroke_filter = filter;
+        self
+    }
+
+    /// Same as [`Context::stroke_filter`], but for the hatch fills.
+    pub fn hatch_filter(&mut self, filter: Option<Arc<Box<dyn LineFilter>>>) -> &mut Self {
+        self.hatch_filter = filter;
+        self
+    }
+
+    /// Dash the hatch fills too, with the same pattern as the strokes.
+    pub fn dash_fills(&mut self, enabled: bool) -> &mut Self {
+        self.dash_fills = enabled;
+        self
+    }
+
     /// Flatten will take a context and "flatten" together all polygons
     /// of a given color and "depth". What that means is that we watch for
     /// changes to fill/color/etc, and set those as boundaries. Then every
@@ -800,6 +1533,168 @@ impl Context {
         new_ctx
     }
 
+    /// Begins a named layer. Every operation drawn until the matching
+    /// [`crate::context::Context::end_layer`] is buffered, and then flattened
+    /// (see [`crate::context::Context::flatten`]) when the layer is closed.
+    /// Overlapping shapes inside the layer get merged, but anything outside
+    /// of it is left alone. If a layer is already open, it is ended first. The
+    /// layer's name ends up on its layers (see [`OPLayer::layer`]), and
+    /// [`Context::to_svg`] wraps it in an Inkscape layer labelled with the name.
+    pub fn begin_layer(&mut self, name: &str) -> &mut Self {
+        if self.layer.is_some() {
+            // We know there's an open layer, so this can't fail.
+            let _ = self.end_layer();
+        }
+        self.layer = Some((name.to_string(), self.operations.len()));
+        self.hatch_bump = true;
+        self
+    }
+
+    /// Ends the currently open layer, flattening all of the operations drawn since
+    /// [`crate::context::Context::begin_layer`] was called. Returns an error if there
+    /// is no open layer.
+    pub fn end_layer(&mut self) -> Result<&mut Self, ContextError> {
+        let (name, start) = self.layer.take().ok_or(ContextError::NoOpenLayer)?;
+        let mut layer_ctx = Context::new();
+        layer_ctx.operations = self.operations.split_off(start);
+        self.bounds_cache.set(None);
+        let flat = layer_ctx.flatten();
+        self.operations.extend(
+            flat.operations
+                .into_iter()
+                .filter(|op| {
+                    let (strokes, fills) = &op.rendered;
+                    !strokes.0.is_empty() || !fills.0.is_empty()
+                })
+                .map(|mut op| {
+                    op.layer = Some(name.clone());
+                    op
+                }),
+        );
+        Ok(self)
+    }
+
+    /// Rounds off the convex corners of the last thing drawn (if it was a polygon)
+    /// with arcs of `radius`, at the context accuracy. The radius is in output
+    /// units, since the shape's already been through the transformation by now.
+    /// Corners too tight for the radius are left alone.
+    pub fn fillet(&mut self, radius: f64) -> &mut Self {
+        if let Some(op) = self.operations.last_mut() {
+            let filleted = match &op.content {
+                Geometry::Polygon(poly) => Geometry::Polygon(poly.fillet(radius, op.accuracy)),
+                Geometry::MultiPolygon(polys) => {
+                    Geometry::MultiPolygon(polys.fillet(radius, op.accuracy))
+                }
+                _ => return self,
+            };
+            op.content = filleted;
+            op.rendered = op.render_to_lines();
+            self.bounds_cache.set(None);
+        }
+        self
+    }
+
+    /// Stitches loose stroke segments whose endpoints are within `tolerance` of
+    /// each other into longer continuous lines. Works across every operation in
+    /// the open layer (or the whole drawing if there isn't one), but only joins up
+    /// strokes with the same color and pen width: each set of stitched strokes
+    /// ends up on the first operation that drew with that pen, while fills stay
+    /// where they were. The stitching sticks, even if the operations get
+    /// re-rendered later on. Guides are left alone. Great for cleaning up imported
+    /// SVG/DXF garbage.
+    pub fn connect_nearest(&mut self, tolerance: f64) -> &mut Self {
+        let start = match &self.layer {
+            Some((_name, start)) => *start,
+            None => 0,
+        };
+        let optimizer = Optimizer::new(tolerance, OptimizationStrategy::Greedy);
+        // Each pen is (stroke color, pen width, first operation, strokes).
+        let mut pens: Vec<(String, f64, usize, MultiLineString<f64>)> = vec![];
+        for (i, op) in self.operations.iter_mut().enumerate().skip(start) {
+            if op.guide {
+                continue;
+            }
+            let mut strokes = std::mem::replace(&mut op.rendered.0, MultiLineString::new(vec![]));
+            op.stitched = Some(MultiLineString::new(vec![]));
+            let pen = pens
+                .iter_mut()
+                .find(|(color, width, _, _)| *color == op.stroke_color && *width == op.pen_width);
+            match pen {
+                Some((_, _, _, lines)) => lines.0.append(&mut strokes.0),
+                None => pens.push((op.stroke_color.clone(), op.pen_width, i, strokes)),
+            }
+        }
+        for (_color, _width, first, strokes) in pens {
+            let connected = optimizer.connect(&strokes);
+            self.operations[first].rendered.0 = connected.clone();
+            self.operations[first].stitched = Some(connected);
+        }
+        self
+    }
+
+    /// Rough estimate of how long this is going to take to plot, in seconds, at
+    /// `speed_mm_s`. Counts drawn length plus the pen-up travel between lines in
+    /// the order they were drawn, so it's pessimistic vs. the optimized output.
+    /// Guides never get plotted, so they don't count.
+    pub fn estimate_plot_time(&self, speed_mm_s: f64) -> f64 {
+        let mut distance = 0.0;
+        let mut last: Option<Coordinate<f64>> = None;
+        for op in self.operations.iter().filter(|op| !op.guide) {
+            for line in op.rendered.0.iter().chain(op.rendered.1.iter()) {
+                distance += line.euclidean_length();
+                if let (Some(last), Some(first)) = (last, line.0.first()) {
+                    distance += last.euclidean_distance(first);
+                }
+                last = line.0.last().copied().or(last);
+            }
+        }
+        distance / speed_mm_s
+    }
+
+    /// For when you've only got a 20 minute slot at the art show. Keeps opening
+    /// up the hatch spacing on every filled operation until the estimated plot
+    /// time (see [`Context::estimate_plot_time`]) fits in `seconds`. Strokes are
+    /// never touched, so if they alone blow the budget you'll end up with very
+    /// sparse (or no) fills, and it still won't fit. Patterns that don't care
+    /// about the scale (bricks, voronoi, etc.) and guides are left as they are.
+    pub fn target_plot_time(&mut self, seconds: f64, speed_mm_s: f64) -> &mut Self {
+        const GROWTH: f64 = 1.5;
+        const MAX_ROUNDS: usize = 32;
+        let mut growing: Vec<bool> = self
+            .operations
+            .iter()
+            .map(|op| !op.guide && !op.rendered.1 .0.is_empty())
+            .collect();
+        for _round in 0..MAX_ROUNDS {
+            if self.estimate_plot_time(speed_mm_s) <= seconds {
+                break;
+            }
+            let mut changed = false;
+            for (op, growing) in self.operations.iter_mut().zip(growing.iter_mut()) {
+                if !*growing {
+                    continue;
+                }
+                let scale = op.hatch_scale;
+                op.hatch_scale = Some(scale.unwrap_or(op.pen_width) * GROWTH);
+                // Content is already transformed and masked, so just redo the fills.
+                // The strokes don't depend on the hatch scale, so they stay put.
+                let (_strokes, fills) = op.render_to_lines();
+                if fills == op.rendered.1 {
+                    op.hatch_scale = scale;
+                    *growing = false;
+                    continue;
+                }
+                *growing = !fills.0.is_empty();
+                op.rendered.1 = fills;
+                changed = true;
+            }
+            if !changed {
+                break;
+            }
+        }
+        self
+    }
+
     pub fn to_geo(&self) -> Result<Geometry<f64>, Box<dyn Error>> {
         let mut all: Vec<Geometry<f64>> = vec![];
         for operation in &self.operations {
@@ -810,36 +1705,54 @@ impl Context {
         ))
     }
 
+    /// Tells the context which physical pen draws each color. Layers (see
+    /// [`Context::to_layers`]) get the tool slots and the pen's real width, and
+    /// [`Context::to_gcode`] groups the drawing by tool with a tool change between
+    /// each. Colors that aren't in here are drawn just like they would be without it.
+    pub fn pen_registry(&mut self, pens: PenRegistry) -> &mut Self {
+        self.pens = pens;
+        self
+    }
+
     /// Generate layers of perimeters and fills
     pub fn to_layers(&self) -> Vec<OPLayer> {
+        // Guides never get plotted, so they don't get to clip anything either.
+        let operations: Vec<&Operation> = self.operations.iter().filter(|op| !op.guide).collect();
         let mut oplayers: Vec<OPLayer> = vec![];
-        for op in &self.operations {
+        for op in &operations {
             let (stroke, fill) = op.rendered.clone();
+            let stroke_pen = self.pens.get(&op.stroke_color);
+            let fill_pen = self.pens.get(&op.fill_color);
             oplayers.push(OPLayer {
                 stroke_lines: stroke,
                 fill_lines: fill,
                 stroke: op.stroke_color.clone(),
                 fill: op.fill_color.clone(),
-                stroke_width: op.pen_width,
+                stroke_width: stroke_pen.map(|pen| pen.width).unwrap_or(op.pen_width),
                 stroke_linejoin: op.line_join.clone(),
                 stroke_linecap: op.line_cap.clone(),
+                stroke_gradient: op.stroke_gradient.clone(),
+                stroke_dasharray: op.stroke_dasharray.clone(),
+                stroke_tool: stroke_pen.map(|pen| pen.index),
+                fill_tool: fill_pen.map(|pen| pen.index),
+                layer: op.layer.clone(),
             });
         }
-        assert_eq!(&self.operations.len(), &oplayers.len());
+        assert_eq!(&operations.len(), &oplayers.len());
 
         // Iterate the layers, and clip their predecessors where appropriate.
         // NOTE: CLIPPING IS S_L_O_W AF.
-        if self.operations.len() > 1 {
-            for i in 0..(self.operations.len() - 1) {
-                for j in (i + 1)..self.operations.len() {
-                    if self.operations[j].clip_previous {
+        if operations.len() > 1 {
+            for i in 0..(operations.len() - 1) {
+                for j in (i + 1)..operations.len() {
+                    if operations[j].clip_previous {
                         oplayers[i].stroke_lines =
                             Geometry::MultiLineString(oplayers[i].stroke_lines.clone())
-                                .clipwith(&self.operations[j].content)
+                                .clipwith(&operations[j].content)
                                 .unwrap_or(MultiLineString::<f64>::new(vec![]));
                         oplayers[i].fill_lines =
                             Geometry::MultiLineString(oplayers[i].fill_lines.clone())
-                                .clipwith(&self.operations[j].content)
+                                .clipwith(&operations[j].content)
                                 .unwrap_or(oplayers[i].fill_lines.clone());
                     }
                 }
@@ -848,63 +1761,386 @@ impl Context {
         oplayers
     }
 
-    /// Take this giant complex thing and generate and SVG Document, or an error. Whatever.
-    pub fn to_svg(&self, arrangement: &Arrangement<f64>) -> Result<Document, ContextError> {
-        let oplayers = self.to_layers();
-
-        let mut svg =
-            arrangement
-                .create_svg_document()
-                .or(Err(ContextError::SvgGenerationError(
-                    "Failed to create raw svg doc".into(),
-                )
-                .into()))?;
+    /// All the pen-up moves the plotter is going to make, one LineString per hop,
+    /// using the same optimized ordering as [`Context::to_svg`]. Each stroke or
+    /// fill set is its own pen pass, so there's no travel counted between them.
+    /// Handy for eyeballing (or plotting in a different color) how much time is
+    /// spent in the air.
+    pub fn travel_moves(&self) -> MultiLineString<f64> {
+        let mut moves = MultiLineString::new(vec![]);
+        for oplayer in self.to_layers() {
+            let (strokes, fills) = oplayer.optimized_lines();
+            for lines in [strokes, fills] {
+                for pair in lines.0.windows(2) {
+                    if let (Some(from), Some(to)) = (pair[0].0.last(), pair[1].0.first()) {
+                        moves.0.push(LineString::new(vec![*from, *to]));
+                    }
+                }
+            }
+        }
+        moves
+    }
 
-        let mut id = 0;
-        for oplayer in oplayers {
-            if !oplayer.stroke_lines.0.is_empty() {
-                let optimizer = crate::optimizer::Optimizer::new(
-                    oplayer.stroke_width * 2.,
-                    crate::optimizer::OptimizationStrategy::Greedy,
-                );
-                let slines_opt = optimizer.optimize(&optimizer.merge(&oplayer.stroke_lines));
-                let slines = slines_opt.to_path(&arrangement);
-                svg = svg.add(
-                    slines
-                        .set("id", format!("outline-{}", id))
-                        .set("fill", "none")
-                        .set("stroke", oplayer.stroke.clone())
-                        .set("stroke-width", oplayer.stroke_width)
-                        .set("stroke-linejoin", oplayer.stroke_linejoin.clone())
-                        .set("stroke-linecap", oplayer.stroke_linecap.clone()),
-                );
+    /// How much drawing (and pen-up travel) each color is going to take, laid out
+    /// on the page with `arrangement` and optimized the same way as
+    /// [`Context::to_svg`], so you know what you're in for before you start. Strokes
+    /// count toward the stroke color and fills toward the fill color.
+    pub fn plot_report(&self, arrangement: &Arrangement<f64>) -> PlotReport {
+        let mut layers: Vec<LayerStats> = vec![];
+        for oplayer in self.to_layers() {
+            let optimizer = Optimizer::new(oplayer.stroke_width, OptimizationStrategy::Greedy);
+            let (strokes, fills) = oplayer.optimized_lines();
+            for (color, lines) in [(&oplayer.stroke, strokes), (&oplayer.fill, fills)] {
+                if lines.0.is_empty() {
+                    continue;
+                }
+                let lines = lines.arrange(arrangement).unwrap_or(lines);
+                let index = match layers.iter().position(|layer| &layer.color == color) {
+                    Some(index) => index,
+                    None => {
+                        layers.push(LayerStats {
+                            color: color.clone(),
+                            draw_length: 0.0,
+                            travel_length: 0.0,
+                            line_count: 0,
+                        });
+                        layers.len() - 1
+                    }
+                };
+                let layer = &mut layers[index];
+                layer.draw_length += lines.euclidean_length();
+                layer.travel_length += optimizer.travel_distance(&lines);
+                layer.line_count += lines.0.len();
             }
-            if !oplayer.fill_lines.0.is_empty() {
-                let optimizer = crate::optimizer::Optimizer::new(
-                    oplayer.stroke_width,
-                    crate::optimizer::OptimizationStrategy::Greedy,
-                );
-                let fill_opt = optimizer.optimize(&oplayer.fill_lines);
-                let flines = fill_opt.to_path(&arrangement);
-                svg = svg.add(
-                    flines
-                        .set("id", format!("fill-{}", id))
-                        .set("fill", "none")
-                        .set("stroke", oplayer.fill.clone())
-                        .set("stroke-width", oplayer.stroke_width)
-                        .set("stroke-linejoin", oplayer.stroke_linejoin.clone())
-                        .set("stroke-linecap", oplayer.stroke_linecap.clone()),
-                );
-                id = id + 1;
+        }
+        PlotReport { layers }
+    }
+
+    /// Straight to GCode. Arranges everything onto the page, optimizes each layer
+    /// exactly the same way [`Context::to_svg`] does (so the preview matches the plot),
+    /// and posts all the layers, strokes then fills, as one program in draw order.
+    /// With a [`Context::pen_registry`], everything for each tool is drawn together
+    /// (lowest tool first, then any colors without a pen) with a tool change between.
+    pub fn to_gcode(
+        &self,
+        arrangement: &Arrangement<f64>,
+        post_template: &Tera,
+    ) -> Result<Vec<String>, ContextError> {
+        // Each group is (tool, the color we call it, lines).
+        let mut groups: Vec<(Option<usize>, String, MultiLineString<f64>)> = vec![];
+        for oplayer in self.to_layers() {
+            let (strokes, fills) = oplayer.optimized_lines();
+            for (tool, color, layer_lines) in [
+                (oplayer.stroke_tool, &oplayer.stroke, strokes),
+                (oplayer.fill_tool, &oplayer.fill, fills),
+            ] {
+                if layer_lines.0.is_empty() {
+                    continue;
+                }
+                let mut arranged = layer_lines
+                    .arrange(arrangement)
+                    .map_err(|err| ContextError::GCodeGenerationError(format!("{:?}", err)))?;
+                // Without a registry, it's all one big group in draw order.
+                let group = groups.iter_mut().find(|(group_tool, group_color, _)| {
+                    self.pens.is_empty()
+                        || match tool {
+                            Some(_) => *group_tool == tool,
+                            None => group_tool.is_none() && group_color == color,
+                        }
+                });
+                match group {
+                    Some((_, _, lines)) => lines.0.append(&mut arranged.0),
+                    None => groups.push((tool, color.clone(), arranged)),
+                }
             }
         }
+        let source = if self.pens.is_empty() {
+            PostGeometrySource::MultiLineString(
+                groups
+                    .pop()
+                    .map(|(_, _, lines)| lines)
+                    .unwrap_or(MultiLineString::new(vec![])),
+            )
+        } else {
+            groups.sort_by_key(|(tool, _, _)| tool.unwrap_or(usize::MAX));
+            PostGeometrySource::Layers(
+                groups
+                    .into_iter()
+                    .map(|(_, color, lines)| (color, lines))
+                    .collect(),
+            )
+        };
+        let pens: HashMap<String, PenConfig> = self
+            .pens
+            .iter()
+            .map(|(color, pen)| {
+                (
+                    color.clone(),
+                    PenConfig {
+                        tool: Some(pen.index),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+        post_with_pens(&source, post_template, &pens, &PostOptions::default())
+            .map_err(|err| ContextError::GCodeGenerationError(err.to_string()))
+    }
+
+    /// Take this giant complex thing and generate and SVG Document, or an error. Whatever.
+    pub fn to_svg(&self, arrangement: &Arrangement<f64>) -> Result<Document, ContextError> {
+        let svg = Context::svg_document(arrangement)?;
+        let mut svg = Context::add_svg_nodes(svg, self.svg_nodes(arrangement));
+        if let Some(guides) = self.svg_guides(arrangement) {
+            svg = svg.add(guides);
+        }
         Ok(svg)
     }
-}
 
-#[cfg(test)]
+    /// Same as to_svg, but every pen color gets its own Inkscape layer (in the order
+    /// the colors first show up), which is what AxiDraw/vpype/Inkscape want for
+    /// plotting one pen at a time. Guides get a layer of their own at the end.
+    pub fn to_svg_layered(&self, arrangement: &Arrangement<f64>) -> Result<Document, ContextError> {
+        let mut layers: Vec<(String, svg::node::element::Group)> = vec![];
+        for (color, _layer, node) in self.svg_nodes(arrangement) {
+            match layers
+                .iter_mut()
+                .find(|(layer_color, _group)| *layer_color == color)
+            {
+                Some((_color, group)) => svg::Node::append(group, node),
+                None => {
+                    let number = layers.len() + 1;
+                    let group = Context::inkscape_layer(
+                        &format!("layer-{}", number),
+                        &format!("{}-{}", number, color),
+                    )
+                    .add(node);
+                    layers.push((color, group));
+                }
+            }
+        }
+        let mut svg = Context::svg_document(arrangement)?.set(
+            "xmlns:inkscape",
+            "http://www.inkscape.org/namespaces/inkscape",
+        );
+        for (_color, group) in layers {
+            svg = svg.add(group);
+        }
+        if let Some(guides) = self.svg_guides(arrangement) {
+            svg = svg.add(Context::inkscape_layer("layer-guides", "guides").add(guides));
+        }
+        Ok(svg)
+    }
+
+    /// Cuts the drawing up into one document per sheet for an [`Arrangement::tile`]
+    /// arrangement, with registration marks in the corners of every sheet. Each
+    /// layer's lines are clipped to their sheet (plus the overlap), and guides are left
+    /// out. Any other arrangement just gets you the one page from to_svg.
+    pub fn to_svg_pages(
+        &self,
+        arrangement: &Arrangement<f64>,
+    ) -> Result<Vec<Document>, ContextError> {
+        let page = match arrangement {
+            Arrangement::Tile(page, _overlap) => *page,
+            _ => return Ok(vec![self.to_svg(arrangement)?]),
+        };
+        let bounds = self.bounds().or(Err(ContextError::EmptyContext))?;
+        let oplayers = self.to_layers();
+        let clip = |lines: &MultiLineString<f64>, tile: &Polygon<f64>| {
+            if lines.0.is_empty() {
+                return Ok(lines.clone());
+            }
+            Geometry::MultiLineString(lines.clone())
+                .maskwith(&Geometry::Polygon(tile.clone()))
+                .map_err(|err| ContextError::SvgGenerationError(err.to_string()))
+        };
+        let mut pages = vec![];
+        for tile in arrangement.tiles(&bounds) {
+            let tile_poly = tile.to_polygon();
+            let mut tile_layers = vec![];
+            for oplayer in &oplayers {
+                let mut tile_layer = oplayer.clone();
+                tile_layer.stroke_lines = clip(&oplayer.stroke_lines, &tile_poly)?;
+                tile_layer.fill_lines = clip(&oplayer.fill_lines, &tile_poly)?;
+                tile_layers.push(tile_layer);
+            }
+            let delta = page.min() - tile.min();
+            let tile_arrangement = Arrangement::Transform(
+                page,
+                Affine2::from_matrix_unchecked(Matrix3::new(
+                    1.0, 0.0, delta.x, 0.0, 1.0, delta.y, 0.0, 0.0, 1.0,
+                )),
+            );
+            let svg = Context::add_svg_nodes(
+                Context::svg_document(&tile_arrangement)?,
+                Context::svg_layer_nodes(tile_layers, &tile_arrangement),
+            );
+            pages.push(tile_arrangement.with_registration_marks(svg, REGISTRATION_MARK_SIZE));
+        }
+        Ok(pages)
+    }
+
+    fn inkscape_layer(id: &str, label: &str) -> svg::node::element::Group {
+        svg::node::element::Group::new()
+            .set("id", id)
+            .set("inkscape:groupmode", "layer")
+            .set("inkscape:label", label)
+    }
+
+    fn svg_document(arrangement: &Arrangement<f64>) -> Result<Document, ContextError> {
+        arrangement
+            .create_svg_document()
+            .or(Err(ContextError::SvgGenerationError(
+                "Failed to create raw svg doc".into(),
+            )))
+    }
+
+    /// Adds the nodes to the document in order. Runs of nodes from the same named
+    /// layer (see [`Context::begin_layer`]) get wrapped in an Inkscape layer
+    /// labelled with the layer's name.
+    fn add_svg_nodes(mut svg: Document, nodes: Vec<SvgNode>) -> Document {
+        let mut count = 0;
+        let mut open: Option<(String, svg::node::element::Group)> = None;
+        for (_color, layer, node) in nodes {
+            if let (Some(name), Some((open_name, group))) = (&layer, &mut open) {
+                if name == open_name {
+                    svg::Node::append(group, node);
+                    continue;
+                }
+            }
+            if let Some((_name, group)) = open.take() {
+                svg = svg.add(group);
+            }
+            match layer {
+                Some(name) => {
+                    count += 1;
+                    let group = Context::inkscape_layer(&format!("layer-{}", count), &name);
+                    open = Some((name, group.add(node)));
+                }
+                None => svg = svg.add(node),
+            }
+        }
+        if let Some((_name, group)) = open {
+            svg = svg.add(group);
+        }
+        if count > 0 {
+            svg = svg.set(
+                "xmlns:inkscape",
+                "http://www.inkscape.org/namespaces/inkscape",
+            );
+        }
+        svg
+    }
+
+    /// The outline and fill paths for every operation, in drawing order, along with
+    /// the pen color each one needs and the named layer it was drawn in.
+    fn svg_nodes(&self, arrangement: &Arrangement<f64>) -> Vec<SvgNode> {
+        Context::svg_layer_nodes(self.to_layers(), arrangement)
+    }
+
+    fn svg_layer_nodes(oplayers: Vec<OPLayer>, arrangement: &Arrangement<f64>) -> Vec<SvgNode> {
+        let mut nodes: Vec<SvgNode> = vec![];
+        let mut id = 0;
+        for oplayer in oplayers {
+            let (slines_opt, fill_opt) = oplayer.optimized_lines();
+            if !oplayer.stroke_lines.0.is_empty() {
+                let gradient = oplayer.stroke_gradient.as_ref().and_then(|(from, to)| {
+                    Some((Context::parse_color(from)?, Context::parse_color(to)?))
+                });
+                let arranged = slines_opt.arrange(&arrangement);
+                if let (Some((from, to)), Ok(arranged)) = (gradient, arranged) {
+                    let mut group = svg::node::element::Group::new()
+                        .set("id", format!("outline-{}", id))
+                        .set("fill", "none")
+                        .set("stroke-width", oplayer.stroke_width)
+                        .set("stroke-linejoin", oplayer.stroke_linejoin.clone())
+                        .set("stroke-linecap", oplayer.stroke_linecap.clone());
+                    if let Some(dasharray) = &oplayer.stroke_dasharray {
+                        group = group.set("stroke-dasharray", dasharray.clone());
+                    }
+                    for path in Context::gradient_paths(&arranged, from, to) {
+                        group = group.add(path);
+                    }
+                    nodes.push((
+                        oplayer.stroke.clone(),
+                        oplayer.layer.clone(),
+                        Box::new(group),
+                    ));
+                } else {
+                    let mut slines = slines_opt
+                        .to_path(&arrangement)
+                        .set("id", format!("outline-{}", id))
+                        .set("fill", "none")
+                        .set("stroke", oplayer.stroke.clone())
+                        .set("stroke-width", oplayer.stroke_width)
+                        .set("stroke-linejoin", oplayer.stroke_linejoin.clone())
+                        .set("stroke-linecap", oplayer.stroke_linecap.clone());
+                    if let Some(dasharray) = &oplayer.stroke_dasharray {
+                        slines = slines.set("stroke-dasharray", dasharray.clone());
+                    }
+                    nodes.push((
+                        oplayer.stroke.clone(),
+                        oplayer.layer.clone(),
+                        Box::new(slines),
+                    ));
+                }
+            }
+            if !oplayer.fill_lines.0.is_empty() {
+                let flines = fill_opt.to_path(&arrangement);
+                nodes.push((
+                    oplayer.fill.clone(),
+                    oplayer.layer.clone(),
+                    Box::new(
+                        flines
+                            .set("id", format!("fill-{}", id))
+                            .set("fill", "none")
+                            .set("stroke", oplayer.fill.clone())
+                            .set("stroke-width", oplayer.stroke_width)
+                            .set("stroke-linejoin", oplayer.stroke_linejoin.clone())
+                            .set("stroke-linecap", oplayer.stroke_linecap.clone()),
+                    ),
+                ));
+                id = id + 1;
+            }
+        }
+        nodes
+    }
+
+    /// All the guide geometry in one group of dashed grey paths (one per guide, at
+    /// its own pen width), if there is any.
+    fn svg_guides(&self, arrangement: &Arrangement<f64>) -> Option<svg::node::element::Group> {
+        let mut group = svg::node::element::Group::new()
+            .set("id", "guides")
+            .set("fill", "none")
+            .set("stroke", "#999999")
+            .set("stroke-dasharray", "2,1");
+        let mut empty = true;
+        for op in self.operations.iter().filter(|op| op.guide) {
+            let mut guide_lines = op.rendered.0.clone();
+            guide_lines.0.append(&mut op.rendered.1 .0.clone());
+            if guide_lines.0.is_empty() {
+                continue;
+            }
+            group = group.add(
+                guide_lines
+                    .to_path(&arrangement)
+                    .set("stroke-width", op.pen_width),
+            );
+            empty = false;
+        }
+        if empty {
+            None
+        } else {
+            Some(group)
+        }
+    }
+}
+
+#[cfg(test)]
 mod test {
     use super::*;
+    use geo::centroid::Centroid;
+    use geo::coords_iter::CoordsIter;
     use geo_types::{Rect, Triangle};
 
     #[test]
@@ -1064,6 +2300,974 @@ mod test {
                    ));
     }
 
+    #[test]
+    fn test_layer_flatten() {
+        let mut context = Context::new();
+        context
+            .stroke("red")
+            .pen(0.5)
+            .pattern(Hatches::none())
+            .begin_layer("merged")
+            .rect(10.0, 10.0, 30.0, 30.0)
+            .rect(20.0, 20.0, 40.0, 40.0)
+            .begin_layer("separate")
+            .rect(25.0, 25.0, 45.0, 45.0)
+            .end_layer()
+            .unwrap();
+        let layers = context.to_layers();
+        assert_eq!(layers.len(), 2);
+        // The overlapping pair became a single 8 sided outline...
+        assert_eq!(layers[0].stroke_lines.0.len(), 1);
+        assert_eq!(layers[0].stroke_lines.0[0].0.len(), 9);
+        // ...but the rect in the second layer is untouched.
+        assert_eq!(layers[1].stroke_lines.0.len(), 1);
+        assert_eq!(layers[1].stroke_lines.0[0].0.len(), 5);
+        assert_eq!(layers[0].layer(), Some("merged".to_string()));
+        assert_eq!(layers[1].layer(), Some("separate".to_string()));
+        assert!(context.end_layer().is_err());
+        let svg = context
+            .to_svg(&Arrangement::unit(&Context::viewbox(0.0, 0.0, 50.0, 50.0)))
+            .unwrap()
+            .to_string();
+        assert!(svg.contains("xmlns:inkscape"));
+        assert!(svg.contains("inkscape:label=\"merged\""));
+        assert!(svg.contains("inkscape:label=\"separate\""));
+    }
+
+    #[test]
+    fn test_dash() {
+        let mut context = Context::new();
+        context
+            .stroke("black")
+            .pen(0.5)
+            .pattern(Hatches::none())
+            .dash(Some(vec![2.0, 2.0]))
+            .line(0.0, 0.0, 10.0, 0.0)
+            .dash(None)
+            .line(0.0, 5.0, 10.0, 5.0);
+        let layers = context.to_layers();
+        let dashed = &layers[0].stroke_lines;
+        // 0-2, 4-6 and 8-10
+        assert_eq!(dashed.0.len(), 3);
+        assert_eq!(
+            dashed.0[2].0,
+            vec![coord! {x: 8.0, y: 0.0}, coord! {x: 10.0, y: 0.0}]
+        );
+        // And the solid one
+        let solid = &layers[1].stroke_lines;
+        assert_eq!(solid.0.len(), 1);
+        assert_eq!(
+            solid.0[0].0,
+            vec![coord! {x: 0.0, y: 5.0}, coord! {x: 10.0, y: 5.0}]
+        );
+    }
+
+    #[test]
+    fn test_fillet() {
+        let mut context = Context::new();
+        context
+            .pattern(Hatches::none())
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .fillet(2.0);
+        let layers = context.to_layers();
+        assert!(layers[0].stroke_lines.0[0].0.len() > 5);
+        let bounds = context.bounds().unwrap();
+        assert!((bounds.width() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_line_filters() {
+        let sketchy: Arc<Box<dyn LineFilter>> =
+            Arc::new(Box::new(line_filter::SketchyLineFilter::new(0.3, 1.0, 1)));
+        let mut context = Context::new();
+        context
+            .pattern(Hatches::line())
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .push()
+            .stroke_filter(Some(sketchy.clone()))
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .hatch_filter(Some(sketchy))
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .pop()
+            .unwrap()
+            .rect(0.0, 0.0, 10.0, 10.0);
+        let vertices = |lines: &MultiLineString<f64>| -> usize {
+            lines.0.iter().map(|line| line.0.len()).sum()
+        };
+        let ops = &context.operations;
+        assert!(vertices(&ops[1].rendered.0) > vertices(&ops[0].rendered.0));
+        assert_eq!(ops[1].rendered.1, ops[0].rendered.1);
+        assert!(vertices(&ops[2].rendered.1) > vertices(&ops[0].rendered.1));
+        // Popped back off, so we're plain again
+        assert_eq!(ops[3].rendered, ops[0].rendered);
+    }
+
+    #[test]
+    fn test_plot_report() {
+        let mut context = Context::new();
+        context
+            .stroke("black")
+            .fill("red")
+            .pattern(Hatches::line())
+            .circle(10.0, 10.0, 5.0);
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 40.0, 40.0));
+        let report = context.plot_report(&arrangement);
+        let colors: Vec<&str> = report.layers.iter().map(|l| l.color.as_str()).collect();
+        assert_eq!(colors, vec!["black", "red"]);
+        let outline = &report.layers[0];
+        assert!((outline.draw_length - 10.0 * PI).abs() < 0.5);
+        let fill = &report.layers[1];
+        assert!(fill.draw_length > 0.0);
+        assert!(fill.line_count > 1);
+        assert!(fill.travel_length > 0.0);
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("color,draw_length,travel_length,line_count\n\"black\","));
+        assert_eq!(csv.lines().count(), 3);
+        let json = report.to_json().unwrap();
+        assert_eq!(serde_json::from_str::<PlotReport>(&json).unwrap(), report);
+    }
+
+    #[test]
+    fn test_pen_registry() {
+        let mut pens = PenRegistry::new();
+        pens.insert("black".to_string(), pen_registry::PenInfo::new(1, 0.3));
+        pens.insert("#ff0000".to_string(), pen_registry::PenInfo::new(2, 0.8));
+        let mut context = Context::new();
+        context
+            .pen_registry(pens)
+            .pattern(Hatches::none())
+            .stroke("#ff0000")
+            .line(0.0, 0.0, 10.0, 0.0)
+            .stroke("black")
+            .line(0.0, 5.0, 10.0, 5.0)
+            .stroke("green")
+            .line(0.0, 10.0, 10.0, 10.0)
+            .stroke("#ff0000")
+            .line(0.0, 15.0, 10.0, 15.0);
+        let layers = context.to_layers();
+        let tools: Vec<Option<usize>> = layers.iter().map(|layer| layer.stroke_tool()).collect();
+        assert_eq!(tools, vec![Some(2), Some(1), None, Some(2)]);
+        assert_eq!(layers[0].stroke_width(), 0.8);
+        assert_eq!(layers[1].stroke_width(), 0.3);
+        assert_eq!(layers[2].stroke_width(), context.pen_width);
+
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 40.0, 40.0));
+        let post_template =
+            crate::gcode::AoerPostMachines::get_machine(crate::gcode::AoerPostMachines::BAPv1)
+                .unwrap();
+        let program = context.to_gcode(&arrangement, &post_template).unwrap();
+        let changes: Vec<&String> = program.iter().filter(|l| l.starts_with("M0 ")).collect();
+        assert_eq!(
+            changes,
+            vec!["M0 ; change pen #ff0000", "M0 ; change pen green"]
+        );
+        assert_eq!(
+            program
+                .iter()
+                .filter(|l| l.ends_with("NEW LINE START"))
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_min_length_hatch_filter() {
+        let shortest = |context: &Context| -> f64 {
+            let (_strokes, fills) = &context.operations[0].rendered;
+            fills
+                .iter()
+                .map(|line| line.euclidean_length())
+                .fold(f64::MAX, f64::min)
+        };
+        let mut context = Context::new();
+        context
+            .pen(0.5)
+            .pattern(Hatches::line())
+            .poly(vec![(0.0, 0.0), (10.0, 0.0), (5.0, 10.0)], vec![]);
+        // The hatch line nearest the point of the triangle is a sliver
+        assert!(shortest(&context) < 0.5);
+        let mut filtered = Context::new();
+        filtered
+            .pen(0.5)
+            .pattern(Hatches::line())
+            .hatch_filter(Some(Arc::new(Box::new(line_filter::MinLengthFilter::new(
+                0.5,
+            )))))
+            .poly(vec![(0.0, 0.0), (10.0, 0.0), (5.0, 10.0)], vec![]);
+        assert!(shortest(&filtered) >= 0.5);
+        assert!(
+            filtered.operations[0].rendered.1 .0.len() < context.operations[0].rendered.1 .0.len()
+        );
+    }
+
+    #[test]
+    fn test_hatch_scale() {
+        let mut context = Context::new();
+        context
+            .pen(0.3)
+            .pattern(Hatches::line())
+            .circle(0.0, 0.0, 10.0)
+            .hatch_scale(Some(2.0))
+            .circle(0.0, 0.0, 10.0)
+            .hatch_scale(None)
+            .circle(0.0, 0.0, 10.0);
+        let fills: Vec<usize> = context
+            .operations
+            .iter()
+            .map(|op| op.rendered.1 .0.len())
+            .collect();
+        assert!(fills[1] < fills[0] / 3);
+        assert_eq!(fills[2], fills[0]);
+    }
+
+    #[test]
+    fn test_catmull_rom() {
+        let points = vec![(0.0, 0.0), (10.0, 5.0), (20.0, -5.0), (30.0, 0.0)];
+        for closed in [false, true] {
+            let mut context = Context::new();
+            context.catmull_rom(&points, 8, closed);
+            assert_eq!(context.len(), 1);
+            let content = &context.operations[0].content;
+            // Goes through each of the points
+            for (x, y) in &points {
+                assert!(content
+                    .coords_iter()
+                    .any(|c| (c.x - x).abs() < 1e-6 && (c.y - y).abs() < 1e-6));
+            }
+            let count = content.coords_iter().count();
+            if closed {
+                assert!(count > 4 * 8);
+            } else {
+                assert!(count > 3 * 8);
+            }
+        }
+        let mut context = Context::new();
+        context.catmull_rom(&[(1.0, 1.0)], 8, false);
+        assert!(context.is_empty());
+    }
+
+    #[test]
+    fn test_bezier() {
+        let mut context = Context::new();
+        context
+            .cubic_bezier((0.0, 0.0), (3.0, 3.0), (6.0, 6.0), (10.0, 10.0))
+            .quad_bezier((0.0, 20.0), (10.0, 30.0), (20.0, 20.0));
+        assert_eq!(context.len(), 2);
+        // Unioning might split the line up, so just look at all the coords.
+        let straight = &context.operations[0].content;
+        let bounds = straight.bounding_rect().unwrap();
+        assert_eq!((bounds.min().x, bounds.min().y), (0.0, 0.0));
+        assert_eq!((bounds.max().x, bounds.max().y), (10.0, 10.0));
+        for c in straight.coords_iter() {
+            assert!((c.x - c.y).abs() < 0.01);
+        }
+        let quad = &context.operations[1].content;
+        assert!(quad.coords_iter().count() > 3);
+        // Halfway along, the quad peaks at 25
+        let top = quad.bounding_rect().unwrap().max().y;
+        assert!((top - 25.0).abs() < context.accuracy * 2.0);
+    }
+
+    #[test]
+    fn test_line_mask() {
+        let mut context = Context::new();
+        context
+            .pen(0.5)
+            .mask_line_width(Some(2.0))
+            .set_mask(&Some(Geometry::LineString(LineString::new(vec![
+                coord! {x: 0.0, y: 5.0},
+                coord! {x: 20.0, y: 5.0},
+            ]))))
+            .pattern(Hatches::none())
+            .line(5.0, 0.0, 5.0, 10.0);
+        // We only get the bit of the line that crosses the 2mm wide strip.
+        let bounds = context.bounds().unwrap();
+        assert!((bounds.min().y - 4.0).abs() < 0.01);
+        assert!((bounds.max().y - 6.0).abs() < 0.01);
+        assert!((bounds.min().x - 5.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut context = Context::new();
+        context
+            .pen(0.3)
+            .stroke("blue")
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .push()
+            .circle(5.0, 5.0, 2.0);
+        assert_eq!(context.len(), 2);
+        assert!(context.bounds().is_ok());
+        context.clear();
+        assert!(context.is_empty());
+        assert!(context.bounds().is_err());
+        assert!(context.pop().is_err());
+        assert_eq!(context.pen_width, 0.3);
+        assert_eq!(context.stroke_color, "blue");
+        context.line(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(context.len(), 1);
+        assert_eq!(context.operations[0].pen_width, 0.3);
+    }
+
+    #[test]
+    fn test_append() {
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 40.0, 40.0));
+        let mut stamp = Context::new();
+        stamp
+            .fill("red")
+            .pattern(Hatches::line())
+            .hatch(45.0)
+            .circle(0.0, 0.0, 8.0);
+
+        let mut direct = Context::new();
+        direct
+            .fill("red")
+            .pattern(Hatches::line())
+            .hatch(45.0)
+            .circle(0.0, 0.0, 8.0);
+        let mut appended = Context::new();
+        appended.append(&stamp, None);
+        assert_eq!(
+            appended.to_svg(&arrangement).unwrap().to_string(),
+            direct.to_svg(&arrangement).unwrap().to_string()
+        );
+
+        let mut direct = Context::new();
+        direct
+            .transform(Some(&Context::translate_matrix(20.0, 20.0)))
+            .fill("red")
+            .pattern(Hatches::line())
+            .hatch(45.0)
+            .circle(0.0, 0.0, 8.0);
+        let mut appended = Context::new();
+        appended.append(&stamp, Some(&Context::translate_matrix(20.0, 20.0)));
+        assert_eq!(
+            appended.to_svg(&arrangement).unwrap().to_string(),
+            direct.to_svg(&arrangement).unwrap().to_string()
+        );
+        assert_eq!(appended.bounds().unwrap(), direct.bounds().unwrap());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mut context = Context::new();
+        context
+            .fill("red")
+            .pattern(Hatches::cross())
+            .hatch(30.0)
+            .rect(5.0, 5.0, 25.0, 15.0)
+            .push()
+            .stroke("blue")
+            .circle(20.0, 20.0, 5.0);
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 40.0, 40.0));
+        let json = serde_json::to_string(&context).unwrap();
+        let reloaded: Context = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            reloaded.to_svg(&arrangement).unwrap().to_string(),
+            context.to_svg(&arrangement).unwrap().to_string()
+        );
+        // Still got the stack, and the font came back.
+        let mut reloaded = reloaded;
+        assert!(reloaded.font.is_some());
+        assert!(reloaded.pop().is_ok());
+    }
+
+    #[test]
+    fn test_stroke_dasharray() {
+        let mut context = Context::new();
+        context
+            .fill("red")
+            .pattern(Hatches::line())
+            .stroke_dasharray(Some("4,2"))
+            .rect(0.0, 0.0, 10.0, 10.0);
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 20.0, 20.0));
+        let svg = context.to_svg(&arrangement).unwrap().to_string();
+        // Just the outline, not the fill
+        assert_eq!(svg.matches("stroke-dasharray=\"4,2\"").count(), 1);
+        let dashed_layers = context.to_layers();
+
+        let mut context = Context::new();
+        context
+            .fill("red")
+            .pattern(Hatches::line())
+            .rect(0.0, 0.0, 10.0, 10.0);
+        let svg = context.to_svg(&arrangement).unwrap().to_string();
+        assert!(!svg.contains("stroke-dasharray"));
+        // And the actual lines don't change at all
+        assert_eq!(
+            context.to_layers()[0].stroke_lines,
+            dashed_layers[0].stroke_lines
+        );
+        assert_eq!(
+            context.to_layers()[0].fill_lines,
+            dashed_layers[0].fill_lines
+        );
+    }
+
+    #[test]
+    fn test_to_svg_pages() {
+        let mut context = Context::new();
+        context
+            .pattern(Hatches::none())
+            .rect(0.0, 0.0, 300.0, 300.0)
+            .line(0.0, 150.0, 300.0, 150.0);
+        let page = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 200.0, y: 200.0});
+        let pages = context
+            .to_svg_pages(&Arrangement::tile(page, 10.0))
+            .unwrap();
+        assert_eq!(pages.len(), 4);
+        for page in pages {
+            let svg = page.to_string();
+            assert_eq!(svg.matches("id=\"registration-mark-").count(), 4);
+            assert!(svg.contains("outline-0"));
+            // The marks are in an inkscape layer, so the namespace has to be declared.
+            let root = svg::read(&svg)
+                .unwrap()
+                .find_map(|event| match event {
+                    svg::parser::Event::Tag("svg", _, attributes) => Some(attributes),
+                    _ => None,
+                })
+                .unwrap();
+            assert!(root.contains_key("xmlns:inkscape"));
+        }
+
+        // Plain arrangements are just one page.
+        let arrangement = Arrangement::FitCenter(page, false);
+        assert_eq!(context.to_svg_pages(&arrangement).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_to_svg_layered() {
+        let mut context = Context::new();
+        context
+            .stroke("black")
+            .fill("red")
+            .pattern(Hatches::line())
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .stroke("blue")
+            .pattern(Hatches::none())
+            .line(0.0, 0.0, 10.0, 10.0)
+            .stroke("black")
+            .line(0.0, 10.0, 10.0, 0.0);
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 20.0, 20.0));
+        let svg = context.to_svg_layered(&arrangement).unwrap().to_string();
+        assert!(svg.contains("xmlns:inkscape=\"http://www.inkscape.org/namespaces/inkscape\""));
+        // black, red (the hatch) and blue
+        assert_eq!(svg.matches("inkscape:groupmode=\"layer\"").count(), 3);
+        assert!(svg.contains("inkscape:label=\"1-black\""));
+        assert!(svg.contains("inkscape:label=\"2-red\""));
+        assert!(svg.contains("inkscape:label=\"3-blue\""));
+        // Nothing lost along the way
+        let flat = context.to_svg(&arrangement).unwrap().to_string();
+        assert_eq!(svg.matches("<path").count(), flat.matches("<path").count());
+    }
+
+    #[test]
+    fn test_stroke_gradient() {
+        let mut context = Context::new();
+        context
+            .stroke("black")
+            .pen(0.5)
+            .pattern(Hatches::none())
+            .line(0.0, 0.0, 10.0, 0.0)
+            .line(10.0, 0.0, 10.0, 10.0)
+            .line(10.0, 10.0, 0.0, 10.0)
+            .stroke_gradient("red", "#0000ff");
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 20.0, 20.0));
+        let svg = context.to_svg(&arrangement).unwrap().to_string();
+        // Only the last line gets the gradient, and it's a single segment
+        assert_eq!(svg.matches("stroke=\"black\"").count(), 2);
+        assert_eq!(svg.matches("stroke=\"#800080\"").count(), 1);
+
+        let mut context = Context::new();
+        context
+            .stroke("black")
+            .pen(0.5)
+            .pattern(Hatches::none())
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .stroke_gradient("red", "blue");
+        let svg = context.to_svg(&arrangement).unwrap().to_string();
+        assert!(svg.contains("<g "));
+        assert!(!svg.contains("stroke=\"black\""));
+        assert_eq!(svg.matches("stroke=\"#").count(), 4);
+    }
+
+    #[test]
+    fn test_connect_nearest() {
+        let mut context = Context::new();
+        context
+            .stroke("black")
+            .pen(0.5)
+            .pattern(Hatches::none())
+            .line(0.0, 0.0, 10.0, 0.0)
+            .line(10.0, 0.05, 10.0, 10.0)
+            .line(0.0, 10.0, 10.0, 10.0) // Backwards!
+            .line(0.0, 10.0, 0.0, 0.0)
+            .stroke("red")
+            .line(10.0, 0.0, 20.0, 0.0) // Touches, but it's a different pen.
+            .connect_nearest(0.1);
+        let check = |context: &Context| {
+            let lines_for = |color: &str| -> Vec<LineString<f64>> {
+                context
+                    .to_layers()
+                    .iter()
+                    .filter(|layer| layer.stroke == color)
+                    .flat_map(|layer| layer.stroke_lines.0.clone())
+                    .collect()
+            };
+            let black = lines_for("black");
+            assert_eq!(black.len(), 1);
+            assert!(black[0].is_closed());
+            assert_eq!(lines_for("red").len(), 1);
+        };
+        check(&context);
+        // Re-rendering doesn't undo the stitching.
+        for op in context.operations.iter_mut() {
+            op.rendered = op.render_to_lines();
+        }
+        check(&context);
+    }
+
+    #[test]
+    fn test_target_plot_time() {
+        let mut context = Context::new();
+        context
+            .stroke("black")
+            .fill("black")
+            .pen(0.1)
+            .pattern(Hatches::line())
+            .hatch(45.0)
+            .circle(0.0, 0.0, 20.0);
+        let fills_before: usize = context
+            .to_layers()
+            .iter()
+            .map(|l| l.fill_lines.0.len())
+            .sum();
+        assert!(context.estimate_plot_time(50.0) > 10.0);
+        context.target_plot_time(10.0, 50.0);
+        let fills_after: usize = context
+            .to_layers()
+            .iter()
+            .map(|l| l.fill_lines.0.len())
+            .sum();
+        assert!(fills_after < fills_before);
+        assert!(context.estimate_plot_time(50.0) <= 10.0);
+
+        // Guides aren't plotted, so they don't count, and they're left alone.
+        let mut guided = Context::new();
+        guided
+            .pen(0.1)
+            .pattern(Hatches::line())
+            .hatch(45.0)
+            .guide(true)
+            .circle(0.0, 0.0, 20.0)
+            .guide(false)
+            .rect(0.0, 0.0, 1.0, 1.0);
+        let guide_fills = guided.operations[0].rendered.1.clone();
+        assert!(!guide_fills.0.is_empty());
+        assert!(guided.estimate_plot_time(50.0) < 1.0);
+        guided.target_plot_time(0.0, 50.0);
+        assert_eq!(guided.operations[0].rendered.1, guide_fills);
+        assert_eq!(guided.operations[0].hatch_scale, None);
+    }
+
+    #[test]
+    fn test_guide() {
+        let mut context = Context::new();
+        context
+            .stroke("black")
+            .pen(0.5)
+            .line(0.0, 5.0, 10.0, 5.0)
+            .pen(0.25)
+            .guide(true)
+            .line(0.0, 0.0, 10.0, 10.0)
+            .clip(true)
+            .rect(2.0, 2.0, 8.0, 8.0) // A guide mustn't clip what's under it.
+            .clip(false)
+            .guide(false)
+            .pen(0.5)
+            .line(0.0, 10.0, 10.0, 0.0);
+        let layers = context.to_layers();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].stroke_lines.euclidean_length(), 10.0);
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 10.0, 10.0));
+        let svg = context.to_svg(&arrangement).unwrap().to_string();
+        assert!(svg.contains("id=\"guides\""));
+        assert!(svg.contains("M0,0 L10,10"));
+        assert!(svg.contains("stroke-dasharray"));
+        // Guides keep their own pen width, not whatever's current at the end.
+        assert!(svg.contains("stroke-width=\"0.25\""));
+    }
+
+    #[test]
+    fn test_auto_rotate_hatch() {
+        let mut context = Context::new();
+        context
+            .hatch(10.0)
+            .auto_rotate_hatch(30.0)
+            .fill("red")
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .rect(5.0, 5.0, 15.0, 15.0)
+            .fill("green")
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .fill("blue")
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .begin_layer("still blue")
+            .rect(0.0, 0.0, 10.0, 10.0);
+        let angles: Vec<f64> = context.operations.iter().map(|op| op.hatch_angle).collect();
+        assert_eq!(angles, vec![10.0, 10.0, 40.0, 70.0, 100.0]);
+    }
+
+    #[test]
+    fn test_draw_bounds() {
+        let mut context = Context::new();
+        assert!(context.draw_bounds(None).is_err());
+        context
+            .rect(0.0, 5.0, 10.0, 15.0)
+            .circle(20.0, 20.0, 5.0)
+            .draw_bounds(None)
+            .unwrap();
+        let frame = context.operations.last().unwrap();
+        assert!(frame.guide);
+        let frame_bounds = frame.content.bounding_rect().unwrap();
+        assert!(frame_bounds.min().x.abs() < 0.0001);
+        assert!((frame_bounds.min().y - 5.0).abs() < 0.0001);
+        assert!((frame_bounds.max().x - 25.0).abs() < 0.0001);
+        assert!((frame_bounds.max().y - 25.0).abs() < 0.0001);
+        assert_eq!(context.to_layers().len(), 2);
+    }
+
+    #[test]
+    fn test_replace_color() {
+        let mut context = Context::new();
+        context
+            .stroke("red")
+            .rect(0.0, 0.0, 10.0, 10.0)
+            .circle(20.0, 20.0, 5.0)
+            .stroke("green")
+            .line(0.0, 0.0, 5.0, 5.0);
+        assert_eq!(context.replace_color("red", "blue"), 2);
+        assert_eq!(context.operations[0].stroke_color, "blue");
+        assert_eq!(context.operations[1].stroke_color, "blue");
+        assert_eq!(context.operations[2].stroke_color, "green");
+    }
+
+    #[test]
+    fn test_rounded_rect() {
+        let mut context = Context::new();
+        context
+            .rect(0.0, 0.0, 20.0, 10.0)
+            .rounded_rect(0.0, 0.0, 20.0, 10.0, 0.0)
+            .rounded_rect(0.0, 0.0, 20.0, 10.0, 50.0);
+        let ring = |i: usize| match &context.operations[i].content {
+            Geometry::Polygon(poly) => poly.exterior().clone(),
+            _ => panic!("Expected a polygon"),
+        };
+        let (square, rounded) = (ring(0), ring(1));
+        assert_eq!(square.0.len(), rounded.0.len());
+        for (a, b) in square.0.iter().zip(rounded.0.iter()) {
+            assert!(a.euclidean_distance(b) < 0.0001);
+        }
+        // Radius gets clamped to 5, so we end up with a stadium
+        let stadium = context.operations[2].content.bounding_rect().unwrap();
+        assert!(stadium.min().x.abs() < 0.0001 && (stadium.max().x - 20.0).abs() < 0.0001);
+        assert!(ring(2).is_closed());
+    }
+
+    #[test]
+    fn test_ellipse_hatch() {
+        let mut context = Context::new();
+        context
+            .pen(0.5)
+            .fill("red")
+            .pattern(Hatches::line())
+            .hatch(45.0)
+            .ellipse(50.0, 50.0, 30.0, 10.0, 30.0);
+        let layers = context.to_layers();
+        assert!(layers.iter().any(|layer| !layer.fill_lines.0.is_empty()));
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 100.0, 100.0));
+        let svg = context.to_svg(&arrangement).unwrap().to_string();
+        assert!(svg.contains("<path"));
+        let bounds = context.bounds().unwrap();
+        assert!(bounds.width() < 60.0 && bounds.width() > 20.0);
+    }
+
+    #[test]
+    fn test_bounds_min() {
+        let mut context = Context::new();
+        context
+            .rect(10.0, 10.0, 20.0, 20.0)
+            .rect(-50.0, -50.0, -40.0, -40.0);
+        let bounds = context.bounds().unwrap();
+        assert_eq!(bounds.min(), coord! {x: -50.0, y: -50.0});
+        assert_eq!(bounds.max(), coord! {x: 20.0, y: 20.0});
+
+        // Lopsided, so mixing up x and y can't give the right answer by accident.
+        let mut context = Context::new();
+        context
+            .rect(10.0, -5.0, 20.0, 20.0)
+            .rect(-50.0, -30.0, -40.0, -20.0);
+        let bounds = context.bounds().unwrap();
+        assert_eq!(bounds.min(), coord! {x: -50.0, y: -30.0});
+        assert_eq!(bounds.max(), coord! {x: 20.0, y: 20.0});
+    }
+
+    #[test]
+    fn test_bounds_cache() {
+        let mut context = Context::new();
+        context.rect(0.0, 0.0, 10.0, 10.0);
+        let first = context.bounds().unwrap();
+        let second = context.bounds().unwrap();
+        assert_eq!(first, second);
+        assert_eq!(context.bounds_computed.get(), 1);
+        context.rect(20.0, 20.0, 30.0, 30.0);
+        assert!((context.bounds().unwrap().max().x - 30.0).abs() < 0.0001);
+        assert_eq!(context.bounds_computed.get(), 2);
+    }
+
+    #[test]
+    fn test_set_default_font() {
+        let count_points = |context: &Context| -> usize {
+            context
+                .to_layers()
+                .iter()
+                .flat_map(|layer| layer.stroke_lines.0.iter().map(|line| line.0.len()))
+                .sum()
+        };
+        let mut bundled = Context::new();
+        bundled.glyph('A', false);
+
+        let custom_data =
+            include_bytes!("../../resources/fonts/ReliefSingleLineOutline-Regular.otf").to_vec();
+        let custom_font = Font::from_bytes(Arc::new(custom_data), 0).unwrap();
+        let mut custom = Context::new();
+        custom.set_default_font(custom_font).glyph('A', false);
+        assert!(count_points(&custom) > 0);
+        assert_ne!(count_points(&bundled), count_points(&custom));
+        // It's only this context's default; everybody else keeps the bundled font.
+        let mut other = Context::new();
+        other.glyph('A', false);
+        assert_eq!(count_points(&bundled), count_points(&other));
+
+        // Typography without a font of its own picks up the context's default too.
+        let text = "A".to_string();
+        let single_line = Context::default_font();
+        let mut pinned = Context::new();
+        pinned.typography(&text, 0.0, 0.0, Typography::new().font(&single_line));
+        let mut defaulted = Context::new();
+        defaulted
+            .set_default_font(single_line)
+            .typography(&text, 0.0, 0.0, &Typography::new());
+        let mut plain = Context::new();
+        plain.typography(&text, 0.0, 0.0, &Typography::new());
+        assert_eq!(count_points(&pinned), count_points(&defaulted));
+        assert_ne!(count_points(&plain), count_points(&defaulted));
+    }
+
+    #[test]
+    fn test_font_from_bytes() {
+        let data =
+            include_bytes!("../../resources/fonts/ReliefSingleLineOutline-Regular.otf").to_vec();
+        let mut context = Context::new();
+        context.font_from_bytes(data).unwrap().glyph('A', false);
+        assert!(!context.to_layers()[0].stroke_lines.0.is_empty());
+
+        let mut context = Context::new();
+        assert!(matches!(
+            context.font_from_bytes(b"not a font".to_vec()),
+            Err(ContextError::FontLoadError(_))
+        ));
+        assert!(matches!(
+            context.font_from_path(std::path::Path::new("/no/such/font.ttf")),
+            Err(ContextError::FontLoadError(msg)) if msg.starts_with("/no/such/font.ttf")
+        ));
+    }
+
+    #[test]
+    fn test_paragraph() {
+        let mut context = Context::new();
+        let mut typography = Typography::new();
+        typography.size(2.0);
+        // Exactly two words fit on a line, so six words make three lines.
+        let two_words = typography
+            .render(&"HIHI HIHI".to_string(), context.accuracy)
+            .unwrap()
+            .bounding_rect()
+            .unwrap()
+            .width();
+        let box_ = Context::viewbox(10.0, 10.0, 10.0 + two_words + 0.01, 200.0);
+        context.paragraph("HIHI HIHI HIHI HIHI HIHI HIHI", box_, &typography);
+        let line_height = typography.line_height();
+        // Every glyph sits on its line's baseline, so the bottoms tell us the line.
+        let mut lines: Vec<(i64, Rect<f64>)> = vec![];
+        for op in &context.operations {
+            let bounds = op.content.bounding_rect().unwrap();
+            let line = ((bounds.max().y - box_.min().y) / line_height).round() as i64;
+            match lines.iter_mut().find(|(number, _)| *number == line) {
+                Some((_, rect)) => {
+                    *rect = Rect::new(
+                        coord! {x: rect.min().x.min(bounds.min().x), y: rect.min().y},
+                        coord! {x: rect.max().x.max(bounds.max().x), y: rect.max().y},
+                    )
+                }
+                None => lines.push((line, bounds)),
+            }
+        }
+        lines.sort_by_key(|(number, _)| *number);
+        assert_eq!(
+            lines.iter().map(|(number, _)| *number).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        for (_number, rect) in &lines {
+            assert!(rect.width() <= box_.width() + 0.0001);
+            assert!(rect.min().x >= box_.min().x - 1.0);
+        }
+    }
+
+    #[test]
+    fn test_text_on_path() {
+        let mut context = Context::new();
+        let mut typography = Typography::new();
+        typography.size(2.0);
+        // Over the top of a circle, left to right.
+        let arc = LineString::new(
+            (0..=180)
+                .map(|i| {
+                    let angle = PI + (i as f64).to_radians();
+                    coord! {x: 100.0 + 50.0 * angle.cos(), y: 100.0 + 50.0 * angle.sin()}
+                })
+                .collect(),
+        );
+        context.text_on_path("AROUND THE TOP OF THE CIRCLE WE GO", &arc, &typography);
+        assert!(context.operations.len() > 10);
+        let centroids: Vec<Point<f64>> = context
+            .operations
+            .iter()
+            .filter_map(|op| op.content.centroid())
+            .collect();
+        for centroid in &centroids {
+            // Just outside the arc, since the glyphs are sitting on it.
+            let radius = centroid.euclidean_distance(&Point::new(100.0, 100.0));
+            assert!(radius > 50.0 && radius < 58.0);
+            assert!(centroid.y() < 100.0);
+        }
+        let (min_y, max_y) = centroids.iter().fold((f64::MAX, f64::MIN), |(lo, hi), c| {
+            (lo.min(c.y()), hi.max(c.y()))
+        });
+        assert!(max_y - min_y > 20.0);
+
+        // Too long for a short path, so most of it gets dropped.
+        let mut short = Context::new();
+        let stub = LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0}]);
+        short.text_on_path("AROUND THE TOP OF THE CIRCLE WE GO", &stub, &typography);
+        assert!(short.operations.len() < context.operations.len() / 4);
+        assert!(short.bounds().unwrap().max().x < 11.0);
+    }
+
+    #[test]
+    fn test_text_block() {
+        let mut context = Context::new();
+        let mut typography = Typography::new();
+        typography.size(2.0);
+        let text = "one two three four five six\n\nseven";
+        // Narrow enough that only a couple of words fit per line.
+        let lines = context.wrap_text(text, 30.0, &typography);
+        assert_eq!(
+            lines,
+            vec!["one two", "three four", "five six", "", "seven"]
+        );
+        assert_eq!(context.wrap_text(text, 1.0, &typography).len(), 8);
+
+        context.text_block(text, 10.0, 20.0, 30.0, &typography);
+        let bounds = context.bounds().unwrap();
+        let line_height = typography.line_height();
+        // Five lines down, and the last one is "seven", sitting on its baseline.
+        assert!((bounds.max().y - (20.0 + 4.0 * line_height)).abs() < 0.5);
+        assert!(bounds.min().x >= 10.0 - 1.0);
+        assert!(bounds.max().x <= 40.0 + 0.0001);
+    }
+
+    #[test]
+    fn test_to_gcode() {
+        let mut context = Context::new();
+        context
+            .pattern(Hatches::none())
+            .line(0.0, 0.0, 10.0, 0.0)
+            .stroke("red")
+            .line(0.0, 10.0, 10.0, 10.0)
+            .line(20.0, 10.0, 30.0, 10.0);
+        let arrangement = Arrangement::unit(&Context::viewbox(0.0, 0.0, 40.0, 40.0));
+        let post_template =
+            crate::gcode::AoerPostMachines::get_machine(crate::gcode::AoerPostMachines::BAPv1)
+                .unwrap();
+        let program = context.to_gcode(&arrangement, &post_template).unwrap();
+        let line_count: usize = context
+            .to_layers()
+            .iter()
+            .map(|layer| layer.optimized_lines().0 .0.len())
+            .sum();
+        assert_eq!(program.first().unwrap(), "M280 S5");
+        assert_eq!(program.last().unwrap(), "M281 ; FINISHED");
+        assert_eq!(
+            program
+                .iter()
+                .filter(|l| l.ends_with("NEW LINE START"))
+                .count(),
+            line_count
+        );
+    }
+
+    #[test]
+    fn test_travel_moves() {
+        let lines = |offset: f64, count: usize| {
+            Geometry::MultiLineString(MultiLineString::new(
+                (0..count)
+                    .map(|i| {
+                        let x = offset + 10.0 * i as f64;
+                        LineString::new(vec![coord! {x: x, y: 0.0}, coord! {x: x, y: 5.0}])
+                    })
+                    .collect(),
+            ))
+        };
+        let mut context = Context::new();
+        context
+            .stroke("red")
+            .geometry(&lines(0.0, 3))
+            .stroke("blue")
+            .geometry(&lines(100.0, 2));
+        let total: usize = context
+            .to_layers()
+            .iter()
+            .map(|layer| layer.optimized_lines().0 .0.len())
+            .sum();
+        assert_eq!(total, 5);
+        assert_eq!(context.travel_moves().0.len(), total - 2);
+    }
+
+    #[test]
+    fn test_arrangement_from_svg() {
+        let context = Context::new();
+        let arrangement = context
+            .arrangement_from_svg(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 210 297\" \
+                 width=\"210mm\" height=\"297mm\"></svg>",
+            )
+            .unwrap();
+        assert_eq!(
+            arrangement.viewbox(),
+            Context::viewbox(0.0, 0.0, 210.0, 297.0)
+        );
+        let arrangement = context
+            .arrangement_from_svg("<svg width=\"100mm\" height=\"50mm\"/>")
+            .unwrap();
+        assert_eq!(
+            arrangement.viewbox(),
+            Context::viewbox(0.0, 0.0, 100.0, 50.0)
+        );
+        assert!(context.arrangement_from_svg("<g/>").is_err());
+    }
+
     #[test]
     fn test_to_geo() {
         let mut context = Context::new();
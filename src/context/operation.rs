@@ -1,20 +1,25 @@
+use crate::geo_types::buffer::{CapStyle, JoinStyle};
+use crate::geo_types::dash::Dash;
+use crate::geo_types::simplify::SimplifyRings;
 use crate::prelude::{Hatch, Hatches, OutlineFillStroke};
 use geo::map_coords::MapCoords;
 use geo_types::{Geometry, MultiLineString, MultiPolygon, Polygon};
 use geos::{Geom, GeometryTypes};
 use std::borrow::BorrowMut;
 // use geos::GeometryTypes::Point;
+use super::line_filter::LineFilter;
 use crate::geo_types::clip::try_to_geos_geometry;
-use geo::simplify::Simplify;
+use crate::optimizer::{OptimizationStrategy, Optimizer};
 pub use kurbo::BezPath;
 pub use kurbo::Point as BezPoint;
 use nalgebra::{Affine2, Point2 as NPoint2};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 /// Operations are private items used to store the operation stack
 /// consisting of a combination of Geometry and Context state.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Operation {
     pub(crate) accuracy: f64,
     pub(crate) content: Geometry<f64>,
@@ -30,6 +35,24 @@ pub struct Operation {
     pub(crate) clip_previous: bool,
     pub(crate) hatch_pattern: Hatches,
     pub(crate) hatch_angle: f64,
+    pub(crate) hatch_scale: Option<f64>,
+    pub(crate) guide: bool,
+    pub(crate) stroke_gradient: Option<(String, String)>,
+    pub(crate) dash: Option<Vec<f64>>,
+    pub(crate) dash_fills: bool,
+    pub(crate) stroke_dasharray: Option<String>,
+    /// The named layer (see begin_layer) this was drawn in, if any.
+    #[serde(default)]
+    pub(crate) layer: Option<String>,
+    /// Strokes stitched together by connect_nearest. They can come from several
+    /// operations, so they replace whatever strokes the content renders to.
+    #[serde(default)]
+    pub(crate) stitched: Option<MultiLineString<f64>>,
+    /// Filters can't be saved, so a reloaded operation just keeps its rendered lines.
+    #[serde(skip)]
+    pub(crate) stroke_filter: Option<Arc<Box<dyn LineFilter>>>,
+    #[serde(skip)]
+    pub(crate) hatch_filter: Option<Arc<Box<dyn LineFilter>>>,
 }
 
 impl Operation {
@@ -46,6 +69,9 @@ impl Operation {
     pub fn render(mut self) -> Self {
         if let Some(tx) = &self.transformation {
             self.content = self.content.map_coords(|xy| Operation::xform_coord(xy, tx));
+            self.stitched = self
+                .stitched
+                .map(|lines| lines.map_coords(|xy| Operation::xform_coord(xy, tx)));
         }
         self.content = match &self.mask {
             Some(mask) => {
@@ -71,6 +97,24 @@ impl Operation {
         self
     }
 
+    /// SVG style line_join name to the buffer JoinStyle. Anything unknown is round.
+    fn join_style(&self) -> JoinStyle {
+        match self.line_join.as_str() {
+            "miter" | "mitre" => JoinStyle::Mitre,
+            "bevel" => JoinStyle::Bevel,
+            _ => JoinStyle::Round,
+        }
+    }
+
+    /// SVG style line_cap name to the buffer CapStyle. Anything unknown is round.
+    fn cap_style(&self) -> CapStyle {
+        match self.line_cap.as_str() {
+            "butt" => CapStyle::Flat,
+            "square" => CapStyle::Square,
+            _ => CapStyle::Round,
+        }
+    }
+
     pub fn consistent(&self, other: &Operation) -> bool {
         if self.stroke_color == other.stroke_color
             && self.outline_stroke == other.outline_stroke
@@ -80,6 +124,7 @@ impl Operation {
             && self.pen_width == other.pen_width
             && self.hatch_angle == other.hatch_angle
             && self.clip_previous == other.clip_previous
+            && self.guide == other.guide
         // &&
         {
             true
@@ -92,6 +137,7 @@ impl Operation {
     fn poly2lines(
         poly: &Polygon<f64>,
         pen_width: f64,
+        hatch_scale: f64,
         hatch_angle: f64,
         hatch_pattern: Hatches,
     ) -> (MultiLineString<f64>, MultiLineString<f64>) {
@@ -105,7 +151,7 @@ impl Operation {
         // let hatch_pattern = hatch_pattern.deref();
         // println!("Hatching with pattern: {:?}", &hatch_pattern);
         let hatches = poly
-            .hatch(hatch_pattern, hatch_angle, pen_width, pen_width)
+            .hatch(hatch_pattern, hatch_angle, hatch_scale, pen_width)
             .unwrap_or(MultiLineString::new(vec![]));
         // fills.0.append(&mut hatches.0.clone());
         (strokes, hatches)
@@ -115,6 +161,7 @@ impl Operation {
     fn mpoly2lines(
         mpoly: &MultiPolygon<f64>,
         pen_width: f64,
+        hatch_scale: f64,
         hatch_angle: f64,
         hatch_pattern: Hatches,
     ) -> (MultiLineString<f64>, MultiLineString<f64>) {
@@ -130,7 +177,7 @@ impl Operation {
         // let hatch_pattern = hatch_pattern.deref();
         // println!("Hatching with pattern: {:?}", &hatch_pattern);
         let hatches = mpoly
-            .hatch(hatch_pattern, hatch_angle, pen_width, pen_width)
+            .hatch(hatch_pattern, hatch_angle, hatch_scale, pen_width)
             .unwrap_or(MultiLineString::new(vec![]));
         // fills.0.append(&mut hatches.0.clone());
         (strokes, hatches)
@@ -145,6 +192,7 @@ impl Operation {
     fn help_render_geo(
         txgeo: &Geometry<f64>,
         pen_width: f64,
+        hatch_scale: f64,
         hatch_angle: f64,
         hatch_pattern: Hatches,
     ) -> (MultiLineString<f64>, MultiLineString<f64>) {
@@ -154,11 +202,21 @@ impl Operation {
                 MultiLineString::new(vec![ls.clone()]),
                 MultiLineString::new(vec![]),
             ),
-            Geometry::Polygon(poly) => {
-                Self::poly2lines(&poly, pen_width, hatch_angle, hatch_pattern.clone())
-            }
+            Geometry::Polygon(poly) => Self::poly2lines(
+                &poly,
+                pen_width,
+                hatch_scale,
+                hatch_angle,
+                hatch_pattern.clone(),
+            ),
             Geometry::MultiPolygon(polys) => {
-                Self::mpoly2lines(&polys, pen_width, hatch_angle, hatch_pattern.clone())
+                Self::mpoly2lines(
+                    &polys,
+                    pen_width,
+                    hatch_scale,
+                    hatch_angle,
+                    hatch_pattern.clone(),
+                )
                 // let mut strokes = MultiLineString::new(vec![]);
                 // let mut fills = MultiLineString::new(vec![]);
                 // for poly in polys {
@@ -177,6 +235,7 @@ impl Operation {
                     let (mut tmpstrokes, mut tmpfills) = Operation::help_render_geo(
                         item,
                         pen_width,
+                        hatch_scale,
                         hatch_angle,
                         hatch_pattern.clone(),
                     );
@@ -220,6 +279,7 @@ impl Operation {
                 Self::help_render_geo(
                     &g,
                     self.pen_width,
+                    self.hatch_scale.unwrap_or(self.pen_width),
                     self.hatch_angle,
                     self.hatch_pattern.clone(),
                 )
@@ -235,18 +295,38 @@ impl Operation {
         // Finally, if we have outline stroke, then outline the existing strokes.
         let outlines = match self.outline_stroke {
             Some(stroke) => outlines
-                .outline_fill_stroke_with_hatch(
+                .outline_fill_stroke_with_hatch_styled(
                     stroke,
                     self.pen_width,
                     Hatches::line(),
                     self.hatch_angle,
+                    self.join_style(),
+                    self.cap_style(),
                 )
                 .unwrap_or(outlines),
             None => outlines,
         };
+        let (outlines, fills) = (
+            outlines.simplify_preserving_rings(&self.accuracy),
+            fills.simplify_preserving_rings(&self.accuracy),
+        );
+        let (outlines, fills) = match &self.dash {
+            Some(pattern) if self.dash_fills => (outlines.dash(pattern), fills.dash(pattern)),
+            Some(pattern) => (outlines.dash(pattern), fills),
+            None => (outlines, fills),
+        };
+        let outlines = match (&self.stitched, &self.stroke_filter) {
+            // Stitched strokes were already dashed and filtered when first rendered.
+            (Some(stitched), _) => stitched.clone(),
+            (None, Some(filter)) => filter.apply(&outlines),
+            (None, None) => outlines,
+        };
         (
-            outlines.simplify(&self.accuracy),
-            fills.simplify(&self.accuracy),
+            outlines,
+            match &self.hatch_filter {
+                Some(filter) => filter.apply(&fills),
+                None => fills,
+            },
         )
     }
 }
@@ -261,6 +341,16 @@ pub struct OPLayer {
     pub(crate) stroke_width: f64,
     pub(crate) stroke_linejoin: String,
     pub(crate) stroke_linecap: String,
+    pub(crate) stroke_gradient: Option<(String, String)>,
+    #[serde(default)]
+    pub(crate) stroke_dasharray: Option<String>,
+    /// Tool slots for the stroke and fill colors, if they're in the pen registry.
+    #[serde(default)]
+    pub(crate) stroke_tool: Option<usize>,
+    #[serde(default)]
+    pub(crate) fill_tool: Option<usize>,
+    #[serde(default)]
+    pub(crate) layer: Option<String>,
 }
 
 impl OPLayer {
@@ -268,6 +358,17 @@ impl OPLayer {
         (self.stroke_lines.clone(), self.fill_lines.clone())
     }
 
+    /// The stroke and fill lines in the order they'll actually be drawn: strokes
+    /// get merged, and both get the greedy travel optimization.
+    pub fn optimized_lines(&self) -> (MultiLineString<f64>, MultiLineString<f64>) {
+        let stroke_optimizer = Optimizer::new(self.stroke_width * 2., OptimizationStrategy::Greedy);
+        let fill_optimizer = Optimizer::new(self.stroke_width, OptimizationStrategy::Greedy);
+        (
+            stroke_optimizer.optimize(&stroke_optimizer.merge(&self.stroke_lines)),
+            fill_optimizer.optimize(&self.fill_lines),
+        )
+    }
+
     pub fn stroke(&self) -> String {
         self.stroke.clone()
     }
@@ -278,4 +379,17 @@ impl OPLayer {
     pub fn stroke_width(&self) -> f64 {
         self.stroke_width.clone()
     }
+
+    pub fn stroke_tool(&self) -> Option<usize> {
+        self.stroke_tool
+    }
+
+    pub fn fill_tool(&self) -> Option<usize> {
+        self.fill_tool
+    }
+
+    /// The name given to begin_layer for the operation this came from, if any.
+    pub fn layer(&self) -> Option<String> {
+        self.layer.clone()
+    }
 }
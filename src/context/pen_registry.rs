@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A physical pen: which tool slot it lives in, and how wide a line it really
+/// draws (in mm).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PenInfo {
+    pub index: usize,
+    pub width: f64,
+}
+
+impl PenInfo {
+    pub fn new(index: usize, width: f64) -> PenInfo {
+        PenInfo { index, width }
+    }
+}
+
+/// Maps drawing colors (exactly as passed to stroke/fill, like "black" or
+/// "#ff0000") to the pens that draw them. See [`crate::context::Context::pen_registry`].
+pub type PenRegistry = HashMap<String, PenInfo>;
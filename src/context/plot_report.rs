@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+/// Stats for everything drawn in one color. Lengths are in mm on the page (after
+/// the arrangement), and travel only counts the hops within each pen pass, same
+/// as [`crate::context::Context::travel_moves`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerStats {
+    pub color: String,
+    pub draw_length: f64,
+    pub travel_length: f64,
+    pub line_count: usize,
+}
+
+/// What [`crate::context::Context::plot_report`] hands back: one [`LayerStats`]
+/// per color, in the order they're first drawn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlotReport {
+    pub layers: Vec<LayerStats>,
+}
+
+impl PlotReport {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// One row per color, with a header. Colors get quoted, since rgb(...) colors
+    /// have commas in them.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "color,draw_length,travel_length,line_count\n".to_string();
+        for layer in &self.layers {
+            csv.push_str(&format!(
+                "\"{}\",{:.3},{:.3},{}\n",
+                layer.color.replace('"', "\"\""),
+                layer.draw_length,
+                layer.travel_length,
+                layer.line_count
+            ));
+        }
+        csv
+    }
+}
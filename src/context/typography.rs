@@ -3,15 +3,19 @@ use font_kit::hinting::HintingOptions;
 use geo::bounding_rect::BoundingRect;
 use geo::map_coords::MapCoords;
 use geo::translate::Translate;
-use geo_types::{coord, Geometry, GeometryCollection, Rect};
+use geo_types::{coord, Geometry, GeometryCollection, MultiLineString, Rect};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
 use crate::context::glyph_proxy::GlyphProxy;
+use crate::geo_types::flatten::FlattenPolygons;
+use crate::geo_types::skeleton::Centerline;
 use crate::geo_types::ToGTGeometry;
 use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::vector::Vector2F;
+use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+use ttf_parser::{Face, GlyphId, Tag};
 
 type TypographicBounds = RectF;
 
@@ -30,13 +34,23 @@ impl Display for TypographyError {
 
 impl Error for TypographyError {}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum TextAlignment {
     Left,
     Center,
     Right,
 }
 
+/// Which way the text runs. The vertical modes stack glyphs top to bottom, one em
+/// apart, each centered on its column. A newline starts a new column, to the left of
+/// the last one for VerticalRL (like traditional CJK) and to the right for VerticalLR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextDirection {
+    Horizontal,
+    VerticalRL,
+    VerticalLR,
+}
+
 #[derive(Debug)]
 pub struct RenderedGlyph {
     geo: Geometry<f64>,
@@ -44,12 +58,16 @@ pub struct RenderedGlyph {
     pub advance: Vector2F,
 }
 
+#[derive(Clone)]
 pub struct Typography {
     font: Option<Font>,
+    /// Whether font() was called, rather than just using the default.
+    pub(crate) custom_font: bool,
     hinting: HintingOptions,
     em: f64,
     close: bool,
-    align: TextAlignment, // TODO: Add text-align, other stuff.
+    pub(crate) align: TextAlignment, // TODO: Add text-align, other stuff.
+    direction: TextDirection,
 }
 
 impl Typography {
@@ -64,10 +82,12 @@ impl Typography {
     pub fn new() -> Self {
         Typography {
             font: Some(Self::default_font()),
+            custom_font: false,
             hinting: HintingOptions::None,
             em: 1.0,
             close: false,
             align: TextAlignment::Left,
+            direction: TextDirection::Horizontal,
         }
     }
 
@@ -80,6 +100,13 @@ impl Typography {
         self
     }
 
+    /// Horizontal (the default) or one of the vertical modes. In the vertical modes
+    /// the alignment works top to bottom instead of left to right.
+    pub fn direction(&mut self, direction: TextDirection) -> &mut Self {
+        self.direction = direction;
+        self
+    }
+
     pub fn close(&mut self, close: bool) -> &mut Self {
         self.close = close;
         self
@@ -92,6 +119,7 @@ impl Typography {
 
     pub fn font(&mut self, font: &Font) -> &mut Self {
         self.font = Some(font.clone());
+        self.custom_font = true;
         self
     }
 
@@ -100,6 +128,21 @@ impl Typography {
         self
     }
 
+    /// Distance from one baseline to the next in mm, based on the font's own
+    /// ascent/descent/line gap.
+    pub fn line_height(&self) -> f64 {
+        match &self.font {
+            Some(font) => {
+                let metrics = font.metrics();
+                let units_per_em: f64 = self.em / f64::from(metrics.units_per_em);
+                units_per_em
+                    * Self::mm_per_em()
+                    * f64::from(metrics.ascent - metrics.descent + metrics.line_gap)
+            }
+            None => self.em * Self::mm_per_em() * 1.2,
+        }
+    }
+
     pub fn render(&self, text: &String, accuracy: f64) -> Result<Geometry<f64>, Box<dyn Error>> {
         let font = match &self.font {
             None => return Err(Box::new(TypographyError::NoFontSet)),
@@ -108,11 +151,33 @@ impl Typography {
         let metrics = font.metrics();
         let mut glyphs: Vec<RenderedGlyph> = vec![];
         let mut advance = Vector2F::new(0.0, 0.0);
+        let vertical = self.direction != TextDirection::Horizontal;
+        let (mut column, mut row) = (0.0f32, 0.0f32);
+        let column_width = metrics.ascent - metrics.descent + metrics.line_gap;
+        let em = metrics.units_per_em as f32;
         for char in text.chars() {
+            if vertical && char == '\n' {
+                column += 1.0;
+                row = 0.0;
+                continue;
+            }
             let mut gp = GlyphProxy::new(self.close);
             let glyph = font.glyph_for_char(char).or(Some(32)).unwrap();
             font.outline(glyph, self.hinting, &mut gp)?;
             let thisadvance = font.advance(glyph)?;
+            if vertical {
+                // Each glyph gets an em box going down the column, with its baseline
+                // far enough up that the descenders stay in the box.
+                let column_x = match self.direction {
+                    TextDirection::VerticalLR => column * column_width,
+                    _ => -column * column_width,
+                };
+                advance = Vector2F::new(
+                    column_x - thisadvance.x() / 2.0,
+                    -(row + 1.0) * em - metrics.descent,
+                );
+                row += 1.0;
+            }
             let gtgeo = gp.path().to_gt_geometry(accuracy)?;
             // let gbounds = font.typographic_bounds(glyph)?;
             // println!("Advancing: {:?} for {:?} which has bounds: {:?} and self-advance of {:?}", advance.x(), char, gbounds.0.x(), thisadvance.x());
@@ -121,7 +186,9 @@ impl Typography {
                 bounds: font.typographic_bounds(glyph)?,
                 advance: advance.clone(),
             };
-            advance = advance + thisadvance;
+            if !vertical {
+                advance = advance + thisadvance;
+            }
             //println!("GLYPH PUSHED: {:?}", &rglyph);
             glyphs.push(rglyph);
         }
@@ -145,23 +212,142 @@ impl Typography {
             .unwrap_or(Rect::new(coord! {x: 0.0, y:0.0}, coord! {x:0.0, y:0.0}));
         // println!("After scasling: {:?}", output_geo_collection);
         // println!("Scaled bounds: {:?}", bounds);
-        let output_geo_collection = match &self.align {
-            TextAlignment::Left => output_geo_collection,
-            TextAlignment::Right => {
+        let output_geo_collection = match (&self.align, vertical) {
+            (TextAlignment::Left, _) => output_geo_collection,
+            (TextAlignment::Right, true) => output_geo_collection.translate(0.0, bounds.height()),
+            (TextAlignment::Center, true) => {
+                output_geo_collection.translate(0.0, bounds.height() / 2.0)
+            }
+            (TextAlignment::Right, false) => {
                 output_geo_collection.translate(-(bounds.max().x - bounds.min().x), 0.0)
             }
-            TextAlignment::Center => {
+            (TextAlignment::Center, false) => {
                 output_geo_collection.translate(-(bounds.max().x - bounds.min().x) / 2.0, 0.0)
             }
         };
         Ok(Geometry::GeometryCollection(output_geo_collection))
     }
+
+    /// Lays the text out one glyph at a time, for when you want to place them yourself
+    /// (justified, tracked, on a path...). Each glyph comes back at the origin, scaled to
+    /// mm, along with how far along the baseline the next glyph should go. That advance
+    /// includes kerning against the next character, if the font has any (either an old
+    /// school kern table, or GPOS pair kerning).
+    pub fn layout(
+        &self,
+        text: &str,
+        accuracy: f64,
+    ) -> Result<Vec<(char, Geometry<f64>, f64)>, Box<dyn Error>> {
+        let font = match &self.font {
+            None => return Err(Box::new(TypographyError::NoFontSet)),
+            Some(font) => font.clone(),
+        };
+        let scale = self.em / f64::from(font.metrics().units_per_em) * Self::mm_per_em();
+        let font_data = font.copy_font_data();
+        let face = font_data
+            .as_ref()
+            .and_then(|data| Face::from_slice(data.as_slice(), 0).ok());
+        let chars: Vec<char> = text.chars().collect();
+        let glyph_ids: Vec<u32> = chars
+            .iter()
+            .map(|c| font.glyph_for_char(*c).unwrap_or(32))
+            .collect();
+        let mut glyphs = vec![];
+        for (i, (c, glyph)) in chars.iter().zip(glyph_ids.iter()).enumerate() {
+            let mut gp = GlyphProxy::new(self.close);
+            font.outline(*glyph, self.hinting, &mut gp)?;
+            let geo = gp
+                .path()
+                .to_gt_geometry(accuracy)?
+                .map_coords(|(x, y)| (x * scale, y * scale));
+            let mut advance = f64::from(font.advance(*glyph)?.x());
+            if let (Some(face), Some(next)) = (&face, glyph_ids.get(i + 1)) {
+                advance += f64::from(pair_kerning(face, *glyph, *next));
+            }
+            glyphs.push((*c, geo, advance * scale));
+        }
+        Ok(glyphs)
+    }
+
+    /// Renders the text, then collapses each glyph down to its centerline, so that
+    /// you can engrave it in a single pass instead of outlining and hatching. If
+    /// you want the "inline" look, draw this on top of the regular [`Typography::render`]
+    /// output. Only really makes sense with the outline fonts (like the default).
+    pub fn render_centerline(
+        &self,
+        text: &String,
+        accuracy: f64,
+    ) -> Result<MultiLineString<f64>, Box<dyn Error>> {
+        let glyphs = self.render(text, accuracy)?.flatten_polys()?;
+        glyphs.centerline(accuracy)
+    }
+}
+
+/// Kerning (in font units) between two glyphs. Checks the kern table first, then the
+/// pair adjustments in GPOS' kern feature. Zero if the font doesn't say.
+fn pair_kerning(face: &Face, left: u32, right: u32) -> i16 {
+    let (left, right) = match (u16::try_from(left), u16::try_from(right)) {
+        (Ok(left), Ok(right)) => (GlyphId(left), GlyphId(right)),
+        _ => return 0,
+    };
+    if let Some(kern) = face.tables().kern {
+        for subtable in kern.subtables {
+            if subtable.horizontal && !subtable.variable {
+                if let Some(kerning) = subtable.glyphs_kerning(left, right) {
+                    return kerning;
+                }
+            }
+        }
+    }
+    let gpos = match face.tables().gpos {
+        Some(gpos) => gpos,
+        None => return 0,
+    };
+    let kern_tag = Tag::from_bytes(b"kern");
+    for feature in gpos
+        .features
+        .into_iter()
+        .filter(|feature| feature.tag == kern_tag)
+    {
+        for lookup in feature
+            .lookup_indices
+            .into_iter()
+            .filter_map(|i| gpos.lookups.get(i))
+        {
+            for subtable in lookup.subtables.into_iter::<PositioningSubtable>() {
+                let kerning = match subtable {
+                    PositioningSubtable::Pair(PairAdjustment::Format1 { coverage, sets }) => {
+                        coverage
+                            .get(left)
+                            .and_then(|i| sets.get(i))
+                            .and_then(|set| set.get(right))
+                            .map(|(first, _second)| first.x_advance)
+                    }
+                    PositioningSubtable::Pair(PairAdjustment::Format2 {
+                        coverage,
+                        classes,
+                        matrix,
+                    }) if coverage.contains(left) => matrix
+                        .get((classes.0.get(left), classes.1.get(right)))
+                        .map(|(first, _second)| first.x_advance),
+                    _ => None,
+                };
+                if let Some(kerning) = kerning {
+                    return kerning;
+                }
+            }
+        }
+    }
+    0
 }
 
 #[cfg(test)]
 pub mod tests {
-    use crate::context::typography::Typography;
+    use crate::context::typography::{TextDirection, Typography};
     use font_kit::font::Font;
+    use geo::bounding_rect::BoundingRect;
+    use geo::centroid::Centroid;
+    use geo_types::{Geometry, Point};
     use std::sync::Arc;
 
     #[test]
@@ -174,4 +360,72 @@ pub mod tests {
             .font(&f)
             .render(&"YES: This is some text XXX".to_string(), 0.1);
     }
+
+    #[test]
+    fn test_layout_kerning() {
+        let fdata =
+            include_bytes!("../../resources/fonts/ReliefSingleLineOutline-Regular.otf").to_vec();
+        let f = Font::from_bytes(Arc::new(fdata), 0).unwrap();
+        let mut t = Typography::new();
+        t.size(10.0).font(&f);
+        let advance = |text: &str| -> f64 {
+            t.layout(text, 0.1)
+                .unwrap()
+                .iter()
+                .map(|(_c, _geo, advance)| advance)
+                .sum()
+        };
+        let glyphs = t.layout("AV", 0.1).unwrap();
+        assert_eq!(glyphs.len(), 2);
+        assert_eq!(glyphs[0].0, 'A');
+        assert!(glyphs[0].1.bounding_rect().is_some());
+        // The bundled font kerns AV in GPOS, but not AA.
+        assert!(advance("AV") < advance("A") + advance("V"));
+        assert!((advance("AA") - 2.0 * advance("A")).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_vertical() {
+        let fdata =
+            include_bytes!("../../resources/fonts/ReliefSingleLineOutline-Regular.otf").to_vec();
+        let f = Font::from_bytes(Arc::new(fdata), 0).unwrap();
+        let mut t = Typography::new();
+        t.size(10.0).font(&f).direction(TextDirection::VerticalRL);
+        let centroids = |text: &str, t: &Typography| -> Vec<Point<f64>> {
+            match t.render(&text.to_string(), 0.1).unwrap() {
+                Geometry::GeometryCollection(glyphs) => {
+                    glyphs.iter().filter_map(|glyph| glyph.centroid()).collect()
+                }
+                _ => vec![],
+            }
+        };
+        let down = centroids("HOXIW", &t);
+        assert_eq!(down.len(), 5);
+        for pair in down.windows(2) {
+            // Going down the page, which is down in font coordinates too.
+            assert!(pair[1].y() < pair[0].y() - 10.0);
+        }
+        for c in &down {
+            assert!(c.x().abs() < 3.0);
+        }
+        // A newline starts the next column over to the left, or right for LR.
+        let rl = centroids("HO\nXI", &t);
+        assert!(rl[2].x() < rl[0].x() - 10.0);
+        assert!((rl[2].y() - rl[0].y()).abs() < 1.0);
+        t.direction(TextDirection::VerticalLR);
+        let lr = centroids("HO\nXI", &t);
+        assert!(lr[2].x() > lr[0].x() + 10.0);
+    }
+
+    #[test]
+    fn test_render_centerline() {
+        let mut t = Typography::new();
+        let lines = t
+            .size(10.0)
+            .render_centerline(&"I".to_string(), 0.05)
+            .unwrap();
+        assert_eq!(lines.0.len(), 1);
+        let bounds = lines.bounding_rect().unwrap();
+        assert!(bounds.width() < bounds.height() * 0.05);
+    }
 }
@@ -20,7 +20,12 @@ impl fmt::Display for SvgCreationError {
 #[derive(Debug)]
 pub enum ContextError {
     PoppedEmptyStack,
-    SvgGenerationError(String)
+    NoOpenLayer,
+    SvgGenerationError(String),
+    SvgParseError(String),
+    EmptyContext,
+    FontLoadError(String),
+    GCodeGenerationError(String),
 }
 
 impl std::error::Error for ContextError {}
@@ -30,8 +35,53 @@ impl fmt::Display for ContextError {
         match self {
             ContextError::PoppedEmptyStack =>
                 write!(f, "Popping from an empty context stack."),
+            ContextError::NoOpenLayer =>
+                write!(f, "Ending a layer which was never begun."),
             ContextError::SvgGenerationError(msg) =>
                 write!(f, "Svg generation error: {}", msg),
+            ContextError::SvgParseError(msg) =>
+                write!(f, "Svg parse error: {}", msg),
+            ContextError::EmptyContext =>
+                write!(f, "Context has no geometry to measure."),
+            ContextError::FontLoadError(msg) =>
+                write!(f, "Font load error: {}", msg),
+            ContextError::GCodeGenerationError(msg) =>
+                write!(f, "GCode generation error: {}", msg),
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LSystemError {
+    /// Expansion got too long. Order is how many generations we got through,
+    /// and length is how long the next one came out.
+    TooLong { order: u32, length: usize },
+}
+
+impl std::error::Error for LSystemError {}
+
+impl fmt::Display for LSystemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LSystemError::TooLong { order, length } =>
+                write!(f, "L-system grew to {} symbols after order {}.", length, order),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PostError {
+    /// A point (in mm) landed outside the bed we were told about.
+    OutOfBounds { x: f64, y: f64 },
+}
+
+impl std::error::Error for PostError {}
+
+impl fmt::Display for PostError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PostError::OutOfBounds { x, y } =>
+                write!(f, "Point {},{} is outside the bed.", x, y),
+        }
+    }
+}
@@ -1,12 +1,22 @@
 //! Module which provides Line->GCode post-processing
-use geo_types::{CoordNum, MultiLineString};
+use crate::errors::PostError;
+use geo_types::{coord, CoordNum, MultiLineString, Rect};
 use tera::{Context, Tera};
+use std::collections::HashMap;
 use std::error::Error;
 use num_traits::real::Real;
 
 /// List of all available machines as an Enum
 pub enum AoerPostMachines {
     BAPv1,
+    /// CNC style machine that lifts the pen on Z instead of a servo. Pen up goes to
+    /// the [`PostOptions`] `pen_up_z` at rapid, pen down feeds to `pen_down_z` (or
+    /// the pen's `draw_z`) at `z_feed`.
+    ZLift,
+    /// Laser (or spindle) on the same frame: `M3 S{power}` to start burning, `M5`
+    /// to stop. Feeds still come from the `lineto` template, same as the others,
+    /// and a pen's `power` in [`PenConfig`] overrides this one for its layer.
+    Laser { power: u16 },
     CustomMachine(Tera),
 }
 
@@ -20,6 +30,94 @@ pub enum PostTemplateError {
 pub enum PostGeometrySource<T>
     where T: CoordNum, T: Real{
     MultiLineString(MultiLineString<T>),
+    /// A bunch of layers, each tagged with the pen (color) that draws it. The same
+    /// string is the key into the [`PenConfig`] map. You get a `toolchange`
+    /// wherever the pen changes from one layer to the next, so consecutive layers
+    /// in the same color (or on the same `tool`) just carry on.
+    Layers(Vec<(String, MultiLineString<T>)>),
+}
+
+/// Per-pen overrides for multi-tool programs. Anything left as None falls
+/// back to whatever the machine template defaults to. The `draw_z` is
+/// handed to the `pendown` template as `pen_down_z`, `feed` to `lineto`
+/// as `feed`, `power` (for lasers) to `pendown` as `power`, and `tool`
+/// to `toolchange` as `tool`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PenConfig {
+    pub draw_z: Option<f64>,
+    pub feed: Option<f64>,
+    pub power: Option<u16>,
+    pub tool: Option<usize>,
+}
+
+/// What units the program is in. Geometry is always mm on our side, so for inches
+/// the coordinates get converted on the way out and the prelude says G20.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Units {
+    #[default]
+    Millimeters,
+    Inches,
+}
+
+impl Units {
+    fn scale(&self) -> f64 {
+        match self {
+            Units::Millimeters => 1.0,
+            Units::Inches => 1.0 / 25.4,
+        }
+    }
+}
+
+/// Knobs for the whole program. Everything here goes into the template context,
+/// `draw_feed` as `feed` (unless a [`PenConfig`] overrides it), `travel_feed` as
+/// `travel_feed` (left out when None, so travel is the machine's rapid) and the
+/// units as `units` ("mm" or "in"). Feeds are passed through in whatever units
+/// per minute you picked. The built in machines round coordinates to `precision`
+/// decimal places, and two places of an inch is pretty coarse, so bump it up if
+/// you're working in inches. Z lift machines also get `pen_up_z`, `pen_down_z` and
+/// `z_feed` (the plunge feed), which go through as-is, in the program's units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostOptions {
+    pub draw_feed: f64,
+    pub travel_feed: Option<f64>,
+    pub units: Units,
+    pub precision: usize,
+    pub pen_up_z: f64,
+    pub pen_down_z: f64,
+    pub z_feed: f64,
+}
+
+impl Default for PostOptions {
+    fn default() -> Self {
+        PostOptions {
+            draw_feed: 1200.0,
+            travel_feed: None,
+            units: Units::Millimeters,
+            precision: 2,
+            pen_up_z: 5.0,
+            pen_down_z: -1.0,
+            z_feed: 300.0,
+        }
+    }
+}
+
+impl PostOptions {
+    fn context(&self) -> Context {
+        let mut context = Context::new();
+        context.insert("feed", &self.draw_feed);
+        if let Some(travel_feed) = self.travel_feed {
+            context.insert("travel_feed", &travel_feed);
+        }
+        context.insert("precision", &self.precision);
+        context.insert("pen_up_z", &self.pen_up_z);
+        context.insert("pen_down_z", &self.pen_down_z);
+        context.insert("z_feed", &self.z_feed);
+        context.insert("units", match self.units {
+            Units::Millimeters => "mm",
+            Units::Inches => "in",
+        });
+        context
+    }
 }
 
 
@@ -33,16 +131,51 @@ impl AoerPostMachines {
         match machine {
             AoerPostMachines::BAPv1 => {
                 bap_post_template.add_raw_templates(vec![
-                    ("prelude", "M280 S5\nG4 P150\nG28 X Y\nG90\n G92 X0 Y0 ; HOME"),
+                    ("prelude", "M280 S5\nG4 P150\nG28 X Y\nG90\n{% if units == 'in' %}G20\n{% endif %} G92 X0 Y0 ; HOME"),
                     ("epilog", "M280 S5\nG4 P150\nG0 X0 Y230\nM281 ; FINISHED"),
                     ("penup", "M400\nM280 S9\nG4 P150\nM400\nM281 ; PENUP"),
                     ("pendown", "M400\nM280 S12\nG4 P250\nM400 ; PENDOWN"),
-                    ("moveto", "G0 X{{xmm|round(precision=2)}} Y{{ymm|round(precision=2)}} ; NEW LINE START"),
-                    ("lineto", "G01 F1200 X{{xmm|round(precision=2)}} Y{{ymm|round(precision=2)}}"),
+                    ("moveto", "G0{% if travel_feed %} F{{travel_feed}}{% endif %} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} ; NEW LINE START"),
+                    ("lineto", "G01 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}}"),
+                    ("arc_cw", "G02 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} I{{imm|round(precision=precision)}} J{{jmm|round(precision=precision)}}"),
+                    ("arc_ccw", "G03 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} I{{imm|round(precision=precision)}} J{{jmm|round(precision=precision)}}"),
+                    ("toolchange", "M0 ; change pen {{color}}"),
                 ]).unwrap();
                 Ok(bap_post_template)
             }
-            _ => Err(PostTemplateError::NoSuchTemplateError)
+            AoerPostMachines::ZLift => {
+                let mut zlift_post_template = Tera::default();
+                zlift_post_template.add_raw_templates(vec![
+                    ("prelude", "{% if units == 'in' %}G20{% else %}G21{% endif %}\nG90\nG0 Z{{pen_up_z}}\nG28 X Y\nG92 X0 Y0 ; HOME"),
+                    ("epilog", "G0 Z{{pen_up_z}}\nG0 X0 Y0 ; FINISHED"),
+                    ("penup", "G0 Z{{pen_up_z}} ; PENUP"),
+                    ("pendown", "G1 Z{{pen_down_z}} F{{z_feed}} ; PENDOWN"),
+                    ("moveto", "G0{% if travel_feed %} F{{travel_feed}}{% endif %} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} ; NEW LINE START"),
+                    ("lineto", "G1 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}}"),
+                    ("arc_cw", "G2 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} I{{imm|round(precision=precision)}} J{{jmm|round(precision=precision)}}"),
+                    ("arc_ccw", "G3 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} I{{imm|round(precision=precision)}} J{{jmm|round(precision=precision)}}"),
+                    ("toolchange", "G0 Z{{pen_up_z}}\nM0 ; change pen {{color}}"),
+                ]).unwrap();
+                Ok(zlift_post_template)
+            }
+            AoerPostMachines::Laser { power } => {
+                let mut laser_post_template = Tera::default();
+                laser_post_template.add_raw_templates(vec![
+                    ("prelude", "M5\n{% if units == 'in' %}G20{% else %}G21{% endif %}\nG90\nG28 X Y\nG92 X0 Y0 ; HOME"),
+                    ("epilog", "M5\nG0 X0 Y0 ; FINISHED"),
+                    ("penup", "M5 ; LASER OFF"),
+                    ("moveto", "G0{% if travel_feed %} F{{travel_feed}}{% endif %} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} ; NEW LINE START"),
+                    ("lineto", "G1 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}}"),
+                    ("arc_cw", "G2 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} I{{imm|round(precision=precision)}} J{{jmm|round(precision=precision)}}"),
+                    ("arc_ccw", "G3 F{{feed|default(value=1200)}} X{{xmm|round(precision=precision)}} Y{{ymm|round(precision=precision)}} I{{imm|round(precision=precision)}} J{{jmm|round(precision=precision)}}"),
+                    ("toolchange", "M5\nM0 ; change layer {{color}}"),
+                ]).unwrap();
+                laser_post_template.add_raw_template(
+                    "pendown",
+                    &format!("M3 S{{{{power|default(value={})}}}} ; LASER ON", power)).unwrap();
+                Ok(laser_post_template)
+            }
+            AoerPostMachines::CustomMachine(template) => Ok(template),
         }
     }
 }
@@ -50,56 +183,484 @@ impl AoerPostMachines {
 
 /// Given a set of lines, gcode-process and generate GCode
 /// Returns either a list of gcode lines, or a box'd dyn error
-/// for what went wrong.
-pub fn post<T>(lines: &PostGeometrySource<T>, post_template: &Tera)
+/// for what went wrong. `PostOptions::default()` gets you the template's
+/// usual feeds, in mm.
+pub fn post<T>(lines: &PostGeometrySource<T>, post_template: &Tera, options: &PostOptions)
                -> Result<Vec<String>, Box<dyn Error>>
     where T: CoordNum, T: Real {
+    post_with_pens(lines, post_template, &HashMap::new(), options)
+}
+
+/// Same as [`post`], but each layer of a [`PostGeometrySource::Layers`] gets
+/// the [`PenConfig`] matching its pen (if there is one), so a fat marker can
+/// go slower than a fineliner.
+pub fn post_with_pens<T>(lines: &PostGeometrySource<T>, post_template: &Tera,
+                         pens: &HashMap<String, PenConfig>, options: &PostOptions)
+                         -> Result<Vec<String>, Box<dyn Error>>
+    where T: CoordNum, T: Real {
+    post_with_arcs(lines, post_template, pens, options, None)
+}
+
+/// An arc we found in a polyline: where it ends (as a point index), the center
+/// and whether it goes clockwise.
+struct ArcFit {
+    end: usize,
+    center: (f64, f64),
+    clockwise: bool,
+}
+
+/// Circle through three points, or None if they're (near enough) in a line.
+fn circumcenter(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Option<(f64, f64)> {
+    let d = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    let (a2, b2, c2) = (a.0 * a.0 + a.1 * a.1, b.0 * b.0 + b.1 * b.1, c.0 * c.0 + c.1 * c.1);
+    Some((
+        (a2 * (b.1 - c.1) + b2 * (c.1 - a.1) + c2 * (a.1 - b.1)) / d,
+        (a2 * (c.0 - b.0) + b2 * (a.0 - c.0) + c2 * (b.0 - a.0)) / d,
+    ))
+}
+
+/// Checks whether points[start..=end] all sit on one circle (within tolerance),
+/// turning the same way the whole time, and sweeping no more than half a turn so
+/// the controller can't get confused about which way round to go. Any three points
+/// are on some circle, so it takes at least four, and every segment has to hug the
+/// arc (its sagitta within tolerance), otherwise a plain corner would get rounded
+/// off. Points that are all within tolerance of a straight line don't count either;
+/// those stay as G01s.
+fn fit_arc(points: &[(f64, f64)], start: usize, end: usize, tolerance: f64) -> Option<ArcFit> {
+    if end < start + 3 {
+        return None;
+    }
+    let (a, c) = (points[start], points[end]);
+    let center = circumcenter(a, points[(start + end) / 2], c)?;
+    let radius = (a.0 - center.0).hypot(a.1 - center.1);
+    let chord = (c.0 - a.0).hypot(c.1 - a.1);
+    let mut bulge: f64 = 0.0;
+    let mut sweep = 0.0;
+    for k in start..=end {
+        let p = points[k];
+        if ((p.0 - center.0).hypot(p.1 - center.1) - radius).abs() > tolerance {
+            return None;
+        }
+        if chord > 0.0 {
+            let off_chord = ((c.0 - a.0) * (p.1 - a.1) - (c.1 - a.1) * (p.0 - a.0)) / chord;
+            bulge = bulge.max(off_chord.abs());
+        }
+        if k > start {
+            let q = points[k - 1];
+            let half = (p.0 - q.0).hypot(p.1 - q.1) / 2.0;
+            if half > radius || radius - (radius * radius - half * half).sqrt() > tolerance {
+                return None;
+            }
+            let step = ((q.0 - center.0) * (p.1 - center.1) - (q.1 - center.1) * (p.0 - center.0))
+                .atan2((q.0 - center.0) * (p.0 - center.0) + (q.1 - center.1) * (p.1 - center.1));
+            if step == 0.0 || (sweep != 0.0 && step.signum() != f64::signum(sweep)) {
+                return None;
+            }
+            sweep += step;
+        }
+    }
+    if bulge <= tolerance || sweep.abs() > std::f64::consts::PI + 1e-9 {
+        return None;
+    }
+    Some(ArcFit { end, center, clockwise: sweep < 0.0 })
+}
+
+/// Same as [`post_with_pens`], but with an `arc_tolerance` (in mm) any run of four
+/// or more points that sits on a circle comes out as a single `arc_cw`/`arc_ccw`
+/// (G02/G03) with I/J center offsets instead of a pile of G01s. Templates without
+/// the arc commands just get lines, same as when the tolerance is None.
+pub fn post_with_arcs<T>(lines: &PostGeometrySource<T>, post_template: &Tera,
+                         pens: &HashMap<String, PenConfig>, options: &PostOptions,
+                         arc_tolerance: Option<f64>)
+                         -> Result<Vec<String>, Box<dyn Error>>
+    where T: CoordNum, T: Real {
+    let scale = options.units.scale();
+    let base_context = options.context();
+    let arc_tolerance = arc_tolerance.filter(|_| {
+        post_template.get_template("arc_cw").is_ok()
+            && post_template.get_template("arc_ccw").is_ok()
+    });
     let mut program: Vec<String> = Vec::new();
-    // This currently only allows for a single enum subtype, but that's all we have
-    // for now. Eventually we'll expand this to include a variety of subtypes, like
-    // svg2polyline lines, and even multi-layers with separate tools.
-    let lines = match lines{
-        PostGeometrySource::MultiLineString(lines) => lines
+    let layers: Vec<(Option<&String>, PenConfig, &MultiLineString<T>)> = match lines {
+        PostGeometrySource::MultiLineString(lines) => vec![(None, PenConfig::default(), lines)],
+        PostGeometrySource::Layers(layers) => layers
+            .iter()
+            .map(|(pen, lines)| (Some(pen), pens.get(pen).copied().unwrap_or_default(), lines))
+            .collect(),
     };
+    // Custom machines that predate tool changes won't have the template, so skip it.
+    let can_toolchange = post_template.get_template("toolchange").is_ok();
     program.extend(
-        post_template.render("prelude", &Context::new())?
+        post_template.render("prelude", &base_context)?
             .split("\n").map(|s| s.to_string()));
-    for line in lines.iter() {
-        program.extend(post_template.render("penup", &Context::new())?
-            .split("\n")
-            .map(|s| s.to_string()));
-        let mut context = Context::new();
-        context.insert("xmm", &line[0].x.to_f64().unwrap());
-        context.insert("ymm", &line[0].y.to_f64().unwrap());
-        program.extend(
-            post_template.render("moveto", &context)?
+    let mut last_pen: Option<(&String, PenConfig)> = None;
+    for (color, pen, lines) in layers {
+        let pen_changed = match (color, last_pen) {
+            (Some(color), Some((last_color, last_config))) => match (pen.tool, last_config.tool) {
+                (Some(tool), Some(last_tool)) => tool != last_tool,
+                _ => color != last_color,
+            },
+            _ => false,
+        };
+        last_pen = color.map(|color| (color, pen));
+        if let Some(color) = color.filter(|_| pen_changed && can_toolchange) {
+            program.extend(post_template.render("penup", &base_context)?
                 .split("\n")
                 .map(|s| s.to_string()));
-
-        program.extend(post_template.render("pendown", &Context::new())?
-            .split("\n")
-            .map(|s| s.to_string()));
-        for point in line.points().skip(1) {
-            let mut context = Context::new();
-            context.insert("xmm", &point.x().to_f64().unwrap());
-            context.insert("ymm", &point.y().to_f64().unwrap());
+            let mut context = base_context.clone();
+            context.insert("color", color);
+            if let Some(tool) = pen.tool {
+                context.insert("tool", &tool);
+            }
+            program.extend(post_template.render("toolchange", &context)?
+                .split("\n")
+                .map(|s| s.to_string()));
+        }
+        let mut pen_context = base_context.clone();
+        if let Some(z) = pen.draw_z {
+            pen_context.insert("pen_down_z", &z);
+        }
+        if let Some(feed) = pen.feed {
+            pen_context.insert("feed", &feed);
+        }
+        if let Some(power) = pen.power {
+            pen_context.insert("power", &power);
+        }
+        for line in lines.iter() {
+            program.extend(post_template.render("penup", &base_context)?
+                .split("\n")
+                .map(|s| s.to_string()));
+            let mut context = base_context.clone();
+            context.insert("xmm", &(line[0].x.to_f64().unwrap() * scale));
+            context.insert("ymm", &(line[0].y.to_f64().unwrap() * scale));
             program.extend(
-                post_template.render("lineto", &context)?
-                    .split("\n").map(|s| s.to_string()));
+                post_template.render("moveto", &context)?
+                    .split("\n")
+                    .map(|s| s.to_string()));
+
+            program.extend(post_template.render("pendown", &pen_context)?
+                .split("\n")
+                .map(|s| s.to_string()));
+            let points: Vec<(f64, f64)> = line.points()
+                .map(|p| (p.x().to_f64().unwrap(), p.y().to_f64().unwrap()))
+                .collect();
+            let mut i = 0;
+            while i + 1 < points.len() {
+                let mut arc: Option<ArcFit> = None;
+                if let Some(tolerance) = arc_tolerance {
+                    for end in i + 3..points.len() {
+                        match fit_arc(&points, i, end, tolerance) {
+                            Some(fit) => arc = Some(fit),
+                            None => break,
+                        }
+                    }
+                }
+                let mut context = pen_context.clone();
+                let (template, next) = match arc {
+                    Some(fit) => {
+                        context.insert("imm", &((fit.center.0 - points[i].0) * scale));
+                        context.insert("jmm", &((fit.center.1 - points[i].1) * scale));
+                        (if fit.clockwise { "arc_cw" } else { "arc_ccw" }, fit.end)
+                    }
+                    None => ("lineto", i + 1),
+                };
+                context.insert("xmm", &(points[next].0 * scale));
+                context.insert("ymm", &(points[next].1 * scale));
+                program.extend(
+                    post_template.render(template, &context)?
+                        .split("\n").map(|s| s.to_string()));
+                i = next;
+            }
         }
     }
     program.extend(
-        post_template.render("epilog", &Context::new())?
+        post_template.render("epilog", &base_context)?
             .split("\n").map(|s| s.to_string()));
     Ok(program)
 }
 
+/// Same as [`post`], but first makes sure everything fits on a `bed` (in mm) and
+/// bails with [`PostError::OutOfBounds`] if it doesn't. With `trace` on, right
+/// after the prelude the pen goes (up) around the bounding box of the drawing, so
+/// you can see where it'll land before committing ink.
+pub fn post_with_bed<T>(lines: &PostGeometrySource<T>, post_template: &Tera,
+                        options: &PostOptions, bed: Rect<f64>, trace: bool)
+                        -> Result<Vec<String>, Box<dyn Error>>
+    where T: CoordNum, T: Real {
+    let all_lines: Vec<&MultiLineString<T>> = match lines {
+        PostGeometrySource::MultiLineString(lines) => vec![lines],
+        PostGeometrySource::Layers(layers) => layers.iter().map(|(_, lines)| lines).collect(),
+    };
+    let mut bounds: Option<Rect<f64>> = None;
+    for point in all_lines.iter().flat_map(|lines| lines.iter()).flat_map(|line| line.points()) {
+        let (x, y) = (point.x().to_f64().unwrap(), point.y().to_f64().unwrap());
+        if x < bed.min().x || x > bed.max().x || y < bed.min().y || y > bed.max().y {
+            return Err(Box::new(PostError::OutOfBounds { x, y }));
+        }
+        bounds = Some(match bounds {
+            None => Rect::new(coord! {x: x, y: y}, coord! {x: x, y: y}),
+            Some(b) => Rect::new(
+                coord! {x: b.min().x.min(x), y: b.min().y.min(y)},
+                coord! {x: b.max().x.max(x), y: b.max().y.max(y)}),
+        });
+    }
+    let mut program = post(lines, post_template, options)?;
+    if let Some(bounds) = bounds.filter(|_| trace) {
+        let base_context = options.context();
+        let scale = options.units.scale();
+        let mut dry_run: Vec<String> = post_template.render("penup", &base_context)?
+            .split("\n").map(|s| s.to_string()).collect();
+        let (min, max) = (bounds.min(), bounds.max());
+        for (x, y) in [(min.x, min.y), (max.x, min.y), (max.x, max.y), (min.x, max.y),
+            (min.x, min.y)] {
+            let mut context = base_context.clone();
+            context.insert("xmm", &(x * scale));
+            context.insert("ymm", &(y * scale));
+            dry_run.extend(post_template.render("moveto", &context)?
+                .split("\n").map(|s| s.to_string()));
+        }
+        let prelude_len = post_template.render("prelude", &base_context)?
+            .split("\n").count();
+        program.splice(prelude_len..prelude_len, dry_run);
+    }
+    Ok(program)
+}
+
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashMap;
     use std::iter::zip;
     use geo_types::{coord, LineString, MultiLineString};
-    use crate::gcode::{AoerPostMachines, post, PostGeometrySource};
+    use tera::Tera;
+    use crate::errors::PostError;
+    use crate::gcode::{AoerPostMachines, post, post_with_arcs, post_with_bed, post_with_pens,
+                       PenConfig, PostGeometrySource, PostOptions, Units};
+    use geo_types::Rect;
+
+    #[test]
+    fn test_post_laser() {
+        let post_template =
+            AoerPostMachines::get_machine(AoerPostMachines::Laser { power: 800 }).unwrap();
+        let line = |y: f64| MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: y}, coord! {x: 10.0, y: y}])]);
+        let mut pens = HashMap::new();
+        pens.insert("etch".to_string(), PenConfig { power: Some(200), ..Default::default() });
+        let program = post_with_pens(
+            &PostGeometrySource::Layers(vec![
+                ("cut".to_string(), line(0.0)),
+                ("etch".to_string(), line(5.0))]),
+            &post_template, &pens, &PostOptions::default()).unwrap();
+        assert!(!program.iter().any(|l| l.starts_with("M280")));
+        let draws: Vec<&str> = program.iter()
+            .filter(|l| l.starts_with("M3 ") || l.starts_with("M5 ") || l.starts_with("G1 "))
+            .map(|l| l.as_str())
+            .collect();
+        assert_eq!(draws, vec![
+            "M5 ; LASER OFF", "M3 S800 ; LASER ON", "G1 F1200 X10 Y0",
+            "M5 ; LASER OFF", "M5 ; LASER OFF", "M3 S200 ; LASER ON", "G1 F1200 X10 Y5"]);
+        assert_eq!(program.last().unwrap(), "G0 X0 Y0 ; FINISHED");
+    }
+
+    #[test]
+    fn test_post_with_bed() {
+        let post_template = AoerPostMachines::get_machine(AoerPostMachines::BAPv1)
+            .unwrap();
+        let lines = PostGeometrySource::MultiLineString(MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 10.0, y: 20.0}, coord! {x: 30.0, y: 40.0}])]));
+        let bed = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 35.0, y: 35.0});
+        let err = post_with_bed(&lines, &post_template, &PostOptions::default(), bed, false)
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<PostError>(),
+                   Some(&PostError::OutOfBounds { x: 30.0, y: 40.0 }));
+
+        let bed = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 200.0, y: 200.0});
+        let plain = post_with_bed(&lines, &post_template, &PostOptions::default(), bed, false)
+            .unwrap();
+        assert_eq!(plain, post(&lines, &post_template, &PostOptions::default()).unwrap());
+        let traced = post_with_bed(&lines, &post_template, &PostOptions::default(), bed, true)
+            .unwrap();
+        let home = traced.iter().position(|l| l.ends_with("; HOME")).unwrap();
+        assert_eq!(traced[home + 1], "M400");
+        let moves: Vec<&String> = traced[home + 1..home + 11].iter()
+            .filter(|l| l.starts_with("G0 ")).collect();
+        assert_eq!(moves, vec![
+            "G0 X10 Y20 ; NEW LINE START", "G0 X30 Y20 ; NEW LINE START",
+            "G0 X30 Y40 ; NEW LINE START", "G0 X10 Y40 ; NEW LINE START",
+            "G0 X10 Y20 ; NEW LINE START"]);
+        assert_eq!(traced.len(), plain.len() + 10);
+    }
+
+    #[test]
+    fn test_post_options() {
+        let post_template = AoerPostMachines::get_machine(AoerPostMachines::BAPv1)
+            .unwrap();
+        let lines = PostGeometrySource::MultiLineString(MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 25.4, y: 0.0}])]));
+        let options = PostOptions { draw_feed: 600.0, ..PostOptions::default() };
+        let program = post(&lines, &post_template, &options).unwrap();
+        let draws: Vec<&String> = program.iter().filter(|l| l.starts_with("G01")).collect();
+        assert_eq!(draws, vec!["G01 F600 X25.4 Y0"]);
+        let options = PostOptions { units: Units::Inches, ..options };
+        let program = post(&lines, &post_template, &options).unwrap();
+        assert!(program.iter().any(|l| l == "G20"));
+        assert!(program.iter().any(|l| l == "G01 F600 X1 Y0"));
+        let thou = PostGeometrySource::MultiLineString(MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 25.4 * 1.001, y: 0.0}])]));
+        let program = post(&thou, &post_template, &options).unwrap();
+        assert!(program.iter().any(|l| l == "G01 F600 X1 Y0"));
+        let options = PostOptions { precision: 4, ..options };
+        let program = post(&thou, &post_template, &options).unwrap();
+        assert!(program.iter().any(|l| l == "G01 F600 X1.001 Y0"));
+        let options = PostOptions { travel_feed: Some(3000.0), ..PostOptions::default() };
+        let program = post(&lines, &post_template, &options).unwrap();
+        assert!(program.iter().any(|l| l == "G0 F3000 X0 Y0 ; NEW LINE START"));
+    }
+
+    #[test]
+    fn test_post_zlift() {
+        let post_template = AoerPostMachines::get_machine(AoerPostMachines::ZLift)
+            .unwrap();
+        let lines = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0}]),
+            LineString::new(vec![coord! {x: 0.0, y: 5.0}, coord! {x: 10.0, y: 5.0}])]);
+        let program = post(&PostGeometrySource::MultiLineString(lines), &post_template,
+                           &PostOptions::default()).unwrap();
+        let start = program.iter().position(|l| l.ends_with("; HOME")).unwrap() + 1;
+        let end = program.iter().position(|l| l.ends_with("; FINISHED")).unwrap() - 1;
+        assert_eq!(&program[start..end], &[
+            "G0 Z5 ; PENUP", "G0 X0 Y0 ; NEW LINE START", "G1 Z-1 F300 ; PENDOWN",
+            "G1 F1200 X10 Y0",
+            "G0 Z5 ; PENUP", "G0 X0 Y5 ; NEW LINE START", "G1 Z-1 F300 ; PENDOWN",
+            "G1 F1200 X10 Y5"]);
+        // Custom machines are handed straight back.
+        let mut custom = Tera::default();
+        custom.add_raw_template("prelude", "; MINE").unwrap();
+        let custom = AoerPostMachines::get_machine(AoerPostMachines::CustomMachine(custom))
+            .unwrap();
+        assert_eq!(custom.render("prelude", &tera::Context::new()).unwrap(), "; MINE");
+
+        // The heights and plunge feed come from the options, and a pen can override
+        // how far down it goes.
+        let lines = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0}])]);
+        let options = PostOptions { pen_up_z: 12.5, z_feed: 150.0, ..PostOptions::default() };
+        let mut pens = HashMap::new();
+        pens.insert("black".to_string(), PenConfig { draw_z: Some(-0.5), ..Default::default() });
+        let program = post_with_pens(
+            &PostGeometrySource::Layers(vec![("black".to_string(), lines)]),
+            &post_template, &pens, &options).unwrap();
+        assert!(program.iter().any(|l| l == "G0 Z12.5 ; PENUP"));
+        assert!(program.iter().any(|l| l == "G1 Z-0.5 F150 ; PENDOWN"));
+        assert!(!program.iter().any(|l| l.contains("Z5")));
+    }
+
+    #[test]
+    fn test_post_arcs() {
+        let post_template = AoerPostMachines::get_machine(AoerPostMachines::BAPv1)
+            .unwrap();
+        let circle = LineString::new((0..=72).map(|i| {
+            let angle = std::f64::consts::PI * 2.0 * f64::from(i) / 72.0;
+            coord! {x: 50.0 + 10.0 * angle.cos(), y: 50.0 + 10.0 * angle.sin()}
+        }).collect());
+        let lines = PostGeometrySource::MultiLineString(MultiLineString::new(vec![
+            circle,
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 5.0, y: 0.0},
+                                 coord! {x: 10.0, y: 0.0}])]));
+        let program = post_with_arcs(&lines, &post_template, &HashMap::new(),
+                                     &PostOptions::default(), Some(0.01))
+            .unwrap();
+        let arcs: Vec<&String> = program.iter().filter(|l| l.starts_with("G03")).collect();
+        assert!(!arcs.is_empty() && arcs.len() <= 4);
+        assert!(!program.iter().any(|l| l.starts_with("G02")));
+        assert_eq!(arcs[0], "G03 F1200 X40 Y50 I-10 J0");
+        // Straight lines never turn into arcs, and by default nothing does.
+        assert_eq!(program.iter().filter(|l| l.starts_with("G01")).count(), 2);
+        let linear = post(&lines, &post_template, &PostOptions::default()).unwrap();
+        assert_eq!(linear.iter().filter(|l| l.starts_with("G01")).count(), 74);
+    }
+
+    #[test]
+    fn test_post_arcs_corners() {
+        let post_template = AoerPostMachines::get_machine(AoerPostMachines::BAPv1)
+            .unwrap();
+        // The corners of a square all sit on a circle, but they're still corners.
+        let lines = PostGeometrySource::MultiLineString(MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0},
+                                 coord! {x: 10.0, y: 10.0}]),
+            LineString::new(vec![coord! {x: 20.0, y: 0.0}, coord! {x: 30.0, y: 0.0},
+                                 coord! {x: 30.0, y: 10.0}, coord! {x: 20.0, y: 10.0},
+                                 coord! {x: 20.0, y: 0.0}])]));
+        let program = post_with_arcs(&lines, &post_template, &HashMap::new(),
+                                     &PostOptions::default(), Some(0.5))
+            .unwrap();
+        assert!(!program.iter().any(|l| l.starts_with("G02") || l.starts_with("G03")));
+        assert_eq!(program.iter().filter(|l| l.starts_with("G01")).count(), 6);
+    }
+
+    #[test]
+    fn test_post_pen_feeds() {
+        let post_template = AoerPostMachines::get_machine(AoerPostMachines::BAPv1)
+            .unwrap();
+        let line = |y: f64| MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 0.0, y: y},
+            coord! {x: 10.0, y: y}])]);
+        let mut pens = HashMap::new();
+        pens.insert("black".to_string(), PenConfig { feed: Some(600.0), ..Default::default() });
+        pens.insert("red".to_string(), PenConfig { feed: Some(2400.0), ..Default::default() });
+        let program = post_with_pens(
+            &PostGeometrySource::Layers(vec![
+                ("black".to_string(), line(0.0)),
+                ("red".to_string(), line(5.0))]),
+            &post_template, &pens, &PostOptions::default()).unwrap();
+        let draws: Vec<&String> = program.iter().filter(|l| l.starts_with("G01")).collect();
+        assert_eq!(draws, vec!["G01 F600 X10 Y0", "G01 F2400 X10 Y5"]);
+    }
+
+    #[test]
+    fn test_post_toolchange() {
+        let post_template = AoerPostMachines::get_machine(AoerPostMachines::BAPv1)
+            .unwrap();
+        let line = |y: f64| MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: y}, coord! {x: 10.0, y: y}]),
+            LineString::new(vec![coord! {x: 0.0, y: y + 1.0}, coord! {x: 10.0, y: y + 1.0}])]);
+        let program = post(&PostGeometrySource::Layers(vec![
+            ("black".to_string(), line(0.0)),
+            ("#ff0000".to_string(), line(5.0))]), &post_template, &PostOptions::default()).unwrap();
+        let changes: Vec<&String> = program.iter().filter(|l| l.starts_with("M0 ")).collect();
+        assert_eq!(changes, vec!["M0 ; change pen #ff0000"]);
+    }
+
+    #[test]
+    fn test_post_toolchange_same_pen() {
+        let post_template = AoerPostMachines::get_machine(AoerPostMachines::BAPv1)
+            .unwrap();
+        let line = |y: f64| MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 0.0, y: y},
+            coord! {x: 10.0, y: y}])]);
+        let program = post(&PostGeometrySource::Layers(vec![
+            ("black".to_string(), line(0.0)),
+            ("black".to_string(), line(5.0)),
+            ("red".to_string(), line(10.0)),
+            ("red".to_string(), line(15.0)),
+            ("black".to_string(), line(20.0))]), &post_template, &PostOptions::default())
+            .unwrap();
+        let changes: Vec<&String> = program.iter().filter(|l| l.starts_with("M0 ")).collect();
+        assert_eq!(changes, vec!["M0 ; change pen red", "M0 ; change pen black"]);
+
+        // Two colors loaded as the same physical tool don't need a swap either.
+        let mut pens = HashMap::new();
+        pens.insert("black".to_string(), PenConfig { tool: Some(1), ..Default::default() });
+        pens.insert("#000".to_string(), PenConfig { tool: Some(1), ..Default::default() });
+        let program = post_with_pens(&PostGeometrySource::Layers(vec![
+            ("black".to_string(), line(0.0)),
+            ("#000".to_string(), line(5.0))]), &post_template, &pens, &PostOptions::default())
+            .unwrap();
+        assert!(!program.iter().any(|l| l.starts_with("M0 ")));
+    }
 
     #[test]
     fn test_post() {
@@ -108,8 +669,8 @@ mod test {
         let lines = MultiLineString::new(vec![LineString::new(vec![
             coord! {x: 0.0, y: 0.0},
             coord! {x: 10.0, y: 0.0}])]);
-        let program = post(&PostGeometrySource::MultiLineString(lines), &post_template)
-            .unwrap();
+        let program = post(&PostGeometrySource::MultiLineString(lines), &post_template,
+                           &PostOptions::default()).unwrap();
         let pairs: Vec<(String, String)> = zip(program, vec!["M280 S5", "G4 P150", "G28 X Y",
                                                              "G90", " G92 X0 Y0 ; HOME", "M400",
                                                              "M280 S9", "G4 P150", "M400",
@@ -1,5 +1,5 @@
 use std::error::Error;
-use geo_types::Geometry;
+use geo_types::{Geometry, GeometryCollection};
 use geos::Geom;
 use crate::geo_types::ToGeos;
 
@@ -16,10 +16,23 @@ pub trait BooleanOp
     /// Returns only the portion of self that overlaps other
     fn intersection(&self, other: &Self) -> Result<Self, Box<dyn Error>>;
 
+    /// Everything in either self or other, but not both (XOR)
+    fn symmetric_difference(&self, other: &Self) -> Result<Self, Box<dyn Error>>;
+
     /// Unary union; faster method of unioning a whole geometry collection
     fn unary_union(&self) -> Result<Self, Box<dyn Error>>;
 }
 
+/// Shapes that don't overlap (or cancel out) give GEOS empties, which don't always
+/// convert back, so those come out as an empty GeometryCollection.
+pub(crate) fn non_empty(geos_geom: geos::Geometry) -> Result<Geometry<f64>, Box<dyn Error>> {
+    if geos_geom.is_empty()? {
+        Ok(Geometry::GeometryCollection(GeometryCollection::new_from(vec![])))
+    } else {
+        Ok(Geometry::try_from(geos_geom)?)
+    }
+}
+
 impl BooleanOp for Geometry<f64> {
     fn difference(&self, other: &Self) -> Result<Self, Box<dyn Error>> {
         let geos_self = self.to_geos()?;
@@ -36,7 +49,13 @@ impl BooleanOp for Geometry<f64> {
     fn intersection(&self, other: &Self) -> Result<Self, Box<dyn Error>> {
         let geos_self = self.to_geos()?;
         let geos_other = other.to_geos()?;
-        Ok(Geometry::try_from(geos_self.intersection(&geos_other)?)?)
+        non_empty(geos_self.intersection(&geos_other)?)
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Result<Self, Box<dyn Error>> {
+        let geos_self = self.to_geos()?;
+        let geos_other = other.to_geos()?;
+        non_empty(geos_self.sym_difference(&geos_other)?)
     }
 
     fn unary_union(&self) -> Result<Self, Box<dyn Error>> {
@@ -47,3 +66,34 @@ impl BooleanOp for Geometry<f64> {
 
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geo_types::shapes::circle;
+    use geo::area::Area;
+    use geo_types::{coord, Rect};
+
+    #[test]
+    fn test_intersection() {
+        let lens = circle(0.0, 0.0, 10.0).intersection(&circle(10.0, 0.0, 10.0)).unwrap();
+        // Two overlapping circles make a lens, about 123 for r=10 at d=10
+        let area = lens.unsigned_area();
+        assert!(area > 100.0 && area < 140.0);
+        let apart = circle(0.0, 0.0, 1.0).intersection(&circle(10.0, 0.0, 1.0)).unwrap();
+        assert_eq!(apart.unsigned_area(), 0.0);
+    }
+
+    #[test]
+    fn test_symmetric_difference() {
+        let square = |x: f64| {
+            Geometry::Polygon(
+                Rect::new(coord! {x: x, y: 0.0}, coord! {x: x + 10.0, y: 10.0}).to_polygon(),
+            )
+        };
+        let empty = square(0.0).symmetric_difference(&square(0.0)).unwrap();
+        assert_eq!(empty.unsigned_area(), 0.0);
+        // Overlapping halves cancel out, leaving two 5x10 strips
+        let strips = square(0.0).symmetric_difference(&square(5.0)).unwrap();
+        assert!((strips.unsigned_area() - 100.0).abs() < 1e-6);
+    }
+}
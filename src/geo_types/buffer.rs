@@ -1,7 +1,18 @@
 use std::error::Error;
-use geo_types::{Geometry, MultiPolygon};
+use geo::convex_hull::ConvexHull;
+use geo_types::{coord, Coordinate, Geometry, GeometryCollection, LineString, MultiLineString,
+                MultiPoint, MultiPolygon, Polygon};
 use geos::Geom;
 use crate::geo_types::flatten::FlattenPolygons;
+use crate::geo_types::ToGeos;
+
+/// How the open ends of a buffered line are finished. Same deal as the SVG
+/// `stroke-linecap`: Round, Flat (butt) or Square.
+pub use geos::CapStyle;
+
+/// How corners of a buffered line are finished. Same deal as the SVG
+/// `stroke-linejoin`: Round, Mitre or Bevel.
+pub use geos::JoinStyle;
 
 /// #Buffer
 ///
@@ -38,4 +49,150 @@ impl Buffer for Geometry<f64> {
         // flatten_gt_geom_to_multipolygon(&gt_out)
         gt_out.flatten_polys()
     }
-}
\ No newline at end of file
+}
+
+/// #BufferCapped
+///
+/// Like [`Buffer`], but for open lines where you care what happens at the ends.
+/// A real pen leaves a round blob, but sometimes you want flat or square ends to
+/// match an SVG `stroke-linecap`. Use buffer_styled if you care about the corners too.
+pub trait BufferCapped {
+    fn buffer_styled(&self, distance: f64, cap: CapStyle, join: JoinStyle)
+        -> Result<MultiPolygon<f64>, Box<dyn Error>>;
+
+    fn buffer_capped(&self, distance: f64, cap: CapStyle)
+        -> Result<MultiPolygon<f64>, Box<dyn Error>> {
+        self.buffer_styled(distance, cap, JoinStyle::Round)
+    }
+}
+
+impl BufferCapped for MultiLineString<f64> {
+    fn buffer_styled(&self, distance: f64, cap: CapStyle, join: JoinStyle)
+        -> Result<MultiPolygon<f64>, Box<dyn Error>> {
+        let gt_self = Geometry::MultiLineString(self.clone());
+        let geo_self = gt_self.to_geos()?;
+        // Mitre limit of 4 is the SVG default, so long skinny corners get beveled the same way.
+        let buffered_self = geo_self.buffer_with_style(distance, 6, cap, join, 4.0)?;
+        let gt_out: geo_types::Geometry<f64> = geo_types::Geometry::try_from(buffered_self)?;
+        gt_out.flatten_polys()
+    }
+}
+
+impl BufferCapped for LineString<f64> {
+    fn buffer_styled(&self, distance: f64, cap: CapStyle, join: JoinStyle)
+        -> Result<MultiPolygon<f64>, Box<dyn Error>> {
+        MultiLineString::new(vec![self.clone()]).buffer_styled(distance, cap, join)
+    }
+}
+
+/// #MinkowskiOffset
+///
+/// Outsets a geometry by sweeping an axis aligned box (dx either side in x, dy
+/// either side in y) all over it. Unlike [`Buffer`] the corners stay square, which
+/// is what you want for stamp and block-letter outline effects. Only grows things;
+/// there's no inset version.
+pub trait MinkowskiOffset {
+    fn minkowski_offset(&self, dx: f64, dy: f64) -> Result<MultiPolygon<f64>, Box<dyn Error>>;
+}
+
+/// The bits we need to union to get the Minkowski sum: every polygon as is, plus the
+/// box swept along every edge (or just the box, for lonely points).
+fn minkowski_pieces(geo: &Geometry<f64>, dx: f64, dy: f64, pieces: &mut Vec<Geometry<f64>>) {
+    let sweep = |coords: &[Coordinate<f64>], pieces: &mut Vec<Geometry<f64>>| {
+        let corners = |c: &Coordinate<f64>| {
+            [
+                coord! {x: c.x - dx, y: c.y - dy},
+                coord! {x: c.x + dx, y: c.y - dy},
+                coord! {x: c.x + dx, y: c.y + dy},
+                coord! {x: c.x - dx, y: c.y + dy},
+            ]
+        };
+        let hull = |points: Vec<Coordinate<f64>>| {
+            Geometry::Polygon(MultiPoint::from(points).convex_hull())
+        };
+        if coords.len() == 1 {
+            pieces.push(hull(corners(&coords[0]).to_vec()));
+        }
+        for pair in coords.windows(2) {
+            pieces.push(hull(corners(&pair[0]).iter().chain(corners(&pair[1]).iter())
+                .cloned()
+                .collect()));
+        }
+    };
+    let polygon = |poly: &Polygon<f64>, pieces: &mut Vec<Geometry<f64>>| {
+        pieces.push(Geometry::Polygon(poly.clone()));
+        for ring in std::iter::once(poly.exterior()).chain(poly.interiors().iter()) {
+            sweep(&ring.0, pieces);
+        }
+    };
+    match geo {
+        Geometry::Point(p) => sweep(&[p.0], pieces),
+        Geometry::MultiPoint(points) => points.iter().for_each(|p| sweep(&[p.0], pieces)),
+        Geometry::Line(line) => sweep(&[line.start, line.end], pieces),
+        Geometry::LineString(line) => sweep(&line.0, pieces),
+        Geometry::MultiLineString(lines) => lines.iter().for_each(|line| sweep(&line.0, pieces)),
+        Geometry::Polygon(poly) => polygon(poly, pieces),
+        Geometry::MultiPolygon(polys) => polys.iter().for_each(|poly| polygon(poly, pieces)),
+        Geometry::Rect(rect) => polygon(&rect.to_polygon(), pieces),
+        Geometry::Triangle(triangle) => polygon(&triangle.to_polygon(), pieces),
+        Geometry::GeometryCollection(collection) => collection
+            .iter()
+            .for_each(|item| minkowski_pieces(item, dx, dy, pieces)),
+    }
+}
+
+impl MinkowskiOffset for Geometry<f64> {
+    fn minkowski_offset(&self, dx: f64, dy: f64) -> Result<MultiPolygon<f64>, Box<dyn Error>> {
+        let (dx, dy) = (dx.abs(), dy.abs());
+        let mut pieces = vec![];
+        minkowski_pieces(self, dx, dy, &mut pieces);
+        let collection = Geometry::GeometryCollection(GeometryCollection::new_from(pieces));
+        let unioned = collection.to_geos()?.unary_union()?;
+        let gt_out: geo_types::Geometry<f64> = geo_types::Geometry::try_from(unioned)?;
+        gt_out.flatten_polys()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use geo::prelude::{Area, BoundingRect};
+    use geo_types::coord;
+
+    #[test]
+    fn test_minkowski_offset() {
+        let square = Geometry::Rect(geo_types::Rect::new(
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 1.0, y: 1.0},
+        ));
+        let offset = square.minkowski_offset(1.0, 1.0).unwrap();
+        assert!((offset.unsigned_area() - 9.0).abs() < 1e-9);
+        let bounds = offset.bounding_rect().unwrap();
+        assert!((bounds.min().x + 1.0).abs() < 1e-9);
+        assert!((bounds.max().y - 2.0).abs() < 1e-9);
+
+        // A line gets a square-ended sausage
+        let line = Geometry::LineString(LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 10.0, y: 0.0},
+        ]));
+        let offset = line.minkowski_offset(1.0, 2.0).unwrap();
+        assert!((offset.unsigned_area() - 48.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_buffer_capped() {
+        let line = LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0}]);
+        let square = line.buffer_capped(1.0, CapStyle::Square).unwrap();
+        let round = line.buffer_capped(1.0, CapStyle::Round).unwrap();
+        let flat = line.buffer_capped(1.0, CapStyle::Flat).unwrap();
+        let bounds = square.bounding_rect().unwrap();
+        assert!((bounds.min().x + 1.0).abs() < 0.0001);
+        assert!((bounds.max().x - 11.0).abs() < 0.0001);
+        // Square corners stick out past the rounded ends...
+        assert!((square.unsigned_area() - 24.0).abs() < 0.0001);
+        assert!(round.unsigned_area() < square.unsigned_area());
+        // ...and flat ends don't stick out at all.
+        assert!((flat.unsigned_area() - 20.0).abs() < 0.0001);
+    }
+}
@@ -6,6 +6,8 @@ use std::error::Error;
 use geo_types::{LineString, MultiLineString, Polygon, Geometry};
 use geos::Geom;
 use std::convert::TryFrom;
+use crate::geo_types::boolean::non_empty;
+use crate::geo_types::ToGeos;
 
 /// #LineClip
 ///
@@ -36,6 +38,32 @@ pub trait LineClip {
 
 }
 
+/// #ClipOutside
+///
+/// The opposite of [`LineClip::maskwith`]: keeps only what's OUTSIDE `clip`. Unlike
+/// the LineClip methods you get back the same kind of thing you put in (lines
+/// stay lines, polygons stay polygons, punched full of holes), so it's handy for
+/// knocking shapes out before they get hatched. Just like clipwith, a `clip`
+/// that's a LineString is treated as the polygon it outlines. Nothing left over
+/// comes back as an empty GeometryCollection.
+pub trait ClipOutside
+where
+    Self: Sized,
+{
+    fn clip_outside(&self, clip: &Self) -> Result<Self, Box<dyn Error>>;
+}
+
+impl ClipOutside for Geometry<f64> {
+    fn clip_outside(&self, clip: &Self) -> Result<Self, Box<dyn Error>> {
+        let clip = match clip {
+            Geometry::LineString(line) => Geometry::Polygon(Polygon::new(line.clone(), vec![])),
+            _ => clip.clone(),
+        };
+        let geo_clip = clip.to_geos()?;
+        non_empty(self.to_geos()?.difference(&geo_clip)?)
+    }
+}
+
 /// Helper function to map geos geoms from geo_types geom. Public, but no promises that it
 /// stays stable.
 pub fn try_to_geos_geometry(geometry: &Geometry<f64>) -> Result<geos::Geometry, Box<dyn Error>>{
@@ -224,6 +252,37 @@ mod test {
     use geo_types::{coord, LineString};
     use wkt::TryFromWkt;
     use super::*;
+
+    #[test]
+    fn test_clip_outside() {
+        let line = Geometry::LineString(LineString::new(vec![
+            coord! {x: 0.0, y: 5.0},
+            coord! {x: 30.0, y: 5.0},
+        ]));
+        let clip = Geometry::Polygon(
+            geo_types::Rect::new(coord! {x: 10.0, y: 0.0}, coord! {x: 20.0, y: 10.0})
+                .to_polygon(),
+        );
+        let mut ends: Vec<Vec<(f64, f64)>> = match line.clip_outside(&clip).unwrap() {
+            Geometry::MultiLineString(mls) => mls
+                .0
+                .iter()
+                .map(|l| l.0.iter().map(|c| (c.x, c.y)).collect())
+                .collect(),
+            other => panic!("Expected two lines, got {:?}", other),
+        };
+        ends.sort_by(|a, b| a[0].0.partial_cmp(&b[0].0).unwrap());
+        assert_eq!(ends, vec![vec![(0.0, 5.0), (10.0, 5.0)], vec![(20.0, 5.0), (30.0, 5.0)]]);
+        // And it's all gone if the clip covers the whole thing.
+        let inside = Geometry::LineString(LineString::new(vec![
+            coord! {x: 12.0, y: 5.0},
+            coord! {x: 18.0, y: 5.0},
+        ]));
+        assert_eq!(
+            inside.clip_outside(&clip).unwrap(),
+            Geometry::GeometryCollection(geo_types::GeometryCollection::new_from(vec![]))
+        );
+    }
     #[test]
     fn test_clip_joydiv(){
         let front = LineString::try_from_wkt_str("LINESTRING (10.0000000000000000 20.0000000000000000, 10.5949999999999989 19.9999999999999964, 11.3600000000000012 20.0000000000000036, 12.2649999999999988 20.0000000000000000, 13.2799999999999976 20.0000000000000000, 14.3750000000000000 20.0000000000000000, 15.5200000000000014 20.0000000000000000, 16.6850000000000023 20.0000000000000000, 17.8399999999999999 20.0000000000000000, 18.9550000000000018 20.0000000000000000, 20.0000000000000000 20.0000000000000000, 20.9999999999999964 19.9999999999999964, 22.0000000000000036 20.0000000000000036, 23.0000000000000000 20.0000000000000000, 24.0000000000000000 20.0000000000000000, 25.0000000000000000 20.0000000000000000, 26.0000000000000036 20.0000000000000000, 26.9999999999999964 20.0000000000000000, 28.0000000000000000 20.0000000000000000, 29.0000000000000000 20.0000000000000000, 30.0000000000000000 20.0000000000000000, 31.0000000000000000 19.9999999999999964, 32.0000000000000000 20.0000000000000036, 32.9999999999999929 20.0000000000000000, 34.0000000000000000 20.0000000000000000, 35.0000000000000000 20.0000000000000000, 36.0000000000000071 20.0000000000000000, 37.0000000000000000 20.0000000000000000, 38.0000000000000000 20.0000000000000000, 39.0000000000000000 20.0000000000000000, 40.0000000000000000 20.0000000000000000, 40.9999999999999929 20.0216277888341878, 42.0000000000000071 20.0768988047437915, 42.9999999999999929 20.1513945218393324, 43.9999999999999929 20.2306964142313674, 45.0000000000000000 20.3003859560304249, 46.0000000000000071 20.3460446213470476, 47.0000000000000071 20.3532538842917781, 48.0000000000000000 20.3075952189751554, 49.0000000000000000 20.1946500995077152, 50.0000000000000000 20.0000000000000000, 51.0000000000000000 19.7083631000475883, 52.0000000000000071 19.3262020447926623, 53.0000000000000000 18.8717150066656281, 54.0000000000000000 18.3631001580969198, 55.0000000000000000 17.8185556715169575, 56.0000000000000071 17.2562797193561721, 57.0000000000000000 16.6944704740449801, 58.0000000000000000 16.1513261080138015, 59.0000000000000000 15.6450447936930690, 60.0000000000000000 15.1938247035132026, 61.0000000000000000 14.7503522106201235, 62.0000000000000000 14.2694697879448338, 62.9999999999999929 13.7726097575953670, 64.0000000000000142 13.2812044416797708, 65.0000000000000000 12.8166861623060715, 65.9999999999999858 12.4004872415823151, 67.0000000000000000 12.0540400016165421, 67.9999999999999858 11.7987767645167825, 69.0000000000000000 11.6561298523910786, 70.0000000000000000 11.6475315873474710, 70.9999999999999858 11.8485698935421198, 72.0000000000000142 12.2790119503571482, 73.0000000000000000 12.8765589627395389, 74.0000000000000000 13.5789121356362745, 75.0000000000000000 14.3237726739943376, 76.0000000000000000 15.0488417827607091, 77.0000000000000000 15.6918206668823679, 77.9999999999999858 16.1904105313062985, 79.0000000000000000 16.4823125809794817, 80.0000000000000000 16.5052280208489037, 80.9999999999999858 16.2421095618403584, 82.0000000000000142 15.7480926456342640, 83.0000000000000000 15.0691525733095482, 84.0000000000000000 14.2512646459451346, 85.0000000000000000 13.3404041646199527, 86.0000000000000000 12.3825464304129174, 87.0000000000000000 11.4236667444029614, 88.0000000000000000 10.5097404076690015, 89.0000000000000000 9.6867427212899653, 90.0000000000000000 9.0006489863447783, 91.0000000000000000 8.4317167467626088, 92.0000000000000142 7.9212998813852309, 93.0000000000000000 7.4570181936604563, 94.0000000000000142 7.0264914870360995, 95.0000000000000000 6.6173395649599778, 96.0000000000000000 6.2171822308799065, 97.0000000000000000 5.8136392882437011, 97.9999999999999858 5.3943305404991744, 99.0000000000000000 4.9468757910941434, 100.0000000000000000 4.4588948434764255, 101.0000000000000000 3.8922038497180198, 102.0000000000000142 3.2384282833204554, 103.0000000000000142 2.5299019298758316, 104.0000000000000142 1.7989585749762496, 105.0000000000000000 1.0779320042138099, 106.0000000000000000 0.3991560031806133, 107.0000000000000000 -0.2050356425312363, 108.0000000000000000 -0.7023091473296432, 109.0000000000000000 -1.0603307256225021, 110.0000000000000000 -1.2467665918177140, 111.0000000000000000 -1.2344990202901340, 112.0000000000000142 -1.0419939411213055, 113.0000000000000142 -0.7052930522791582, 114.0000000000000000 -0.2604380517316218, 115.0000000000000000 0.2565293625533722, 116.0000000000000000 0.8095674926078954, 116.9999999999999858 1.3626346404640159, 117.9999999999999858 1.8796891081538059, 119.0000000000000000 2.3246891977093327, 120.0000000000000000 2.6615932111626677, 121.0000000000000000 3.0110001923865006, 122.0000000000000000 3.4772861858694402, 123.0000000000000000 4.0000749945674308, 124.0000000000000142 4.5189904214364169, 125.0000000000000000 4.9736562694323450, 126.0000000000000000 5.3036963415111611, 127.0000000000000000 5.4487344406288090, 128.0000000000000000 5.3483943697412339, 129.0000000000000000 4.9422999318043832, 130.0000000000000000 4.1700749297742021, 131.0000000000000000 2.9055885164774411, 132.0000000000000284 1.1413803515302052, 133.0000000000000000 -1.0049200019275326, 134.0000000000000000 -3.4156829807557987, 135.0000000000000000 -5.9732790218146183, 136.0000000000000000 -8.5600785619640227, 137.0000000000000000 -11.0584520380640292, 138.0000000000000000 -13.3507698869746783, 139.0000000000000000 -15.3194025455559846, 140.0000000000000000 -16.8467204506679806, 140.9999999999999716 -17.9180188620546907, 142.0000000000000000 -18.6471146932005603, 143.0000000000000284 -19.1091608615762070, 143.9999999999999716 -19.3793102846522700, 145.0000000000000000 -19.5327158798993814, 146.0000000000000000 -19.6445305647881732, 147.0000000000000000 -19.7899072567892738, 147.9999999999999716 -20.0439988733733223, 149.0000000000000000 -20.4819583320109473, 150.0000000000000000 -21.1789385501727736, 150.9999999999999716 -22.3203276778005240, 152.0000000000000284 -23.9311827726700379, 153.0000000000000284 -25.8461601140031689, 154.0000000000000000 -27.8999159810217456, 155.0000000000000000 -29.9271066529476215, 156.0000000000000284 -31.7623884090026323, 157.0000000000000000 -33.2404175284086207, 157.9999999999999716 -34.1958502903874262, 159.0000000000000000 -34.4633429741608950, 160.0000000000000000 -33.8775518589508735, 160.9999999999999716 -32.1485103488569308, 162.0000000000000284 -29.2865934528895160, 163.0000000000000000 -25.5769698576367759, 163.9999999999999716 -21.3048082496868538, 165.0000000000000000 -16.7552773156279109, 166.0000000000000284 -12.2135457420480815, 166.9999999999999716 -7.9647822155355268, 168.0000000000000000 -4.2941554226783687, 169.0000000000000284 -1.4868340500647816, 170.0000000000000000 0.1720132157171044, 171.0000000000000000 0.6453837490708144, 172.0000000000000000 0.2084510255712032, 173.0000000000000000 -0.9556894896128898, 173.9999999999999716 -2.6639423313126258, 175.0000000000000000 -4.7332120343591635, 176.0000000000000284 -6.9804031335836694, 176.9999999999999716 -9.2224201638172936, 178.0000000000000000 -11.2761676598912093, 179.0000000000000000 -12.9585501566365675, 180.0000000000000000 -14.0864721888845352, 181.0000000000000000 -14.8404926080210302, 182.0000000000000284 -15.5107986657318495, 183.0000000000000284 -16.0787374972979507, 184.0000000000000000 -16.5256562380002912, 185.0000000000000000 -16.8329020231198392, 186.0000000000000000 -16.9818219879375611, 187.0000000000000000 -16.9537632677344128, 188.0000000000000000 -16.7300729977913605, 189.0000000000000000 -16.2920983133893671, 190.0000000000000000 -15.6211863498093919, 191.0000000000000000 -14.5740105849692050, 192.0000000000000284 -13.0902888848280554, 193.0000000000000284 -11.2759349667291140, 193.9999999999999716 -9.2368625480155480, 195.0000000000000000 -7.0789853460305476, 196.0000000000000284 -4.9082170781172731, 197.0000000000000000 -2.8304714616189117, 197.9999999999999716 -0.9516622138786224, 199.0000000000000000 0.6222969477604061, 200.0000000000000000 1.7854923059550067, 201.0000000000000000 2.4829229495536316, 202.0000000000000284 2.7938692644205654, 203.0000000000000284 2.8138394787363814, 204.0000000000000000 2.6383418206816498, 205.0000000000000000 2.3628845184369451, 206.0000000000000284 2.0829758001828376, 207.0000000000000000 1.8941238940998992, 208.0000000000000000 1.8918370283687034, 209.0000000000000000 2.1716234311698228, 210.0000000000000000 2.8289913306838272, 211.0000000000000000 3.9885325921125663, 212.0000000000000000 5.6204193483809171, 213.0000000000000284 7.5885302292539958, 214.0000000000000000 9.7567438644969222, 215.0000000000000000 11.9889388838748072, 216.0000000000000284 14.1489939171527759, 217.0000000000000000 16.1007875940959373, 218.0000000000000000 17.7081985444694148, 219.0000000000000000 18.8351053980383227, 220.0000000000000000 19.3453867845677756, 221.0000000000000000 19.1592255837998486, 222.0000000000000284 18.3734418536126185, 223.0000000000000284 17.1168699909751822, 223.9999999999999716 15.5183443928566511, 225.0000000000000000 13.7066994562261328, 226.0000000000000000 11.8107695780527315, 227.0000000000000000 9.9593891553055656, 228.0000000000000000 8.2813925849537267, 229.0000000000000000 6.9056142639663349, 230.0000000000000000 5.9608885893124928, 231.0000000000000000 5.3338402769696627, 232.0000000000000284 4.8278770167351412, 233.0000000000000000 4.4470076663077780, 234.0000000000000000 4.1952410833864304, 235.0000000000000000 4.0765861256699507, 236.0000000000000284 4.0950516508571937, 236.9999999999999716 4.2546465166470133, 237.9999999999999716 4.5593795807382618, 239.0000000000000000 5.0132597008297948, 240.0000000000000000 5.6202957346204645, 241.0000000000000000 6.5151720719554955, 242.0000000000000000 7.7685560399500897, 243.0000000000000000 9.2884309022080664, 244.0000000000000000 10.9827799223332470, 245.0000000000000000 12.7595863639294542, 246.0000000000000000 14.5268334906005094, 246.9999999999999716 16.1925045659502302, 248.0000000000000000 17.6645828535824450, 249.0000000000000000 18.8510516171009712, 250.0000000000000000 19.6598941201096302, 251.0000000000000000 20.0726741532414401, 252.0000000000000284 20.1745741416293072, 253.0000000000000000 20.0290053006268671, 253.9999999999999716 19.6993788455877628, 255.0000000000000000 19.2491059918656333, 256.0000000000000000 18.7415979548141216, 256.9999999999999432 18.2402659497868775, 258.0000000000000000 17.8085211921375262, 259.0000000000000000 17.5097748972197209, 260.0000000000000000 17.4074382803871011, 261.0000000000000000 17.4821377689335655, 262.0000000000000000 17.6573504880260188, 263.0000000000000568 17.9107636998276192, 264.0000000000000000 18.2200646665015320, 265.0000000000000000 18.5629406502108907, 266.0000000000000000 18.9170789131188712, 266.9999999999999432 19.2601667173886106, 268.0000000000000568 19.5698913251832778, 269.0000000000000000 19.8239399986660203, 270.0000000000000000 20.0000000000000000, 271.0000000000000000 20.1049987496443201, 272.0000000000000000 20.1659239500552303, 273.0000000000000568 20.1905532863915482, 274.0000000000000000 20.1866644438121270, 275.0000000000000000 20.1620351074758055, 276.0000000000000000 20.1244429625414192, 277.0000000000000000 20.0816656941678069, 278.0000000000000000 20.0414809875138076, 278.9999999999999432 20.0116665277382566, 280.0000000000000000 20.0000000000000000, 281.0000000000000000 19.9999999999999964, 282.0000000000000000 20.0000000000000036, 283.0000000000000568 20.0000000000000000, 284.0000000000000000 20.0000000000000000, 285.0000000000000000 20.0000000000000000, 286.0000000000000000 20.0000000000000000, 287.0000000000000000 20.0000000000000000, 288.0000000000000000 20.0000000000000000, 289.0000000000000000 20.0000000000000000, 290.0000000000000000 20.0000000000000000, 291.0000000000000000 19.9999999999999964, 292.0000000000000000 20.0000000000000036, 293.0000000000000568 20.0000000000000000, 294.0000000000000000 20.0000000000000000, 295.0000000000000000 20.0000000000000000, 296.0000000000000000 20.0000000000000000, 297.0000000000000000 20.0000000000000000, 298.0000000000000000 20.0000000000000000, 298.9999999999999432 20.0000000000000000, 300.0000000000000000 20.0000000000000000, 301.0449999999999591 19.9999999999999964, 302.1599999999999682 20.0000000000000036, 303.3150000000000546 20.0000000000000000, 304.4799999999999613 20.0000000000000000, 305.6250000000000000 20.0000000000000000, 306.7199999999999704 20.0000000000000000, 307.7349999999999568 20.0000000000000000, 308.6400000000000432 20.0000000000000000, 309.4050000000000296 20.0000000000000000, 310.0000000000000000 20.0000000000000000)").expect("Failed to parse WKT");
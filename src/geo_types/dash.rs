@@ -0,0 +1,103 @@
+use geo_types::{Coordinate, LineString, MultiLineString};
+
+/// Chops lines up into dashes, like an SVG stroke-dasharray. The pattern is a list
+/// of on/off lengths, which repeats (an odd length list gets doubled up, same as
+/// SVG does it). The pattern carries on around corners, so a dash can bend, and
+/// starts over at the beginning of each LineString. An empty pattern, or one that
+/// doesn't add up to anything, leaves the lines solid.
+pub trait Dash {
+    fn dash(&self, pattern: &[f64]) -> MultiLineString<f64>;
+}
+
+impl Dash for LineString<f64> {
+    fn dash(&self, pattern: &[f64]) -> MultiLineString<f64> {
+        let total: f64 = pattern.iter().sum();
+        if pattern.iter().any(|len| *len < 0.0 || len.is_nan()) || total <= 0.0 {
+            return MultiLineString::new(vec![self.clone()]);
+        }
+        let pattern: Vec<f64> = if pattern.len() % 2 == 1 {
+            pattern.iter().chain(pattern.iter()).cloned().collect()
+        } else {
+            pattern.to_vec()
+        };
+
+        let mut dashes: Vec<LineString<f64>> = vec![];
+        let mut current: Vec<Coordinate<f64>> = vec![];
+        let mut index = 0;
+        let mut remaining = pattern[0];
+        for segment in self.lines() {
+            let mut start = segment.start;
+            let mut length = (segment.end.x - start.x).hypot(segment.end.y - start.y);
+            // Walk along the segment, one dash boundary at a time.
+            while remaining < length {
+                let split = start + (segment.end - start) * (remaining / length);
+                if index % 2 == 0 {
+                    if current.is_empty() {
+                        current.push(start);
+                    }
+                    if current.last() != Some(&split) {
+                        current.push(split);
+                    }
+                    dashes.push(LineString::new(current));
+                    current = vec![];
+                }
+                length -= remaining;
+                start = split;
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+            remaining -= length;
+            if index % 2 == 0 {
+                if current.is_empty() {
+                    current.push(start);
+                }
+                current.push(segment.end);
+            }
+        }
+        if current.len() > 1 {
+            dashes.push(LineString::new(current));
+        }
+        // Zero length "on" dashes are dots, but they don't plot as anything.
+        dashes.retain(|dash| dash.0.len() > 1 && dash.0.first() != dash.0.last());
+        MultiLineString::new(dashes)
+    }
+}
+
+impl Dash for MultiLineString<f64> {
+    fn dash(&self, pattern: &[f64]) -> MultiLineString<f64> {
+        MultiLineString::new(self.iter().flat_map(|line| line.dash(pattern).0).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use geo_types::coord;
+
+    #[test]
+    fn test_dash_wraps_corners() {
+        let line = LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 5.0, y: 0.0},
+            coord! {x: 5.0, y: 5.0},
+        ]);
+        let dashes = line.dash(&[2.0, 2.0]);
+        assert_eq!(dashes.0.len(), 3);
+        // The middle dash goes around the corner
+        assert_eq!(
+            dashes.0[1].0,
+            vec![
+                coord! {x: 4.0, y: 0.0},
+                coord! {x: 5.0, y: 0.0},
+                coord! {x: 5.0, y: 1.0},
+            ]
+        );
+        assert_eq!(
+            dashes.0[2].0,
+            vec![coord! {x: 5.0, y: 3.0}, coord! {x: 5.0, y: 5.0}]
+        );
+        assert_eq!(line.dash(&[]), MultiLineString::new(vec![line.clone()]));
+        // Odd patterns get doubled, so this is 1 on, 1 off, 1 on...
+        assert_eq!(line.dash(&[1.0]).0.len(), 5);
+    }
+}
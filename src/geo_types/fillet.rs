@@ -0,0 +1,119 @@
+use geo::area::Area;
+use geo_types::{Coordinate, LineString, MultiPolygon, Polygon};
+use std::f64::consts::PI;
+
+/// Rounds off the sharp (convex) corners of a polygon's exterior with a tangent
+/// arc of the given radius, chopped into segments no more than `accuracy` off the
+/// true arc. Corners where the arc won't fit (the tangent points would go past the
+/// middle of either edge) are left sharp, as are the inside corners and holes.
+pub trait Fillet {
+    fn fillet(&self, radius: f64, accuracy: f64) -> Self;
+}
+
+fn unit(c: Coordinate<f64>) -> Option<Coordinate<f64>> {
+    let length = c.x.hypot(c.y);
+    if length > 0.0 {
+        Some(c / length)
+    } else {
+        None
+    }
+}
+
+/// The arc for a single corner at `p`, between neighbours `a` and `b`, or None if
+/// it doesn't fit.
+fn corner_arc(
+    a: Coordinate<f64>,
+    p: Coordinate<f64>,
+    b: Coordinate<f64>,
+    radius: f64,
+    accuracy: f64,
+) -> Option<Vec<Coordinate<f64>>> {
+    let (u, v) = (unit(a - p)?, unit(b - p)?);
+    let theta = (u.x * v.x + u.y * v.y).clamp(-1.0, 1.0).acos();
+    // Straight through (or folded right back), nothing to round.
+    if theta < 1e-9 || PI - theta < 1e-9 {
+        return None;
+    }
+    let tangent = radius / (theta / 2.0).tan();
+    let (ap, bp) = ((a - p).x.hypot((a - p).y), (b - p).x.hypot((b - p).y));
+    if tangent > ap / 2.0 || tangent > bp / 2.0 {
+        return None;
+    }
+    let center = p + unit(u + v)? * (radius / (theta / 2.0).sin());
+    let (t1, t2) = (p + u * tangent, p + v * tangent);
+    let start = (t1.y - center.y).atan2(t1.x - center.x);
+    let end = (t2.y - center.y).atan2(t2.x - center.x);
+    let sweep = (end - start + PI).rem_euclid(2.0 * PI) - PI;
+    // Sagitta again, same as shapes::ellipse.
+    let step = 2.0 * (1.0 - (accuracy.abs() / radius).min(1.0)).acos();
+    let segments = ((sweep.abs() / step).ceil() as usize).clamp(1, 1000);
+    Some(
+        (0..=segments)
+            .map(|i| {
+                let angle = start + sweep * i as f64 / segments as f64;
+                center + Coordinate::from((angle.cos(), angle.sin())) * radius
+            })
+            .collect(),
+    )
+}
+
+impl Fillet for Polygon<f64> {
+    fn fillet(&self, radius: f64, accuracy: f64) -> Self {
+        let ring = &self.exterior().0;
+        if radius <= 0.0 || ring.len() < 4 || !self.exterior().is_closed() {
+            return self.clone();
+        }
+        let open = &ring[..ring.len() - 1];
+        // Which way round we go decides which corners stick out.
+        let winding = self.exterior().signed_area().signum();
+        let mut out: Vec<Coordinate<f64>> = vec![];
+        for i in 0..open.len() {
+            let a = open[(i + open.len() - 1) % open.len()];
+            let p = open[i];
+            let b = open[(i + 1) % open.len()];
+            let turn = (p - a).x * (b - p).y - (p - a).y * (b - p).x;
+            let arc = if turn * winding > 0.0 {
+                corner_arc(a, p, b, radius, accuracy)
+            } else {
+                None
+            };
+            match arc {
+                Some(mut arc) => out.append(&mut arc),
+                None => out.push(p),
+            }
+        }
+        let mut exterior = LineString::new(out);
+        exterior.close();
+        Polygon::new(exterior, self.interiors().to_vec())
+    }
+}
+
+impl Fillet for MultiPolygon<f64> {
+    fn fillet(&self, radius: f64, accuracy: f64) -> Self {
+        MultiPolygon::new(
+            self.iter()
+                .map(|poly| poly.fillet(radius, accuracy))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use geo_types::{coord, Rect};
+
+    #[test]
+    fn test_fillet_square() {
+        let square = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 10.0}).to_polygon();
+        let round = square.fillet(2.0, 0.1);
+        assert!(round.exterior().0.len() > 5);
+        assert!(round.exterior().is_closed());
+        // Each corner loses (4 - pi) square units
+        let expected = 100.0 - 4.0 * (4.0 - PI);
+        assert!(round.unsigned_area() < 100.0);
+        assert!((round.unsigned_area() - expected).abs() < 0.1);
+        // Way too big a radius, so nothing happens
+        assert_eq!(square.fillet(20.0, 0.1).exterior().0.len(), 5);
+    }
+}
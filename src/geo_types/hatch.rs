@@ -1,31 +1,67 @@
-use crate::geo_types::buffer::Buffer;
+use crate::geo_types::buffer::{Buffer, BufferCapped, CapStyle, JoinStyle};
+use crate::geo_types::shapes;
 use embed_doc_image::embed_doc_image;
 use geo::bounding_rect::BoundingRect;
+use geo::prelude::EuclideanDistance;
 use geo::rotate::Rotate;
-use geo_types::{coord, LineString, MultiLineString, MultiPolygon, Polygon, Rect};
+use geo_types::{
+    coord, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon, Rect,
+};
 use geos::{Geom, Geometry};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::IntoParallelRefIterator;
 use rayon::prelude::*;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
 use serde::{Serialize,Deserialize};
 
-/// Useful for converting a line into a polygon as if it were stroked. Only supports
-/// round caps and joins for now.
+/// Useful for converting a line into a polygon as if it were stroked. The plain
+/// outline_stroke does round caps and joins; outline_stroke_styled lets you pick.
 pub trait OutlineStroke {
     fn outline_stroke(&self, stroke_weight: f64) -> Result<MultiPolygon<f64>, Box<dyn Error>>;
+
+    /// Defaults to the plain (round) outline_stroke, ignoring the styles, for
+    /// implementations that don't know any better.
+    fn outline_stroke_styled(
+        &self,
+        stroke_weight: f64,
+        _join: JoinStyle,
+        _cap: CapStyle,
+    ) -> Result<MultiPolygon<f64>, Box<dyn Error>> {
+        self.outline_stroke(stroke_weight)
+    }
 }
 
 impl OutlineStroke for MultiLineString<f64> {
     fn outline_stroke(&self, stroke_weight: f64) -> Result<MultiPolygon<f64>, Box<dyn Error>> {
         geo_types::Geometry::MultiLineString(self.clone()).buffer(stroke_weight / 2.0)
     }
+
+    fn outline_stroke_styled(
+        &self,
+        stroke_weight: f64,
+        join: JoinStyle,
+        cap: CapStyle,
+    ) -> Result<MultiPolygon<f64>, Box<dyn Error>> {
+        self.buffer_styled(stroke_weight / 2.0, cap, join)
+    }
 }
 
 impl OutlineStroke for LineString<f64> {
     fn outline_stroke(&self, stroke_weight: f64) -> Result<MultiPolygon<f64>, Box<dyn Error>> {
         geo_types::Geometry::LineString(self.clone()).buffer(stroke_weight / 2.0)
     }
+
+    fn outline_stroke_styled(
+        &self,
+        stroke_weight: f64,
+        join: JoinStyle,
+        cap: CapStyle,
+    ) -> Result<MultiPolygon<f64>, Box<dyn Error>> {
+        self.buffer_styled(stroke_weight / 2.0, cap, join)
+    }
 }
 
 /// Turns out that one of the most common things we do to a line is to stroke it with a weight,
@@ -39,6 +75,20 @@ pub trait OutlineFillStroke {
         pattern: Hatches,
         angle: f64,
     ) -> Result<MultiLineString<f64>, Box<dyn Error>>;
+
+    /// Same deal as outline_stroke_styled: by default the styles are ignored and
+    /// you get the round version.
+    fn outline_fill_stroke_with_hatch_styled(
+        &self,
+        stroke_weight: f64,
+        pen_width: f64,
+        pattern: Hatches,
+        angle: f64,
+        _join: JoinStyle,
+        _cap: CapStyle,
+    ) -> Result<MultiLineString<f64>, Box<dyn Error>> {
+        self.outline_fill_stroke_with_hatch(stroke_weight, pen_width, pattern, angle)
+    }
 }
 
 impl OutlineFillStroke for MultiLineString<f64> {
@@ -49,7 +99,26 @@ impl OutlineFillStroke for MultiLineString<f64> {
         pattern: Hatches,
         angle: f64,
     ) -> Result<MultiLineString<f64>, Box<dyn Error>> {
-        let polys = self.outline_stroke(stroke_weight)?;
+        self.outline_fill_stroke_with_hatch_styled(
+            stroke_weight,
+            pen_width,
+            pattern,
+            angle,
+            JoinStyle::Round,
+            CapStyle::Round,
+        )
+    }
+
+    fn outline_fill_stroke_with_hatch_styled(
+        &self,
+        stroke_weight: f64,
+        pen_width: f64,
+        pattern: Hatches,
+        angle: f64,
+        join: JoinStyle,
+        cap: CapStyle,
+    ) -> Result<MultiLineString<f64>, Box<dyn Error>> {
+        let polys = self.outline_stroke_styled(stroke_weight, join, cap)?;
         let mut lines_list: MultiLineString<f64> =
             MultiLineString::new(polys.0.iter().map(|p| p.exterior().clone()).collect());
         for poly in &polys {
@@ -100,6 +169,15 @@ impl Error for InvalidHatchGeometry {}
 /// leisure.
 pub trait HatchPattern {
     fn generate(&self, bbox: &Rect<f64>, scale: f64) -> MultiLineString<f64>;
+
+    /// For patterns that need the actual shape rather than just its bbox. By default
+    /// this is just the bbox pattern, unclipped.
+    fn generate_for(&self, poly: &Polygon<f64>, scale: f64, _pen: f64) -> MultiLineString<f64> {
+        match poly.bounding_rect() {
+            Some(bbox) => self.generate(&bbox, scale),
+            None => MultiLineString::new(vec![]),
+        }
+    }
 }
 
 /// # Hatch
@@ -110,7 +188,8 @@ pub trait HatchPattern {
 /// other Pattern types may not honor this, and generate alternative based scales
 /// for the 1.0 value. Inset is the distance to inset the boundary before filling,
 /// and it's a good idea to inset by approximately the scale value to keep the line
-/// endpoints inside of the boundary/container.
+/// endpoints inside of the boundary/container. Holes are clipped out too, and
+/// get the same inset as the outside boundary.
 ///
 /// # Example hatching
 /// ```rust
@@ -135,6 +214,32 @@ pub trait Hatch {
         scale: f64,
         inset: f64,
     ) -> Result<MultiLineString<f64>, InvalidHatchGeometry>;
+
+    /// Same as hatch, but afterwards chops `inset_ends` off both ends of every
+    /// hatch line, on top of the usual inset. Handy for keeping lines from poking
+    /// past the edge on concave shapes. Lines that are shorter than that just go
+    /// away, and closed rings (stipple dots, concentric rings) are left alone.
+    fn hatch_inset_ends(
+        &self,
+        pattern: Hatches,
+        angle: f64,
+        scale: f64,
+        inset: f64,
+        inset_ends: f64,
+    ) -> Result<MultiLineString<f64>, InvalidHatchGeometry> {
+        let lines = self.hatch(pattern, angle, scale, inset)?;
+        if inset_ends <= 0.0 {
+            return Ok(lines);
+        }
+        Ok(MultiLineString::new(
+            lines
+                .0
+                .iter()
+                .map(|ls| trim_ends(ls, inset_ends, inset_ends))
+                .filter(|ls| ls.0.len() >= 2)
+                .collect(),
+        ))
+    }
 }
 
 /// All of the available hatch types.
@@ -145,6 +250,15 @@ pub enum Hatches {
     NoHatch(NoHatch),
     LineHatch(LineHatch),
     CrossHatch(CrossHatch),
+    VoronoiHatch(VoronoiHatch),
+    SerpentineHatch(SerpentineHatch),
+    WaveHatch(WaveHatch),
+    ConcentricHatch(ConcentricHatch),
+    StippleHatch(StippleHatch),
+    FieldHatch(FieldHatch),
+    GradientLineHatch(GradientLineHatch),
+    BrickHatch(BrickHatch),
+    HerringboneHatch(HerringboneHatch),
 }
 
 impl Hatches {
@@ -159,6 +273,51 @@ impl Hatches {
     pub fn cross() -> Self {
         Hatches::CrossHatch(CrossHatch {})
     }
+
+    pub fn voronoi(density: f64, seed: u64) -> Self {
+        Hatches::VoronoiHatch(VoronoiHatch { density, seed })
+    }
+
+    pub fn serpentine() -> Self {
+        Hatches::SerpentineHatch(SerpentineHatch {})
+    }
+
+    pub fn stipple(jitter: f64, seed: u64) -> Self {
+        Hatches::StippleHatch(StippleHatch { jitter, seed })
+    }
+
+    pub fn field(field: Arc<dyn Fn(f64, f64) -> f64 + Send + Sync>, step: f64) -> Self {
+        Hatches::FieldHatch(FieldHatch { field, step })
+    }
+
+    pub fn gradient(start_scale: f64, end_scale: f64) -> Self {
+        Hatches::GradientLineHatch(GradientLineHatch {
+            start_scale,
+            end_scale,
+        })
+    }
+
+    pub fn brick(brick_width: f64, brick_height: f64) -> Self {
+        Hatches::BrickHatch(BrickHatch {
+            brick_width,
+            brick_height,
+        })
+    }
+
+    pub fn herringbone(tile: f64) -> Self {
+        Hatches::HerringboneHatch(HerringboneHatch { tile })
+    }
+
+    pub fn concentric() -> Self {
+        Hatches::ConcentricHatch(ConcentricHatch {})
+    }
+
+    pub fn wave(amplitude: f64, wavelength: f64) -> Self {
+        Hatches::WaveHatch(WaveHatch {
+            amplitude,
+            wavelength,
+        })
+    }
 }
 
 impl HatchPattern for Hatches {
@@ -167,8 +326,95 @@ impl HatchPattern for Hatches {
             Hatches::NoHatch(_) => MultiLineString::new(vec![]),
             Hatches::LineHatch(hatch) => hatch.generate(bbox, scale.clone()),
             Hatches::CrossHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::VoronoiHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::SerpentineHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::WaveHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::ConcentricHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::StippleHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::FieldHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::GradientLineHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::BrickHatch(hatch) => hatch.generate(bbox, scale.clone()),
+            Hatches::HerringboneHatch(hatch) => hatch.generate(bbox, scale.clone()),
         }
     }
+
+    fn generate_for(&self, poly: &Polygon<f64>, scale: f64, pen: f64) -> MultiLineString<f64> {
+        match self {
+            Hatches::SerpentineHatch(hatch) => hatch.generate_for(poly, scale, pen),
+            Hatches::ConcentricHatch(hatch) => hatch.generate_for(poly, scale, pen),
+            _ => match poly.bounding_rect() {
+                Some(bbox) => self.generate(&bbox, scale),
+                None => MultiLineString::new(vec![]),
+            },
+        }
+    }
+}
+
+/// Hatches are Eq, but plain f64s aren't, so the patterns' float parameters get
+/// compared bit for bit instead. That way a NaN parameter still equals itself
+/// (and 0.0 vs -0.0 are different hatches, which nobody will ever notice).
+fn same_f64(a: f64, b: f64) -> bool {
+    a.to_bits() == b.to_bits()
+}
+
+impl PartialEq for GradientLineHatch {
+    fn eq(&self, other: &Self) -> bool {
+        same_f64(self.start_scale, other.start_scale) && same_f64(self.end_scale, other.end_scale)
+    }
+}
+
+impl Eq for GradientLineHatch {}
+
+impl PartialEq for BrickHatch {
+    fn eq(&self, other: &Self) -> bool {
+        same_f64(self.brick_width, other.brick_width)
+            && same_f64(self.brick_height, other.brick_height)
+    }
+}
+
+impl Eq for BrickHatch {}
+
+impl PartialEq for HerringboneHatch {
+    fn eq(&self, other: &Self) -> bool {
+        same_f64(self.tile, other.tile)
+    }
+}
+
+impl Eq for HerringboneHatch {}
+
+impl PartialEq for WaveHatch {
+    fn eq(&self, other: &Self) -> bool {
+        same_f64(self.amplitude, other.amplitude) && same_f64(self.wavelength, other.wavelength)
+    }
+}
+
+impl Eq for WaveHatch {}
+
+impl PartialEq for StippleHatch {
+    fn eq(&self, other: &Self) -> bool {
+        same_f64(self.jitter, other.jitter) && self.seed == other.seed
+    }
+}
+
+impl Eq for StippleHatch {}
+
+impl PartialEq for VoronoiHatch {
+    fn eq(&self, other: &Self) -> bool {
+        same_f64(self.density, other.density) && self.seed == other.seed
+    }
+}
+
+impl Eq for VoronoiHatch {}
+
+impl Hatches {
+    /// Patterns that follow the boundary, instead of being cut out of a big bbox
+    /// sized sheet of lines. These ignore the hatch angle.
+    fn follows_shape(&self) -> bool {
+        matches!(
+            self,
+            Hatches::SerpentineHatch(_) | Hatches::ConcentricHatch(_)
+        )
+    }
 }
 
 /// The no-hatch option
@@ -213,6 +459,296 @@ impl HatchPattern for LineHatch {
     }
 }
 
+/// LineHatch, but the gap between lines ramps from `start_scale` at the top of
+/// the bbox to `end_scale` at the bottom, so you can fake a gradient. Ignores
+/// scale; the two ends are all you get. Rotate it with the hatch angle to point
+/// the gradient somewhere else.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GradientLineHatch {
+    pub start_scale: f64,
+    pub end_scale: f64,
+}
+
+impl HatchPattern for GradientLineHatch {
+    fn generate(&self, bbox: &Rect<f64>, _scale: f64) -> MultiLineString<f64> {
+        let min = bbox.min();
+        let max = bbox.max();
+        // Don't let a zero/negative gap loop forever.
+        let floor = self.start_scale.max(self.end_scale) * 0.01;
+        if floor.is_nan() || floor <= 0.0 || bbox.height() <= 0.0 {
+            return MultiLineString::new(vec![]);
+        }
+        let mut y = min.y;
+        let mut lines: Vec<LineString<f64>> = vec![];
+        while y < max.y {
+            let (x0, x1) = if lines.len() % 2 == 0 {
+                (min.x, max.x)
+            } else {
+                (max.x, min.x)
+            };
+            lines.push(LineString::new(vec![
+                coord! {x: x0, y: y},
+                coord! {x: x1, y: y},
+            ]));
+            let t = (y - min.y) / bbox.height();
+            y += (self.start_scale + (self.end_scale - self.start_scale) * t).max(floor);
+        }
+        MultiLineString::new(lines)
+    }
+}
+
+/// Running bond brickwork. Horizontal mortar lines every `brick_height`, with the
+/// vertical joints every `brick_width`, shifted half a brick on every other row.
+/// Ignores scale, since the bricks are their own scale.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BrickHatch {
+    pub brick_width: f64,
+    pub brick_height: f64,
+}
+
+impl HatchPattern for BrickHatch {
+    fn generate(&self, bbox: &Rect<f64>, _scale: f64) -> MultiLineString<f64> {
+        let mut lines: Vec<LineString<f64>> = vec![];
+        if self.brick_width <= 0.0 || self.brick_height <= 0.0 {
+            return MultiLineString::new(lines);
+        }
+        let min = bbox.min();
+        let max = bbox.max();
+        let mut y = min.y;
+        let mut row = 0u32;
+        while y < max.y {
+            lines.push(LineString::new(vec![
+                coord! {x: min.x, y: y},
+                coord! {x: max.x, y: y},
+            ]));
+            let row_bottom = (y + self.brick_height).min(max.y);
+            let mut x = if row % 2 == 0 {
+                min.x
+            } else {
+                min.x + self.brick_width / 2.0
+            };
+            while x < max.x {
+                lines.push(LineString::new(vec![
+                    coord! {x: x, y: y},
+                    coord! {x: x, y: row_bottom},
+                ]));
+                x += self.brick_width;
+            }
+            y += self.brick_height;
+            row += 1;
+        }
+        MultiLineString::new(lines)
+    }
+}
+
+/// Herringbone weave. The bbox gets cut into rows `tile` high, and each row is
+/// filled with short diagonal strokes (one tile corner to corner) every half a
+/// tile. Even rows lean one way at 45°, odd rows lean back at 135°, so stacked
+/// up they zigzag. Ignores scale in favour of the tile size.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HerringboneHatch {
+    pub tile: f64,
+}
+
+impl HatchPattern for HerringboneHatch {
+    fn generate(&self, bbox: &Rect<f64>, _scale: f64) -> MultiLineString<f64> {
+        let mut lines: Vec<LineString<f64>> = vec![];
+        if self.tile <= 0.0 {
+            return MultiLineString::new(lines);
+        }
+        let min = bbox.min();
+        let max = bbox.max();
+        let mut y = min.y;
+        let mut row = 0u32;
+        while y < max.y {
+            // Start a tile early so the slanted strokes cover the left edge too.
+            let mut x = min.x - self.tile;
+            while x < max.x {
+                let (x0, x1) = if row % 2 == 0 {
+                    (x, x + self.tile)
+                } else {
+                    (x + self.tile, x)
+                };
+                lines.push(LineString::new(vec![
+                    coord! {x: x0, y: y},
+                    coord! {x: x1, y: y + self.tile},
+                ]));
+                x += self.tile / 2.0;
+            }
+            y += self.tile;
+            row += 1;
+        }
+        MultiLineString::new(lines)
+    }
+}
+
+/// Like LineHatch, but each line wobbles along a sine wave, for that
+/// hand-drawn look. The lines are still `scale` apart.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WaveHatch {
+    pub amplitude: f64,
+    pub wavelength: f64,
+}
+
+impl HatchPattern for WaveHatch {
+    fn generate(&self, bbox: &Rect<f64>, scale: f64) -> MultiLineString<f64> {
+        let min = bbox.min();
+        let max = bbox.max();
+        let amplitude = self.amplitude.abs();
+        // 16 samples a wave is plenty smooth at plotter scales.
+        let samples = if amplitude > 0.0 && self.wavelength > 0.0 {
+            ((max.x - min.x) / (self.wavelength / 16.0)).ceil().max(1.0) as usize
+        } else {
+            1
+        };
+        // Start a wave-height early so the troughs still cover the whole box.
+        let mut y = min.y - amplitude;
+        let mut count = 0u32;
+        let mut lines: Vec<geo_types::LineString<f64>> = vec![];
+        while y < max.y + amplitude {
+            let mut line: Vec<geo_types::Coordinate<f64>> = (0..=samples)
+                .map(|i| {
+                    let x = min.x + (max.x - min.x) * i as f64 / samples as f64;
+                    let phase = if samples > 1 {
+                        2.0 * std::f64::consts::PI * (x - min.x) / self.wavelength
+                    } else {
+                        0.0
+                    };
+                    coord! {x: x, y: y + amplitude * phase.sin()}
+                })
+                .collect();
+            if count % 2 == 1 {
+                line.reverse();
+            }
+            lines.push(LineString::new(line));
+            y += scale;
+            count += 1;
+        }
+        MultiLineString::<f64>::new(lines)
+    }
+}
+
+/// Dots instead of lines, for tonal shading. Tiny circles on a grid `scale`
+/// apart, each nudged randomly by up to `jitter` times the spacing so it doesn't
+/// look too mechanical. The dots are a quarter of the spacing across, so at the
+/// usual scale (about a pen width) you get pen sized blobs. Same seed, same dots.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StippleHatch {
+    pub jitter: f64,
+    pub seed: u64,
+}
+
+impl HatchPattern for StippleHatch {
+    fn generate(&self, bbox: &Rect<f64>, scale: f64) -> MultiLineString<f64> {
+        let mut dots = MultiLineString::new(vec![]);
+        if scale <= 0.0 {
+            return dots;
+        }
+        let min = bbox.min();
+        let max = bbox.max();
+        let radius = scale / 8.0;
+        let wobble = self.jitter.abs() * scale / 2.0;
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut y = min.y;
+        while y < max.y {
+            let mut x = min.x;
+            while x < max.x {
+                let (dx, dy) = if wobble > 0.0 {
+                    (
+                        rng.gen_range(-wobble..wobble),
+                        rng.gen_range(-wobble..wobble),
+                    )
+                } else {
+                    (0.0, 0.0)
+                };
+                if let geo_types::Geometry::Polygon(dot) = shapes::circle(x + dx, y + dy, radius) {
+                    dots.0.push(dot.exterior().clone());
+                }
+                x += scale;
+            }
+            y += scale;
+        }
+        dots
+    }
+}
+
+/// Flow field hatch. Instead of one global angle, each hatch line follows
+/// `field(x, y)`, which returns the direction (in radians) at that point. Lines
+/// start on a grid `scale` apart and get traced `step` at a time, for three grid
+/// cells worth of length, centered on the start point. They're generated across
+/// the whole bbox and then clipped to the polygon by [`Hatch::hatch`], same as
+/// any other pattern (and the hatch angle rotates the whole field). See the
+/// field_hatch example for wiring it up to Perlin noise.
+///
+/// A closure can't be saved, so this deserializes with a flat (0 radian) field.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FieldHatch {
+    #[serde(skip, default = "FieldHatch::flat")]
+    pub field: Arc<dyn Fn(f64, f64) -> f64 + Send + Sync>,
+    pub step: f64,
+}
+
+impl FieldHatch {
+    fn flat() -> Arc<dyn Fn(f64, f64) -> f64 + Send + Sync> {
+        Arc::new(|_x, _y| 0.0)
+    }
+
+    /// Follows the field from x,y for `steps` steps; backwards if step is negative.
+    fn trace(&self, x: f64, y: f64, step: f64, steps: usize) -> Vec<geo_types::Coordinate<f64>> {
+        let mut point = coord! {x: x, y: y};
+        let mut out = vec![point];
+        for _ in 0..steps {
+            let angle = (self.field)(point.x, point.y);
+            point = coord! {x: point.x + step * angle.cos(), y: point.y + step * angle.sin()};
+            out.push(point);
+        }
+        out
+    }
+}
+
+impl Debug for FieldHatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FieldHatch")
+            .field("step", &self.step)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Closures can't be compared, so two field hatches are only equal if they
+/// share the same field.
+impl PartialEq for FieldHatch {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.field, &other.field) && same_f64(self.step, other.step)
+    }
+}
+
+impl Eq for FieldHatch {}
+
+impl HatchPattern for FieldHatch {
+    fn generate(&self, bbox: &Rect<f64>, scale: f64) -> MultiLineString<f64> {
+        let mut lines = MultiLineString::new(vec![]);
+        if scale <= 0.0 || self.step <= 0.0 {
+            return lines;
+        }
+        let half_steps = ((1.5 * scale / self.step).ceil() as usize).max(1);
+        let min = bbox.min();
+        let max = bbox.max();
+        let mut y = min.y;
+        while y < max.y {
+            let mut x = min.x;
+            while x < max.x {
+                let mut line = self.trace(x, y, -self.step, half_steps);
+                line.reverse();
+                line.extend(self.trace(x, y, self.step, half_steps).into_iter().skip(1));
+                lines.0.push(LineString::new(line));
+                x += scale;
+            }
+            y += scale;
+        }
+        lines
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct CrossHatch {}
 
@@ -259,9 +795,165 @@ impl HatchPattern for CrossHatch {
     }
 }
 
+/// Crystalline "crackle" fill. Scatters `density` seed points per square unit
+/// across the bbox and draws the edges of their Voronoi cells. The points come
+/// from a seeded RNG, so the same seed always gives you the same crackle.
+/// Doesn't use the scale at all; density is all you get.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VoronoiHatch {
+    pub density: f64,
+    pub seed: u64,
+}
+
+impl HatchPattern for VoronoiHatch {
+    fn generate(&self, bbox: &Rect<f64>, _scale: f64) -> MultiLineString<f64> {
+        let min = bbox.min();
+        let max = bbox.max();
+        let count = (bbox.width() * bbox.height() * self.density).ceil();
+        if count.is_nan() || count < 2.0 || bbox.width() <= 0.0 || bbox.height() <= 0.0 {
+            return MultiLineString::new(vec![]);
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let sites = MultiPoint::new(
+            (0..count as usize)
+                .map(|_| Point::new(rng.gen_range(min.x..max.x), rng.gen_range(min.y..max.y)))
+                .collect(),
+        );
+        let edges = Geometry::try_from(&sites).and_then(|geo_sites| {
+            let envelope = Geometry::try_from(bbox.to_polygon())?;
+            geo_sites.voronoi(Some(&envelope), 0.0, true)
+        });
+        match edges {
+            Ok(edges) => match geo_types::Geometry::<f64>::try_from(edges) {
+                Ok(gt_edges) => gt_flatten_mlines(gt_edges, MultiLineString::new(vec![])),
+                Err(_) => MultiLineString::new(vec![]),
+            },
+            Err(_) => MultiLineString::new(vec![]),
+        }
+    }
+}
+
+/// Solid fill with (almost) no pen lifts. Walks inward in contours parallel to the
+/// boundary, `scale` apart, but instead of lifting between rings it hops straight
+/// over to the next ring, so a simple shape comes out as one long spiral-ish
+/// stroke. You only get a new stroke when the shape splits (or has holes).
+/// Since it follows the boundary, the hatch angle doesn't do anything.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SerpentineHatch {}
+
+impl SerpentineHatch {
+    /// Generates the joined contours directly from the polygon, since unlike the
+    /// other patterns this can't be clipped out of a bbox sized pattern.
+    pub fn fill(&self, poly: &Polygon<f64>, scale: f64, inset: f64) -> MultiLineString<f64> {
+        let mut strokes = MultiLineString::new(vec![]);
+        if scale <= 0.0 {
+            return strokes;
+        }
+        let mut current = LineString::new(vec![]);
+        let mut distance = inset.max(0.0);
+        // Don't spin forever on something weird
+        for _ring in 0..10000 {
+            let rings = match geo_types::Geometry::Polygon(poly.clone()).buffer(-distance) {
+                Ok(rings) if !rings.0.is_empty() => rings,
+                _ => break,
+            };
+            let contiguous = rings.0.len() == 1 && rings.0[0].interiors().is_empty();
+            if !contiguous && !current.0.is_empty() {
+                strokes.0.push(current);
+                current = LineString::new(vec![]);
+            }
+            for contour in &rings {
+                for ring in std::iter::once(contour.exterior()).chain(contour.interiors().iter()) {
+                    let ring = match current.0.last() {
+                        Some(last) => rotate_ring_to(ring, last),
+                        None => ring.clone(),
+                    };
+                    current.0.extend(ring.0);
+                    if !contiguous {
+                        strokes.0.push(current);
+                        current = LineString::new(vec![]);
+                    }
+                }
+            }
+            distance += scale;
+        }
+        if current.0.len() > 1 {
+            strokes.0.push(current);
+        }
+        strokes
+    }
+}
+
+/// Re-opens a closed ring so that it starts (and ends) at the vertex nearest `to`.
+fn rotate_ring_to(ring: &LineString<f64>, to: &geo_types::Coordinate<f64>) -> LineString<f64> {
+    let coords = &ring.0;
+    if coords.len() < 2 {
+        return ring.clone();
+    }
+    let open = &coords[..coords.len() - 1];
+    let start = open
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, (c.x - to.x).hypot(c.y - to.y)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(i, _d)| i)
+        .unwrap_or(0);
+    let mut out: Vec<geo_types::Coordinate<f64>> = open[start..]
+        .iter()
+        .chain(open[..start].iter())
+        .cloned()
+        .collect();
+    out.push(open[start]);
+    LineString::new(out)
+}
+
+impl HatchPattern for SerpentineHatch {
+    fn generate(&self, bbox: &Rect<f64>, scale: f64) -> MultiLineString<f64> {
+        self.fill(&bbox.to_polygon(), scale, 0.0)
+    }
+
+    fn generate_for(&self, poly: &Polygon<f64>, scale: f64, pen: f64) -> MultiLineString<f64> {
+        self.fill(poly, scale, pen)
+    }
+}
+
+/// Island fill: rings inset from the boundary every `scale`, until there's
+/// nothing left. The outermost ring sits the pen inset in from the edge, same as
+/// serpentine. Looks great on organic shapes. Unlike serpentine, every ring is its
+/// own stroke, and holes get rings around them too.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConcentricHatch {}
+
+impl HatchPattern for ConcentricHatch {
+    fn generate(&self, bbox: &Rect<f64>, scale: f64) -> MultiLineString<f64> {
+        self.generate_for(&bbox.to_polygon(), scale, 0.0)
+    }
+
+    fn generate_for(&self, poly: &Polygon<f64>, scale: f64, pen: f64) -> MultiLineString<f64> {
+        let mut rings = MultiLineString::new(vec![]);
+        if scale <= 0.0 {
+            return rings;
+        }
+        let start = pen.max(0.0);
+        // Don't spin forever on something weird
+        for n in 0..10000 {
+            let distance = start + scale * n as f64;
+            let inset = match geo_types::Geometry::Polygon(poly.clone()).buffer(-distance) {
+                Ok(inset) if !inset.0.is_empty() => inset,
+                _ => break,
+            };
+            for contour in inset {
+                rings.0.push(contour.exterior().clone());
+                rings.0.extend(contour.interiors().iter().cloned());
+            }
+        }
+        rings
+    }
+}
+
 /// Internal helper function for flattening a ton of Geometry which contains LineStrings, into
 /// a single MultiLineString for drawing on whatever output device we want.
-fn gt_flatten_mlines(
+pub(crate) fn gt_flatten_mlines(
     geo: geo_types::Geometry<f64>,
     mut existing: MultiLineString<f64>,
 ) -> MultiLineString<f64> {
@@ -323,7 +1015,7 @@ impl Hatch for MultiPolygon<f64> {
     }
 }
 
-fn dirty_inset(mls_geo: &mut geo_types::Geometry<f64>, inset: f64) {
+fn dirty_inset(mls_geo: &mut geo_types::Geometry<f64>, inset: f64, boundary: &Polygon<f64>) {
     // Only works for MultiLineString, eh?
     match mls_geo {
         geo_types::Geometry::MultiLineString(mls) => {
@@ -338,20 +1030,19 @@ fn dirty_inset(mls_geo: &mut geo_types::Geometry<f64>, inset: f64) {
                 mls.0 = mls.0[0..mls.0.len()]
                     .into_iter()
                     // Skip invalid linestrings
-                    .filter(|ls| ls.0.len() == 2)
+                    .filter(|ls| ls.0.len() >= 2)
                     .map(|ls| {
-                        let ls_vec = ls.0[1] - ls.0[0];
-                        let ls_vec_len = (ls_vec.x.powi(2) + ls_vec.y.powi(2)).sqrt();
-                        // Don't draw too-short hatches
-                        if ls_vec_len < (2. * inset) {
-                            return LineString::new(vec![]);
-                        }
-                        let ls_vec = ls_vec / ls_vec_len;
-                        let out = LineString::new(vec![
-                            ls.0[0] + (ls_vec * inset),
-                            ls.0[1] - (ls_vec * inset),
-                        ]);
-                        out
+                        // Only the ends the clip left on the boundary need pulling
+                        // in. Ones that stop inside (brick joints, herringbone
+                        // strokes) are already where the pattern wants them.
+                        let trim = |c: &geo_types::Coordinate<f64>| {
+                            if on_boundary(c, boundary) {
+                                inset
+                            } else {
+                                0.0
+                            }
+                        };
+                        trim_ends(ls, trim(&ls.0[0]), trim(ls.0.last().unwrap()))
                     })
                     .collect()
             }
@@ -360,6 +1051,53 @@ fn dirty_inset(mls_geo: &mut geo_types::Geometry<f64>, inset: f64) {
     }
 }
 
+/// Whether a hatch line end sits on the outside or a hole of the polygon, which
+/// is where the clip leaves the ends it cut.
+fn on_boundary(c: &geo_types::Coordinate<f64>, boundary: &Polygon<f64>) -> bool {
+    let point = Point::from(*c);
+    std::iter::once(boundary.exterior())
+        .chain(boundary.interiors().iter())
+        .any(|ring| point.euclidean_distance(ring) < 1.0e-6)
+}
+
+/// Trims `start` off the front of a hatch line and `end` off the back. Closed rings
+/// (stipple dots) never touch the boundary, so they're left be. Too-short lines
+/// come back empty.
+fn trim_ends(ls: &LineString<f64>, start: f64, end: f64) -> LineString<f64> {
+    if ls.0.len() > 3 && ls.is_closed() {
+        return ls.clone();
+    }
+    let mut trimmed = trim_start(&ls.0, start);
+    trimmed.reverse();
+    let mut trimmed = trim_start(&trimmed, end);
+    trimmed.reverse();
+    // Don't draw too-short hatches
+    if trimmed.len() < 2 {
+        return LineString::new(vec![]);
+    }
+    LineString::new(trimmed)
+}
+
+/// Chops `distance` off the start of a polyline, following the line around any
+/// bends. If the line's not that long, there's nothing left.
+fn trim_start(
+    coords: &[geo_types::Coordinate<f64>],
+    distance: f64,
+) -> Vec<geo_types::Coordinate<f64>> {
+    let mut remaining = distance;
+    for i in 0..coords.len().saturating_sub(1) {
+        let segment = coords[i + 1] - coords[i];
+        let length = (segment.x.powi(2) + segment.y.powi(2)).sqrt();
+        if length > remaining {
+            let mut out = vec![coords[i] + segment * (remaining / length)];
+            out.extend_from_slice(&coords[i + 1..]);
+            return out;
+        }
+        remaining -= length;
+    }
+    vec![]
+}
+
 impl Hatch for Polygon<f64> {
     fn hatch(
         &self,
@@ -379,6 +1117,10 @@ impl Hatch for Polygon<f64> {
         // HAHAHAHA Holyshit
         // TODO: Contract the BOUNDING BOX for the original generation of the hatch lines
         let _perimeter = self;
+        // Some patterns follow the boundary, so they can't be clipped from a bbox pattern.
+        if pattern.follows_shape() {
+            return Ok(pattern.generate_for(self, scale, inset));
+        }
         let bbox = self
             .bounding_rect()
             .ok_or(InvalidHatchGeometry::CouldNotGenerateHatch)?
@@ -417,7 +1159,7 @@ impl Hatch for Polygon<f64> {
         let mut out: geo_types::Geometry<f64> = hatched_object
             .try_into()
             .or(Err(InvalidHatchGeometry::InvalidResultGeometry))?;
-        dirty_inset(&mut out, scale.max(inset)); // Mutates in place.
+        dirty_inset(&mut out, scale.max(inset), self); // Mutates in place.
         let out = gt_flatten_mlines(out, MultiLineString::new(vec![]));
         Ok(out)
     }
@@ -430,6 +1172,27 @@ mod test {
     use geos::Geometry;
     use std::f64::consts::PI;
 
+    #[test]
+    fn test_outline_stroke_joins() {
+        let corner = LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 10.0, y: 0.0},
+            coord! {x: 10.0, y: 10.0},
+        ]);
+        let vertices = |join: JoinStyle| {
+            corner
+                .outline_stroke_styled(2.0, join, CapStyle::Flat)
+                .unwrap()
+                .0
+                .iter()
+                .map(|poly| poly.exterior().0.len())
+                .sum::<usize>()
+        };
+        // A mitred corner is just a sharp point, but round has to go around the bend.
+        assert!(vertices(JoinStyle::Mitre) < vertices(JoinStyle::Round));
+        assert!(vertices(JoinStyle::Bevel) < vertices(JoinStyle::Round));
+    }
+
     #[test]
     fn test_box_hatch() {
         let rect = Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 100.0});
@@ -532,6 +1295,388 @@ mod test {
         // println!("Disjoint hatch {:?}", hatches);
     }
 
+    #[test]
+    fn test_serpentine_hatch_square() {
+        let square = Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 10.0});
+        let fill = square
+            .to_polygon()
+            .hatch(Hatches::serpentine(), 0.0, 1.0, 0.5)
+            .expect("Serpentine hatch failed");
+        assert_eq!(fill.0.len(), 1);
+        // Five contours at 0.5, 1.5, ... 4.5 in from the edge, all in one stroke
+        assert!(fill.0[0].0.len() >= 5 * 5);
+    }
+
+    #[test]
+    fn test_wave_hatch() {
+        let rect = Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 100.0});
+        let flat = WaveHatch {
+            amplitude: 0.0,
+            wavelength: 10.0,
+        }
+        .generate(&rect, 5.0);
+        assert_eq!(flat.0.len(), LineHatch {}.generate(&rect, 5.0).0.len());
+
+        let poly = rect.to_polygon();
+        let wavy = poly
+            .hatch(Hatches::wave(2.0, 10.0), 30.0, 5.0, 0.5)
+            .expect("Wave hatch failed");
+        assert!(wavy.0.len() > 10);
+        assert!(wavy.0.iter().any(|line| line.0.len() > 2));
+    }
+
+    #[test]
+    fn test_concentric_hatch_star() {
+        let star = Polygon::new(
+            LineString::new(
+                (0..=10)
+                    .map(|i| {
+                        let angle = PI * i as f64 / 5.0;
+                        let radius = if i % 2 == 0 { 20.0 } else { 8.0 };
+                        coord! {x: radius * angle.cos(), y: radius * angle.sin()}
+                    })
+                    .collect(),
+            ),
+            vec![],
+        );
+        let rings = star
+            .hatch(Hatches::concentric(), 0.0, 1.0, 0.5)
+            .expect("Concentric hatch failed");
+        assert!(rings.0.len() > 3);
+        // Each ring is closed, and sits inside the one before it.
+        let bounds: Vec<Rect<f64>> = rings.iter().map(|r| r.bounding_rect().unwrap()).collect();
+        for ring in rings.iter() {
+            assert!(ring.is_closed());
+        }
+        for pair in bounds.windows(2) {
+            assert!(pair[1].width() <= pair[0].width());
+        }
+    }
+
+    #[test]
+    fn test_concentric_hatch_inset() {
+        let square =
+            Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 20.0, y: 20.0}).to_polygon();
+        let rings = square
+            .hatch(Hatches::concentric(), 0.0, 2.0, 0.5)
+            .expect("Concentric hatch failed");
+        // The outer ring is half a pen in from the edge, then every 2mm after that.
+        let outer = rings.0[0].bounding_rect().unwrap();
+        assert!((outer.min().x - 0.5).abs() < 1.0e-6);
+        assert!((outer.max().y - 19.5).abs() < 1.0e-6);
+        let next = rings.0[1].bounding_rect().unwrap();
+        assert!((next.min().x - 2.5).abs() < 1.0e-6);
+        assert_eq!(rings.0.len(), 5);
+    }
+
+    #[test]
+    fn test_stipple_hatch_density() {
+        let square =
+            Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 20.0, y: 20.0}).to_polygon();
+        let sparse = square
+            .hatch(Hatches::stipple(0.3, 7), 0.0, 2.0, 0.0)
+            .expect("Sparse stipple failed");
+        let dense = square
+            .hatch(Hatches::stipple(0.3, 7), 0.0, 1.0, 0.0)
+            .expect("Dense stipple failed");
+        assert!(sparse.0.len() > 0);
+        assert!(dense.0.len() > sparse.0.len());
+        assert!(dense.0.iter().all(|dot| dot.is_closed()));
+    }
+
+    #[test]
+    fn test_field_hatch() {
+        let rect = Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 20.0, y: 20.0});
+        // A field that points straight up (+y) everywhere
+        let vertical = FieldHatch {
+            field: Arc::new(|_x, _y| PI / 2.0),
+            step: 0.5,
+        };
+        let lines = vertical.generate(&rect, 2.0);
+        assert_eq!(lines.0.len(), 100);
+        for line in lines.iter() {
+            let (first, last) = (line.0.first().unwrap(), line.0.last().unwrap());
+            assert!((first.x - last.x).abs() < 1e-9);
+            assert!((last.y - first.y - 6.0).abs() < 1e-9);
+        }
+        let clipped = rect
+            .to_polygon()
+            .hatch(Hatches::FieldHatch(vertical.clone()), 0.0, 2.0, 0.0)
+            .expect("Field hatch failed");
+        assert!(clipped.0.len() > 0);
+        assert_eq!(
+            Hatches::FieldHatch(vertical.clone()),
+            Hatches::FieldHatch(vertical)
+        );
+    }
+
+    #[test]
+    fn test_gradient_line_hatch() {
+        let rect = Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 100.0});
+        let lines = GradientLineHatch {
+            start_scale: 1.0,
+            end_scale: 10.0,
+        }
+        .generate(&rect, 1.0);
+        let ys: Vec<f64> = lines.iter().map(|line| line.0[0].y).collect();
+        let gaps: Vec<f64> = ys.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        assert!(gaps.len() > 10);
+        assert!(gaps.windows(2).all(|pair| pair[1] > pair[0]));
+        assert!((gaps[0] - 1.0).abs() < 1e-9);
+        assert!(*gaps.last().unwrap() <= 10.0);
+
+        let poly = rect.to_polygon();
+        let hatched = poly
+            .hatch(Hatches::gradient(1.0, 10.0), 90.0, 1.0, 0.0)
+            .expect("Gradient hatch failed");
+        assert!(hatched.0.len() > 0);
+    }
+
+    #[test]
+    fn test_hatch_respects_holes() {
+        let square = |min: f64, max: f64| {
+            LineString::<f64>::new(vec![
+                coord! {x: min, y: min},
+                coord! {x: max, y: min},
+                coord! {x: max, y: max},
+                coord! {x: min, y: max},
+                coord! {x: min, y: min},
+            ])
+        };
+        // A small hole, so nothing gets to paper over it with a big inset.
+        let donut = Polygon::new(square(0.0, 100.0), vec![square(48.0, 52.0)]);
+        let in_hole = |c: &geo_types::Coordinate<f64>| {
+            c.x > 48.0 + 1e-6 && c.x < 52.0 - 1e-6 && c.y > 48.0 + 1e-6 && c.y < 52.0 - 1e-6
+        };
+        let patterns = vec![
+            Hatches::line(),
+            Hatches::cross(),
+            Hatches::wave(1.0, 5.0),
+            Hatches::serpentine(),
+            Hatches::concentric(),
+        ];
+        for pattern in patterns {
+            let polys = MultiPolygon::new(vec![donut.clone()]);
+            let hatched = vec![
+                donut.hatch(pattern.clone(), 30.0, 1.0, 0.5).unwrap(),
+                polys.hatch(pattern.clone(), 30.0, 1.0, 0.5).unwrap(),
+            ];
+            for lines in hatched {
+                assert!(lines.0.len() > 0);
+                for line in lines.iter() {
+                    for segment in line.lines() {
+                        // Walk along each segment, not just the vertices.
+                        for i in 0..=20 {
+                            let t = i as f64 / 20.0;
+                            let point = segment.start + (segment.end - segment.start) * t;
+                            assert!(!in_hole(&point), "{:?} crossed the hole", pattern);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hatch_inset_ends() {
+        let poly =
+            Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 100.0}).to_polygon();
+        let plain = poly
+            .hatch_inset_ends(Hatches::line(), 0.0, 10.0, 0.0, 0.0)
+            .unwrap();
+        assert_eq!(plain, poly.hatch(Hatches::line(), 0.0, 10.0, 0.0).unwrap());
+        let trimmed = poly
+            .hatch_inset_ends(Hatches::line(), 0.0, 10.0, 0.0, 5.0)
+            .unwrap();
+        assert_eq!(plain.0.len(), trimmed.0.len());
+        for (before, after) in plain.iter().zip(trimmed.iter()) {
+            let (b0, b1) = (before.0[0], *before.0.last().unwrap());
+            let (a0, a1) = (after.0[0], *after.0.last().unwrap());
+            assert!(((a0.x - b0.x).abs() - 5.0).abs() < 1e-9);
+            assert!(((a1.x - b1.x).abs() - 5.0).abs() < 1e-9);
+            assert!((a0.x - 50.0).abs() < (b0.x - 50.0).abs());
+            assert!((a1.x - 50.0).abs() < (b1.x - 50.0).abs());
+        }
+        // Way too much trimming leaves nothing at all
+        let gone = poly
+            .hatch_inset_ends(Hatches::line(), 0.0, 10.0, 0.0, 60.0)
+            .unwrap();
+        assert!(gone.0.is_empty());
+    }
+
+    #[test]
+    fn test_brick_hatch() {
+        let rect = Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 40.0, y: 20.0});
+        let lines = BrickHatch {
+            brick_width: 10.0,
+            brick_height: 5.0,
+        }
+        .generate(&rect, 1.0);
+        let (horizontal, vertical): (Vec<_>, Vec<_>) =
+            lines.iter().partition(|line| line.0[0].y == line.0[1].y);
+        assert_eq!(horizontal.len(), 4);
+        // 4 joints on even rows (0, 10, 20, 30), 4 on the odd ones (5, 15, 25, 35)
+        assert_eq!(vertical.len(), 16);
+        let row_x = |y: f64| -> Vec<f64> {
+            vertical
+                .iter()
+                .filter(|line| line.0[0].y == y)
+                .map(|line| line.0[0].x)
+                .collect()
+        };
+        assert_eq!(row_x(0.0), vec![0.0, 10.0, 20.0, 30.0]);
+        assert_eq!(row_x(5.0), vec![5.0, 15.0, 25.0, 35.0]);
+
+        let clipped = rect
+            .to_polygon()
+            .hatch(Hatches::brick(10.0, 5.0), 30.0, 1.0, 0.0)
+            .expect("Brick hatch failed");
+        assert!(clipped.0.len() > 0);
+
+        // The joints in the middle two rows never touch the edge, so the inset
+        // mustn't eat into them: they stay a whole brick high.
+        let square = rect
+            .to_polygon()
+            .hatch(Hatches::brick(10.0, 5.0), 0.0, 2.0, 0.0)
+            .expect("Brick hatch failed");
+        let interior: Vec<&LineString<f64>> = square
+            .iter()
+            .filter(|line| {
+                let (start, end) = (line.0[0], *line.0.last().unwrap());
+                (start.x - end.x).abs() < 1e-9
+                    && start.x > 1.0
+                    && start.x < 39.0
+                    && start.y.min(end.y) > 4.0
+                    && start.y.max(end.y) < 16.0
+            })
+            .collect();
+        // 5, 15, 25, 35 on the second row and 10, 20, 30 on the third.
+        assert_eq!(interior.len(), 7);
+        for line in interior {
+            assert!(((line.0[0].y - line.0[1].y).abs() - 5.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_herringbone_hatch() {
+        let rect = Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 20.0, y: 20.0});
+        let lines = HerringboneHatch { tile: 5.0 }.generate(&rect, 1.0);
+        assert!(lines.0.len() > 0);
+        for line in lines.iter() {
+            let (start, end) = (line.0[0], line.0[1]);
+            let row = (start.y.min(end.y) / 5.0).round() as u32;
+            let slope = (end.y - start.y) / (end.x - start.x);
+            // 45° on the even rows, 135° on the odd ones
+            if row % 2 == 0 {
+                assert_eq!(slope, 1.0);
+            } else {
+                assert_eq!(slope, -1.0);
+            }
+        }
+        let clipped = rect
+            .to_polygon()
+            .hatch(Hatches::herringbone(5.0), 0.0, 0.5, 0.0)
+            .expect("Herringbone hatch failed");
+        assert!(clipped.0.len() > 0);
+
+        // Strokes that sit entirely inside come through at full length, even with
+        // a chunky scale that would have trimmed them right down.
+        let clipped = rect
+            .to_polygon()
+            .hatch(Hatches::herringbone(5.0), 0.0, 3.0, 0.0)
+            .expect("Herringbone hatch failed");
+        let diagonal = 5.0 * 2f64.sqrt();
+        let whole = clipped
+            .iter()
+            .filter(|line| {
+                let (start, end) = (line.0[0], *line.0.last().unwrap());
+                ((end.x - start.x).hypot(end.y - start.y) - diagonal).abs() < 1e-6
+            })
+            .count();
+        // Five a row on the middle two rows, the rest all touch the edge somewhere.
+        assert_eq!(whole, 10);
+    }
+
+    #[test]
+    fn test_outline_styled_defaults() {
+        // Something that only implements the original, unstyled methods.
+        struct Square;
+        impl OutlineStroke for Square {
+            fn outline_stroke(&self, weight: f64) -> Result<MultiPolygon<f64>, Box<dyn Error>> {
+                let rect = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: weight, y: weight});
+                Ok(MultiPolygon::new(vec![rect.to_polygon()]))
+            }
+        }
+        impl OutlineFillStroke for Square {
+            fn outline_fill_stroke_with_hatch(
+                &self,
+                stroke_weight: f64,
+                _pen_width: f64,
+                _pattern: Hatches,
+                _angle: f64,
+            ) -> Result<MultiLineString<f64>, Box<dyn Error>> {
+                let polys = self.outline_stroke(stroke_weight)?;
+                Ok(MultiLineString::new(vec![polys.0[0].exterior().clone()]))
+            }
+        }
+        let styled = Square
+            .outline_stroke_styled(2.0, JoinStyle::Mitre, CapStyle::Flat)
+            .unwrap();
+        assert_eq!(styled, Square.outline_stroke(2.0).unwrap());
+        let styled = Square
+            .outline_fill_stroke_with_hatch_styled(
+                2.0,
+                0.5,
+                Hatches::line(),
+                0.0,
+                JoinStyle::Bevel,
+                CapStyle::Square,
+            )
+            .unwrap();
+        assert_eq!(
+            styled,
+            Square
+                .outline_fill_stroke_with_hatch(2.0, 0.5, Hatches::line(), 0.0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hatches_eq() {
+        fn is_eq<T: Eq>(_: &T) {}
+        is_eq(&Hatches::line());
+        assert_eq!(Hatches::voronoi(0.5, 1), Hatches::voronoi(0.5, 1));
+        assert_ne!(Hatches::voronoi(0.5, 1), Hatches::voronoi(0.5, 2));
+        assert_ne!(Hatches::wave(1.0, 5.0), Hatches::wave(1.0, 6.0));
+        // Unlike an f64, a NaN hatch is still equal to itself.
+        assert_eq!(Hatches::brick(f64::NAN, 2.0), Hatches::brick(f64::NAN, 2.0));
+    }
+
+    #[test]
+    fn test_voronoi_hatch_density() {
+        let poly =
+            Rect::<f64>::new(coord! {x: 0.0, y: 0.0}, coord! {x: 20.0, y: 20.0}).to_polygon();
+        let sparse = poly
+            .hatch(Hatches::voronoi(0.05, 42), 0.0, 0.1, 0.0)
+            .expect("Sparse voronoi hatch failed");
+        let dense = poly
+            .hatch(Hatches::voronoi(0.5, 42), 0.0, 0.1, 0.0)
+            .expect("Dense voronoi hatch failed");
+        assert!(sparse.0.len() > 0);
+        assert!(dense.0.len() > sparse.0.len());
+        for line in dense.iter().chain(sparse.iter()) {
+            for c in line.coords() {
+                assert!(c.x >= -1e-6 && c.x <= 20.0 + 1e-6);
+                assert!(c.y >= -1e-6 && c.y <= 20.0 + 1e-6);
+            }
+        }
+        // Same seed, same crackle.
+        let again = poly
+            .hatch(Hatches::voronoi(0.05, 42), 0.0, 0.1, 0.0)
+            .expect("Repeat voronoi hatch failed");
+        assert_eq!(again, sparse);
+    }
+
     #[test]
     fn test_polygon_inset_disjoint() {
         let poly = Polygon::<f64>::new(
@@ -0,0 +1,117 @@
+use geo::area::Area;
+use geo::convex_hull::ConvexHull;
+use geo::coords_iter::CoordsIter;
+use geo_types::{coord, Coordinate, Geometry, LineString, MultiLineString, MultiPoint, Polygon};
+
+/// Hulls for layout and nesting. `convex_hull` is the rubber band around all the
+/// points, and `min_rotated_rect` is the smallest (by area) rectangle that fits
+/// around them at any angle. It comes back with that angle in degrees (0..90),
+/// so you can rotate by -angle to square the thing up.
+pub trait Hull {
+    fn convex_hull(&self) -> Polygon<f64>;
+
+    fn min_rotated_rect(&self) -> (Polygon<f64>, f64);
+}
+
+/// Rotating calipers, the lazy way: the best rectangle always has one side flush
+/// with a hull edge, so try every edge and keep the smallest.
+fn min_rect_for_hull(hull: &Polygon<f64>) -> (Polygon<f64>, f64) {
+    let ring = &hull.exterior().0;
+    let mut best: Option<(f64, f64, [Coordinate<f64>; 2])> = None;
+    for edge in ring.windows(2) {
+        let delta = edge[1] - edge[0];
+        if delta.x == 0.0 && delta.y == 0.0 {
+            continue;
+        }
+        let angle = delta.y.atan2(delta.x);
+        let (sin, cos) = (-angle).sin_cos();
+        let (mut min, mut max) = (
+            coord! {x: f64::MAX, y: f64::MAX},
+            coord! {x: f64::MIN, y: f64::MIN},
+        );
+        for c in ring {
+            let (x, y) = (c.x * cos - c.y * sin, c.x * sin + c.y * cos);
+            min = coord! {x: min.x.min(x), y: min.y.min(y)};
+            max = coord! {x: max.x.max(x), y: max.y.max(y)};
+        }
+        let area = (max.x - min.x) * (max.y - min.y);
+        if best.map_or(true, |(best_area, _, _)| area < best_area) {
+            best = Some((area, angle, [min, max]));
+        }
+    }
+    match best {
+        Some((_area, angle, [min, max])) => {
+            let (sin, cos) = angle.sin_cos();
+            let unrotate = |x: f64, y: f64| coord! {x: x * cos - y * sin, y: x * sin + y * cos};
+            let rect = Polygon::new(
+                LineString::new(vec![
+                    unrotate(min.x, min.y),
+                    unrotate(max.x, min.y),
+                    unrotate(max.x, max.y),
+                    unrotate(min.x, max.y),
+                    unrotate(min.x, min.y),
+                ]),
+                vec![],
+            );
+            (rect, angle.to_degrees().rem_euclid(90.0))
+        }
+        None => (hull.clone(), 0.0),
+    }
+}
+
+impl Hull for Geometry<f64> {
+    fn convex_hull(&self) -> Polygon<f64> {
+        MultiPoint::from(self.coords_iter().collect::<Vec<Coordinate<f64>>>()).convex_hull()
+    }
+
+    fn min_rotated_rect(&self) -> (Polygon<f64>, f64) {
+        let hull = Hull::convex_hull(self);
+        if hull.unsigned_area() == 0.0 {
+            return (hull, 0.0);
+        }
+        min_rect_for_hull(&hull)
+    }
+}
+
+impl Hull for MultiLineString<f64> {
+    fn convex_hull(&self) -> Polygon<f64> {
+        Hull::convex_hull(&Geometry::MultiLineString(self.clone()))
+    }
+
+    fn min_rotated_rect(&self) -> (Polygon<f64>, f64) {
+        Geometry::MultiLineString(self.clone()).min_rotated_rect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use geo::rotate::RotatePoint;
+    use geo_types::Point;
+
+    #[test]
+    fn test_min_rotated_rect() {
+        let points = MultiPoint::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(20.0, 0.0),
+            Point::new(20.0, 10.0),
+            Point::new(0.0, 10.0),
+            Point::new(10.0, 5.0),
+            Point::new(3.0, 7.0),
+        ])
+        .rotate_around_point(30.0, Point::new(0.0, 0.0));
+        let geo = Geometry::MultiPoint(points);
+        let hull = Hull::convex_hull(&geo);
+        assert_eq!(hull.exterior().0.len(), 5);
+        let (rect, angle) = geo.min_rotated_rect();
+        assert!((angle - 30.0).abs() < 1.0, "Angle was {}", angle);
+        assert!((rect.unsigned_area() - 200.0).abs() < 1e-6);
+
+        let lines = MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 5.0, y: 5.0},
+            coord! {x: 10.0, y: 0.0},
+        ])]);
+        assert!((Hull::convex_hull(&lines).unsigned_area() - 25.0).abs() < 1e-9);
+    }
+}
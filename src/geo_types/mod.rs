@@ -37,6 +37,27 @@ pub mod matrix;
 /// Various shapes
 pub mod shapes;
 
+/// Chop lines up into dashes, since a plotter can't do stroke-dasharray
+pub mod dash;
+
+/// Resample lines to evenly spaced points
+pub mod resample;
+
+/// Chaikin corner cutting, to smooth out jaggy lines
+pub mod smooth;
+
+/// Simplify that won't wreck closed rings
+pub mod simplify;
+
+/// Convex hulls and minimum area rotated bounding boxes
+pub mod hull;
+
+/// Round off the corners of polygons
+pub mod fillet;
+
+/// Approximate skeletons (centerlines) of polygons, for single pass engraving
+pub mod skeleton;
+
 /// Trait that implements a distance function between two [`geo_types::Point`] structs.
 /// Also includes a length function which returns the length of a [`geo_types::Point`]
 /// as if it were a Vector.
@@ -167,27 +188,18 @@ pub trait ToGeos {
 impl ToGeos for geo_types::Geometry<f64> {
     fn to_geos(&self) -> Result<geos::Geometry, Box<dyn Error>> {
         if let Geometry::GeometryCollection(collection) = self {
-            let geomap: Vec<geos::Geometry> = collection
+            // If anything in here won't convert, the whole thing fails, rather than
+            // quietly dropping bits of the drawing.
+            let geomap = collection
                 .iter()
-                .map(|item| {
-                    item.to_geos()
-                        .or(geos::Geometry::create_empty_collection(
-                            geos::GeometryTypes::GeometryCollection,
-                        ))
-                        .unwrap()
-                })
-                // .map_ok(|x|x)
-                .collect();
-            if let Ok(geosmap) = geos::Geometry::create_geometry_collection(geomap) {
-                return Ok(geosmap);
-            } else {
-                return Err(Box::new(geos::Error::InvalidGeometry(
-                    "Wrong type of geometry".into(),
-                )));
-            }
+                .map(|item| item.to_geos())
+                .collect::<Result<Vec<geos::Geometry>, Box<dyn Error>>>()?;
+            return Ok(geos::Geometry::create_geometry_collection(geomap)?);
         }
         Ok(match self {
             Geometry::Point(p) => geos::Geometry::try_from(p),
+            Geometry::MultiPoint(points) => geos::Geometry::try_from(points),
+            Geometry::Triangle(triangle) => geos::Geometry::try_from(triangle.to_polygon()),
             Geometry::Line(line) => geos::Geometry::create_line_string(
                 CoordSeq::new_from_vec(&vec![
                     vec![line.start.x, line.start.y],
@@ -227,10 +239,34 @@ impl ToGeos for geo_types::Geometry<f64> {
 
 #[cfg(test)]
 mod tests {
-    use super::PointDistance;
-    use geo_types::Point;
+    use super::{PointDistance, ToGeos};
+    use geo_types::{coord, Geometry, GeometryCollection, Point, Rect, Triangle};
     use num_traits::abs;
 
+    #[test]
+    fn test_collection_to_geos() {
+        let square = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 10.0}).to_polygon();
+        let collection = Geometry::GeometryCollection(GeometryCollection::new_from(vec![
+            Geometry::Polygon(square.clone()),
+            Geometry::Point(Point::new(20.0, 20.0)),
+            Geometry::Triangle(Triangle(
+                coord! {x: 0.0, y: 0.0},
+                coord! {x: 1.0, y: 0.0},
+                coord! {x: 0.0, y: 1.0},
+            )),
+        ]));
+        let back = Geometry::<f64>::try_from(collection.to_geos().unwrap()).unwrap();
+        match back {
+            Geometry::GeometryCollection(items) => {
+                assert_eq!(items.0.len(), 3);
+                assert_eq!(items.0[0], Geometry::Polygon(square));
+                assert_eq!(items.0[1], Geometry::Point(Point::new(20.0, 20.0)));
+                assert!(matches!(items.0[2], Geometry::Polygon(_)));
+            }
+            _ => panic!("Expected a collection back, got {:?}", back),
+        }
+    }
+
     #[test]
     fn test_length() {
         let p = Point::new(10.0f64, 0.0f64);
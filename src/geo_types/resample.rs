@@ -0,0 +1,66 @@
+use geo_types::{Coordinate, LineString, MultiLineString};
+
+/// Resamples lines to evenly spaced points, walking along the line by arc length.
+/// You get a point every `spacing` units, and the first and last vertices are
+/// always kept (so the last gap is usually a bit short). Corners between the
+/// samples get cut, so keep the spacing small if you care about the shape.
+pub trait Resample
+where
+    Self: Sized,
+{
+    fn resample(&self, spacing: f64) -> Self;
+}
+
+impl Resample for LineString<f64> {
+    fn resample(&self, spacing: f64) -> Self {
+        if self.0.len() < 2 || spacing.is_nan() || spacing <= 0.0 {
+            return self.clone();
+        }
+        let mut out: Vec<Coordinate<f64>> = vec![self.0[0]];
+        // Distance along the line to the start of the current segment, and the next sample.
+        let mut travelled = 0.0;
+        let mut next = spacing;
+        for segment in self.lines() {
+            let delta = segment.end - segment.start;
+            let length = delta.x.hypot(delta.y);
+            while length > 0.0 && next <= travelled + length {
+                out.push(segment.start + delta * ((next - travelled) / length));
+                next += spacing;
+            }
+            travelled += length;
+        }
+        // Don't double up the last point if a sample landed on (or really close to) it.
+        let last = *self.0.last().unwrap();
+        if next - spacing > travelled - spacing * 1e-9 && out.len() > 1 {
+            out.pop();
+        }
+        out.push(last);
+        LineString::new(out)
+    }
+}
+
+impl Resample for MultiLineString<f64> {
+    fn resample(&self, spacing: f64) -> Self {
+        MultiLineString::new(self.iter().map(|line| line.resample(spacing)).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use geo_types::coord;
+
+    #[test]
+    fn test_resample_line() {
+        let line = LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 0.0}]);
+        let resampled = line.resample(10.0);
+        assert_eq!(resampled.0.len(), 11);
+        for (i, point) in resampled.0.iter().enumerate() {
+            assert!((point.x - 10.0 * i as f64).abs() < 1e-9);
+        }
+        // Uneven ends keep the last vertex
+        let resampled = line.resample(30.0);
+        let xs: Vec<f64> = resampled.0.iter().map(|c| c.x).collect();
+        assert_eq!(xs, vec![0.0, 30.0, 60.0, 90.0, 100.0]);
+    }
+}
@@ -58,6 +58,34 @@ pub fn circle(x0: f64, y0: f64, radius: f64) -> Geometry<f64> {
     regular_poly(sides, x0, y0, radius, 0.0)
 }
 
+/// An ellipse with radii rx/ry, rotated clockwise by `rotation` compass degrees. Uses
+/// just enough segments that the flats are never more than `accuracy` off the curve.
+pub fn ellipse(cx: f64, cy: f64, rx: f64, ry: f64, rotation: f64, accuracy: f64) -> Geometry<f64> {
+    let (rx, ry) = (rx.abs(), ry.abs());
+    let max_radius = rx.max(ry);
+    if max_radius == 0.0 {
+        return Geometry::Point(Point::new(cx, cy));
+    }
+    // Sagitta of a chord spanning theta is r*(1-cos(theta/2)), so solve for theta.
+    let step = 2.0 * (1.0 - (accuracy.abs() / max_radius).min(1.0)).acos();
+    let sides = usize::from_f64((2.0 * PI / step).ceil())
+        .unwrap_or(1000)
+        .clamp(8, 1000);
+    let (sin_r, cos_r) = (PI * rotation / 180.0).sin_cos();
+    Geometry::Polygon(Polygon::new(
+        LineString::new(
+            (0..=sides)
+                .map(|i| {
+                    let angle = (i % sides) as f64 / sides as f64 * 2.0 * PI;
+                    let (px, py) = (rx * angle.cos(), ry * angle.sin());
+                    coord! {x: cx + px * cos_r - py * sin_r, y: cy + px * sin_r + py * cos_r}
+                })
+                .collect(),
+        ),
+        vec![],
+    ))
+}
+
 /// Draw an arc, centered on a point. Degrees are compass degrees again, sorry.
 pub fn arc_center(x0: f64, y0: f64, radius: f64, deg0: f64, deg1: f64) -> LineString<f64> {
     let radius = radius.abs();
@@ -0,0 +1,80 @@
+use geo::simplify::Simplify;
+use geo_types::{LineString, MultiLineString, Polygon};
+
+/// Like geo's simplify, but careful with closed rings. Plain Douglas-Peucker will
+/// happily squash a skinny ring down to a couple of points, which isn't a ring any
+/// more, and GEOS gets upset when you try to hatch it. Here a closed ring stays
+/// closed (exactly, first == last), and if simplifying would leave it with fewer
+/// than 4 points, it's left as it was. Open lines just get simplified.
+pub trait SimplifyRings {
+    fn simplify_preserving_rings(&self, tolerance: &f64) -> Self;
+}
+
+impl SimplifyRings for LineString<f64> {
+    fn simplify_preserving_rings(&self, tolerance: &f64) -> Self {
+        if self.0.len() < 4 || !self.is_closed() {
+            return self.simplify(tolerance);
+        }
+        let mut ring = self.simplify(tolerance);
+        if ring.0.len() < 4 {
+            return self.clone();
+        }
+        let first = ring.0[0];
+        if let Some(last) = ring.0.last_mut() {
+            *last = first;
+        }
+        ring
+    }
+}
+
+impl SimplifyRings for MultiLineString<f64> {
+    fn simplify_preserving_rings(&self, tolerance: &f64) -> Self {
+        MultiLineString::new(
+            self.iter()
+                .map(|line| line.simplify_preserving_rings(tolerance))
+                .collect(),
+        )
+    }
+}
+
+impl SimplifyRings for Polygon<f64> {
+    fn simplify_preserving_rings(&self, tolerance: &f64) -> Self {
+        Polygon::new(
+            self.exterior().simplify_preserving_rings(tolerance),
+            self.interiors()
+                .iter()
+                .map(|ring| ring.simplify_preserving_rings(tolerance))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geo_types::hatch::{Hatch, Hatches};
+    use geo_types::coord;
+
+    #[test]
+    fn test_simplify_skinny_triangle() {
+        let triangle = Polygon::new(
+            LineString::new(vec![
+                coord! {x: 0.0, y: 0.0},
+                coord! {x: 10.0, y: 0.0},
+                coord! {x: 5.0, y: 0.05},
+                coord! {x: 0.0, y: 0.0},
+            ]),
+            vec![],
+        );
+        // Plain simplify flattens it right out...
+        assert!(triangle.exterior().simplify(&0.1).0.len() < 4);
+        // ...but not this one.
+        let simple = triangle.simplify_preserving_rings(&0.1);
+        assert_eq!(simple.exterior().0.len(), 4);
+        assert!(simple.exterior().is_closed());
+        assert!(simple.hatch(Hatches::line(), 45.0, 0.01, 0.0).is_ok());
+
+        let open = LineString::new(triangle.exterior().0[..3].to_vec());
+        assert_eq!(open.simplify_preserving_rings(&0.1).0.len(), 2);
+    }
+}
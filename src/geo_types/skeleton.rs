@@ -0,0 +1,161 @@
+use crate::geo_types::hatch::gt_flatten_mlines;
+use geo::prelude::{EuclideanDistance, EuclideanLength};
+use geo_types::{
+    coord, Coordinate, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon,
+};
+use geos::Geom;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// How close to the widest part of the shape (as a fraction of the largest
+/// inscribed radius) a skeleton edge has to be to survive. Anything less is
+/// one of those little spurs that runs off into a corner.
+const SPINE_RATIO: f64 = 0.75;
+
+/// #Centerline
+///
+/// Approximates the medial axis (aka skeleton) of a shape: the line running down
+/// the middle of every stroke. Really useful for engraving-style lettering, where
+/// you want to run a thin stroke once instead of outlining and hatching it.
+///
+/// This is the cheap-and-cheerful version: we densify the boundary, take the
+/// voronoi edges that land inside the shape, and prune everything that wanders
+/// off into corners. Works best on shapes with a fairly uniform stroke width,
+/// like the outline fonts.
+pub trait Centerline {
+    fn centerline(&self, accuracy: f64) -> Result<MultiLineString<f64>, Box<dyn Error>>;
+}
+
+/// Chops a ring up so there's a point at least every `step` units.
+fn densify(ring: &LineString<f64>, step: f64) -> Vec<Point<f64>> {
+    let mut out = vec![];
+    for line in ring.lines() {
+        let steps = (line.dx().hypot(line.dy()) / step).ceil().max(1.0) as usize;
+        for i in 0..steps {
+            let t = i as f64 / steps as f64;
+            out.push(Point::from(coord! {
+                x: line.start.x + line.dx() * t,
+                y: line.start.y + line.dy() * t
+            }));
+        }
+    }
+    out
+}
+
+/// Distance from a coordinate to the nearest edge of the polygon.
+fn boundary_distance(poly: &Polygon<f64>, coord: &Coordinate<f64>) -> f64 {
+    let point = Point::from(*coord);
+    std::iter::once(poly.exterior())
+        .chain(poly.interiors().iter())
+        .map(|ring| point.euclidean_distance(ring))
+        .fold(f64::MAX, f64::min)
+}
+
+/// Merges touching lines into longer ones, via geos.
+fn merge_lines(lines: MultiLineString<f64>) -> Result<MultiLineString<f64>, Box<dyn Error>> {
+    if lines.0.is_empty() {
+        return Ok(lines);
+    }
+    let merged = geos::Geometry::try_from(&lines)?.line_merge()?;
+    Ok(gt_flatten_mlines(
+        geo_types::Geometry::try_from(merged)?,
+        MultiLineString::new(vec![]),
+    ))
+}
+
+impl Centerline for Polygon<f64> {
+    fn centerline(&self, accuracy: f64) -> Result<MultiLineString<f64>, Box<dyn Error>> {
+        let sites: Vec<Point<f64>> = std::iter::once(self.exterior())
+            .chain(self.interiors().iter())
+            .flat_map(|ring| densify(ring, accuracy))
+            .collect();
+        if sites.len() < 3 {
+            return Ok(MultiLineString::new(vec![]));
+        }
+        let geos_self = geos::Geometry::try_from(self)?;
+        let edges = geos::Geometry::try_from(&MultiPoint::new(sites))?
+            .voronoi(Some(&geos_self), 0.0, true)?
+            .intersection(&geos_self)?;
+        let edges = gt_flatten_mlines(
+            geo_types::Geometry::try_from(edges)?,
+            MultiLineString::new(vec![]),
+        );
+
+        // Toss anything that isn't near the middle of the widest bit...
+        let segments: Vec<(LineString<f64>, f64, f64)> = edges
+            .0
+            .iter()
+            .flat_map(|line| line.lines())
+            .map(|seg| {
+                (
+                    LineString::from(vec![seg.start, seg.end]),
+                    boundary_distance(self, &seg.start),
+                    boundary_distance(self, &seg.end),
+                )
+            })
+            .collect();
+        let radius = segments
+            .iter()
+            .map(|(_seg, d0, d1)| d0.max(*d1))
+            .fold(0.0, f64::max);
+        let spine = merge_lines(MultiLineString::new(
+            segments
+                .into_iter()
+                .filter(|(_seg, d0, d1)| d0.min(*d1) >= radius * SPINE_RATIO)
+                .map(|(seg, _d0, _d1)| seg)
+                .collect(),
+        ))?;
+
+        // ...then snip off the short stubs left dangling where the corners were.
+        let key = |c: &Coordinate<f64>| (c.x.to_bits(), c.y.to_bits());
+        let mut degree: HashMap<(u64, u64), usize> = HashMap::new();
+        for line in &spine.0 {
+            for end in [line.0.first(), line.0.last()].into_iter().flatten() {
+                *degree.entry(key(end)).or_insert(0) += 1;
+            }
+        }
+        let dangling = |c: Option<&Coordinate<f64>>| match c {
+            Some(c) => degree.get(&key(c)) == Some(&1),
+            None => true,
+        };
+        let pruned: Vec<LineString<f64>> = spine
+            .0
+            .iter()
+            .filter(|line| {
+                spine.0.len() == 1
+                    || line.euclidean_length() >= radius
+                    || !(dangling(line.0.first()) || dangling(line.0.last()))
+            })
+            .cloned()
+            .collect();
+        merge_lines(MultiLineString::new(pruned))
+    }
+}
+
+impl Centerline for MultiPolygon<f64> {
+    fn centerline(&self, accuracy: f64) -> Result<MultiLineString<f64>, Box<dyn Error>> {
+        let mut lines = MultiLineString::new(vec![]);
+        for poly in self {
+            lines.0.append(&mut poly.centerline(accuracy)?.0);
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use geo::prelude::BoundingRect;
+    use geo_types::Rect;
+
+    #[test]
+    fn test_rect_centerline() {
+        let bar = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 2.0, y: 20.0}).to_polygon();
+        let lines = bar.centerline(0.1).unwrap();
+        assert_eq!(lines.0.len(), 1);
+        let bounds = lines.bounding_rect().unwrap();
+        assert!((bounds.center().x - 1.0).abs() < 0.01);
+        assert!(bounds.width() < 0.01);
+        assert!(bounds.height() > 15.0);
+    }
+}
@@ -0,0 +1,108 @@
+use geo_types::{Coordinate, LineString, MultiLineString};
+
+/// Corner cutting (Chaikin) smoothing, handy for the jaggy output from turtles and
+/// L-systems. Every iteration replaces each segment with points 1/4 and 3/4 of the
+/// way along it, which rounds off the corners and doubles the point count.
+/// An open line keeps its end points where they are. A closed line (first point
+/// equals the last) gets smoothed all the way around, and stays closed.
+pub trait Smooth
+where
+    Self: Sized,
+{
+    /// Smooths, treating closed LineStrings as rings.
+    fn chaikin(&self, iterations: u32) -> Self;
+
+    /// Smooths, but you get to decide if it's a ring. Treating an open line as
+    /// closed smooths the gap between its ends too, and closes it.
+    fn chaikin_closed(&self, iterations: u32, closed: bool) -> Self;
+}
+
+fn cut_corners(coords: &[Coordinate<f64>], closed: bool) -> Vec<Coordinate<f64>> {
+    let quarter = |a: Coordinate<f64>, b: Coordinate<f64>| a * 0.75 + b * 0.25;
+    let mut out = vec![];
+    if closed {
+        for i in 0..coords.len() {
+            let (a, b) = (coords[i], coords[(i + 1) % coords.len()]);
+            out.push(quarter(a, b));
+            out.push(quarter(b, a));
+        }
+    } else {
+        out.push(coords[0]);
+        for pair in coords.windows(2) {
+            out.push(quarter(pair[0], pair[1]));
+            out.push(quarter(pair[1], pair[0]));
+        }
+        out.push(coords[coords.len() - 1]);
+    }
+    out
+}
+
+impl Smooth for LineString<f64> {
+    fn chaikin(&self, iterations: u32) -> Self {
+        self.chaikin_closed(iterations, self.is_closed())
+    }
+
+    fn chaikin_closed(&self, iterations: u32, closed: bool) -> Self {
+        if self.0.len() < 3 {
+            return self.clone();
+        }
+        let mut coords = self.0.clone();
+        // Work on the ring without its closing point, and put it back at the end.
+        if closed && self.is_closed() {
+            coords.pop();
+        }
+        for _ in 0..iterations {
+            coords = cut_corners(&coords, closed);
+        }
+        if closed {
+            coords.push(coords[0]);
+        }
+        LineString::new(coords)
+    }
+}
+
+impl Smooth for MultiLineString<f64> {
+    fn chaikin(&self, iterations: u32) -> Self {
+        MultiLineString::new(self.iter().map(|line| line.chaikin(iterations)).collect())
+    }
+
+    fn chaikin_closed(&self, iterations: u32, closed: bool) -> Self {
+        MultiLineString::new(
+            self.iter()
+                .map(|line| line.chaikin_closed(iterations, closed))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use geo_types::coord;
+
+    #[test]
+    fn test_chaikin_square() {
+        let square = LineString::new(vec![
+            coord! {x: 0.0, y: 0.0},
+            coord! {x: 4.0, y: 0.0},
+            coord! {x: 4.0, y: 4.0},
+            coord! {x: 0.0, y: 4.0},
+            coord! {x: 0.0, y: 0.0},
+        ]);
+        let smooth = square.chaikin(1);
+        // 8 vertices, plus the closing point
+        assert!(smooth.is_closed());
+        assert_eq!(smooth.0.len(), 9);
+        assert_eq!(smooth.0[0], coord! {x: 1.0, y: 0.0});
+        assert_eq!(smooth.0[1], coord! {x: 3.0, y: 0.0});
+        assert_eq!(square.chaikin(2).0.len(), 17);
+
+        // Open lines keep their ends
+        let open = LineString::new(square.0[..4].to_vec());
+        let smooth = open.chaikin(1);
+        assert_eq!(smooth.0.len(), 8);
+        assert_eq!(smooth.0[0], open.0[0]);
+        assert_eq!(smooth.0[7], open.0[3]);
+        assert_eq!(open.chaikin_closed(1, true).0.len(), 9);
+    }
+}
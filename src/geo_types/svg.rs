@@ -1,17 +1,25 @@
 use geo::bounding_rect::BoundingRect;
-use geo_types::{CoordNum, Coordinate, LineString, MultiLineString, Point, Rect};
+use geo_types::{
+    coord, CoordNum, Coordinate, Geometry, GeometryCollection, LineString, MultiLineString, Point,
+    Polygon, Rect,
+};
+use kurbo::{Affine, BezPath, PathEl, Shape};
 use nalgebra::{Affine2, Matrix3, Point2 as NPoint2, RealField};
 use num_traits::real::Real;
 use num_traits::{AsPrimitive, Float, FromPrimitive, ToPrimitive};
 use std::ops::Div;
 use svg::node::element::path::Data;
-use svg::node::element::Path;
+use svg::node::element::tag::Type as TagType;
+use svg::node::element::{Group, Path};
+use svg::parser::Event;
 use svg::Document;
 
 /// Generic error
 #[derive(Debug)]
 pub enum SvgCreationError {
     UndefinedViewBox,
+    /// Couldn't make sense of the SVG we were asked to read.
+    ParseError(String),
 }
 
 /// An arrangement is a plan for transformation of an SVG
@@ -24,6 +32,11 @@ where
     Center(Rect<T>, bool),
     FitCenter(Rect<T>, bool),
     FitCenterMargin(T, Rect<T>, bool),
+    /// Like FitCenter, but turns the geometry 90 degrees if that lets it fit bigger.
+    FitCenterRotate(Rect<T>, bool),
+    /// 1:1 scale on a page that's probably too small, with the given overlap between
+    /// pages. See [`Arrangement::tile`].
+    Tile(Rect<T>, T),
     Transform(Rect<T>, Affine2<T>),
 }
 
@@ -35,6 +48,8 @@ impl<T: RealField + Float> Arrangement<T> {
             Arrangement::Center(viewbox, _invert) => viewbox,
             Arrangement::FitCenter(viewbox, _invert) => viewbox,
             Arrangement::FitCenterMargin(_margin, viewbox, _invert) => viewbox,
+            Arrangement::FitCenterRotate(viewbox, _invert) => viewbox,
+            Arrangement::Tile(viewbox, _overlap) => viewbox,
             Arrangement::Transform(viewbox, _affine) => viewbox,
         };
         Arrangement::<T>::Transform(viewbox.clone(), self.affine(geo_bounds))
@@ -45,6 +60,8 @@ impl<T: RealField + Float> Arrangement<T> {
             Arrangement::Center(viewbox, _invert) => viewbox.clone(),
             Arrangement::FitCenter(viewbox, _invert) => viewbox.clone(),
             Arrangement::FitCenterMargin(_margin, viewbox, _invert) => viewbox.clone(),
+            Arrangement::FitCenterRotate(viewbox, _invert) => viewbox.clone(),
+            Arrangement::Tile(viewbox, _overlap) => viewbox.clone(),
             Arrangement::Transform(viewbox, _affine) => viewbox.clone(),
         }
     }
@@ -157,7 +174,99 @@ impl<T: RealField + Float> Arrangement<T> {
                     tx
                 }
             }
+            Arrangement::Tile(page, _overlap) => {
+                // On its own, a tiled arrangement is just the first (top left) page.
+                let delta = page.min() - geo_bounds.min();
+                Affine2::from_matrix_unchecked(Matrix3::<T>::new(
+                    T::one(),
+                    T::zero(),
+                    delta.x,
+                    T::zero(),
+                    T::one(),
+                    delta.y,
+                    T::zero(),
+                    T::zero(),
+                    T::one(),
+                ))
+            }
+            Arrangement::FitCenterRotate(bounds, invert) => {
+                let fit = Arrangement::FitCenter(bounds.clone(), *invert);
+                let scale = <T as Real>::min(
+                    bounds.width() / geo_bounds.width(),
+                    bounds.height() / geo_bounds.height(),
+                );
+                let rotated_scale = <T as Real>::min(
+                    bounds.width() / geo_bounds.height(),
+                    bounds.height() / geo_bounds.width(),
+                );
+                if rotated_scale > scale {
+                    // Quarter turn around the origin, (x, y) -> (-y, x), then fit the
+                    // rotated bounds just like FitCenter would.
+                    let rotate = Affine2::from_matrix_unchecked(Matrix3::<T>::new(
+                        T::zero(),
+                        -T::one(),
+                        T::zero(),
+                        T::one(),
+                        T::zero(),
+                        T::zero(),
+                        T::zero(),
+                        T::zero(),
+                        T::one(),
+                    ));
+                    let rotated_bounds = Rect::new(
+                        coord! {x: -geo_bounds.max().y, y: geo_bounds.min().x},
+                        coord! {x: -geo_bounds.min().y, y: geo_bounds.max().x},
+                    );
+                    fit.affine(&rotated_bounds) * rotate
+                } else {
+                    fit.affine(geo_bounds)
+                }
+            }
+        }
+    }
+
+    /// Plots at 1:1 across as many `page` sized sheets as it takes, with `overlap` mm
+    /// of bleed shared between neighbouring sheets so you can line them up. Use it with
+    /// `Context::to_svg_pages` to get one document per sheet.
+    pub fn tile(page: Rect<T>, overlap: T) -> Arrangement<T> {
+        Arrangement::Tile(page, overlap)
+    }
+
+    /// For a [`Arrangement::Tile`], the page sized pieces (in drawing coordinates)
+    /// that `geo_bounds` gets cut into, a row at a time. Anything else only has the
+    /// one page, which is the whole of `geo_bounds`.
+    pub fn tiles(&self, geo_bounds: &Rect<T>) -> Vec<Rect<T>> {
+        let (page, overlap) = match self {
+            Arrangement::Tile(page, overlap) => (page, *overlap),
+            _ => return vec![*geo_bounds],
+        };
+        let count = |size: T, page_size: T| {
+            let stride = page_size - overlap;
+            if stride <= T::zero() || size <= page_size {
+                1
+            } else {
+                Float::ceil((size - overlap) / stride)
+                    .to_usize()
+                    .unwrap_or(1)
+            }
+        };
+        let columns = count(geo_bounds.width(), page.width());
+        let rows = count(geo_bounds.height(), page.height());
+        let mut tiles = vec![];
+        for row in 0..rows {
+            for column in 0..columns {
+                let min = coord! {
+                    x: geo_bounds.min().x
+                        + T::from(column).unwrap() * (page.width() - overlap),
+                    y: geo_bounds.min().y + T::from(row).unwrap() * (page.height() - overlap),
+                };
+                tiles.push(Rect::new(
+                    min,
+                    coord! {x: min.x + page.width(), y: min.y + page.height()},
+                ));
+            }
         }
+        tiles
     }
 
     pub fn unit(window: &Rect<T>) -> Arrangement<T> {
@@ -177,6 +286,50 @@ impl<T: RealField + Float> Arrangement<T> {
         )
     }
 
+    /// A FitCenterMargin arrangement over a `width` x `height` mm page, with the
+    /// default 10mm margin. The page presets below all use this.
+    pub fn page(width: f64, height: f64) -> Arrangement<T> {
+        let mm = |v: f64| T::from(v).unwrap();
+        Arrangement::FitCenterMargin(
+            mm(10.0),
+            Rect::new(
+                coord! {x: T::zero(), y: T::zero()},
+                coord! {x: mm(width), y: mm(height)},
+            ),
+            false,
+        )
+    }
+
+    /// A4 (210x297mm), tall.
+    pub fn a4_portrait() -> Arrangement<T> {
+        Arrangement::page(210.0, 297.0)
+    }
+
+    /// A4 (297x210mm), wide.
+    pub fn a4_landscape() -> Arrangement<T> {
+        Arrangement::page(297.0, 210.0)
+    }
+
+    /// A3 (297x420mm), tall.
+    pub fn a3_portrait() -> Arrangement<T> {
+        Arrangement::page(297.0, 420.0)
+    }
+
+    /// A3 (420x297mm), wide.
+    pub fn a3_landscape() -> Arrangement<T> {
+        Arrangement::page(420.0, 297.0)
+    }
+
+    /// US Letter (8.5x11in, 215.9x279.4mm), tall.
+    pub fn letter_portrait() -> Arrangement<T> {
+        Arrangement::page(215.9, 279.4)
+    }
+
+    /// US Letter (11x8.5in, 279.4x215.9mm), wide.
+    pub fn letter_landscape() -> Arrangement<T> {
+        Arrangement::page(279.4, 215.9)
+    }
+
     pub fn create_svg_document(&self) -> Result<Document, SvgCreationError>
     where
         T: Real,
@@ -211,7 +364,9 @@ impl<T: RealField + Float> Arrangement<T> {
                 )
                 .set("width", format!("{}mm", viewbox.width()))
                 .set("height", format!("{}mm", viewbox.height()))),
-            Arrangement::FitCenter(viewbox, _invert) => Ok(Document::new()
+            Arrangement::FitCenter(viewbox, _)
+            | Arrangement::FitCenterRotate(viewbox, _)
+            | Arrangement::Tile(viewbox, _) => Ok(Document::new()
                 .set(
                     "viewBox",
                     (
@@ -237,6 +392,238 @@ impl<T: RealField + Float> Arrangement<T> {
                 .set("height", format!("{}mm", viewbox.height()))),
         }
     }
+
+    /// Same as [`Arrangement::create_svg_document`], but the first thing in the document
+    /// is a rect covering the whole viewbox, filled with `fill`. Handy for previewing
+    /// on colored paper. It's only in the SVG, so it never turns into pen moves.
+    pub fn create_svg_document_with_background(
+        &self,
+        fill: &str,
+    ) -> Result<Document, SvgCreationError>
+    where
+        T: ToPrimitive,
+        T: FromPrimitive,
+        f64: From<T>,
+    {
+        let viewbox = self.viewbox();
+        let background = svg::node::element::Rectangle::new()
+            .set("id", "background")
+            .set("x", f64::from(viewbox.min().x))
+            .set("y", f64::from(viewbox.min().y))
+            .set("width", f64::from(viewbox.width()))
+            .set("height", f64::from(viewbox.height()))
+            .set("fill", fill)
+            .set("stroke", "none");
+        Ok(self.create_svg_document()?.add(background))
+    }
+
+    /// Draws an L shaped registration mark `size` long in each corner of the viewbox,
+    /// in their own "registration" layer, so you can line the paper back up between
+    /// passes. Call this on the finished document, after everything else is drawn.
+    /// It declares the inkscape namespace on the document, since the layer uses it.
+    pub fn with_registration_marks(&self, document: Document, size: f64) -> Document {
+        let viewbox = self.viewbox();
+        let (min, max) = (viewbox.min(), viewbox.max());
+        let (x0, y0) = (min.x.to_f64().unwrap(), min.y.to_f64().unwrap());
+        let (x1, y1) = (max.x.to_f64().unwrap(), max.y.to_f64().unwrap());
+        // Each corner, and which way is "into" the page from there.
+        let corners = [
+            (x0, y0, 1.0, 1.0),
+            (x1, y0, -1.0, 1.0),
+            (x1, y1, -1.0, -1.0),
+            (x0, y1, 1.0, -1.0),
+        ];
+        let mut layer = Group::new()
+            .set("id", "registration")
+            .set("inkscape:groupmode", "layer")
+            .set("inkscape:label", "registration")
+            .set("fill", "none")
+            .set("stroke", "black")
+            .set("stroke-width", 0.25);
+        for (i, (x, y, dx, dy)) in corners.iter().enumerate() {
+            let data = Data::new()
+                .move_to((x + dx * size, *y))
+                .line_to((*x, *y))
+                .line_to((*x, y + dy * size));
+            layer = layer.add(
+                Group::new()
+                    .set("id", format!("registration-mark-{}", i))
+                    .add(Path::new().set("d", data)),
+            );
+        }
+        document
+            .set(
+                "xmlns:inkscape",
+                "http://www.inkscape.org/namespaces/inkscape",
+            )
+            .add(layer)
+    }
+}
+
+/// Parses an SVG transform attribute (matrix/translate/scale/rotate/skewX/skewY)
+/// into a single kurbo affine.
+fn parse_transform(transform: &str) -> Result<Affine, SvgCreationError> {
+    let mut out = Affine::IDENTITY;
+    for item in transform.split(')').filter(|item| !item.trim().is_empty()) {
+        let (name, args) = item
+            .split_once('(')
+            .ok_or_else(|| SvgCreationError::ParseError(format!("Bad transform: {}", transform)))?;
+        let args = args
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| arg.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .or(Err(SvgCreationError::ParseError(format!(
+                "Bad transform: {}",
+                transform
+            ))))?;
+        let arg = |i: usize, default: f64| args.get(i).copied().unwrap_or(default);
+        let next = match (name.trim().trim_start_matches(',').trim(), args.len()) {
+            ("matrix", 6) => Affine::new([args[0], args[1], args[2], args[3], args[4], args[5]]),
+            ("translate", 1..=2) => Affine::translate((arg(0, 0.0), arg(1, 0.0))),
+            ("scale", 1..=2) => Affine::scale_non_uniform(arg(0, 1.0), arg(1, arg(0, 1.0))),
+            ("rotate", 1) => Affine::rotate(arg(0, 0.0).to_radians()),
+            ("rotate", 3) => {
+                let center = (arg(1, 0.0), arg(2, 0.0));
+                Affine::translate(center)
+                    * Affine::rotate(arg(0, 0.0).to_radians())
+                    * Affine::translate((-center.0, -center.1))
+            }
+            ("skewX", 1) => Affine::new([1.0, 0.0, arg(0, 0.0).to_radians().tan(), 1.0, 0.0, 0.0]),
+            ("skewY", 1) => Affine::new([1.0, arg(0, 0.0).to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+            _ => {
+                return Err(SvgCreationError::ParseError(format!(
+                    "Bad transform: {}",
+                    transform
+                )))
+            }
+        };
+        out *= next;
+    }
+    Ok(out)
+}
+
+/// Reads the basic drawing elements (path, line, polyline, polygon, rect and circle)
+/// out of an SVG, applying any transforms along the way. Curves get flattened to
+/// within `accuracy`. Closed shapes come back as Polygons, everything else as
+/// MultiLineStrings. It's not a browser, so styles, use/defs, text and friends are
+/// all ignored.
+pub fn from_svg_str(s: &str, accuracy: f64) -> Result<GeometryCollection<f64>, SvgCreationError> {
+    let parser = svg::read(s).map_err(|err| SvgCreationError::ParseError(err.to_string()))?;
+    let mut transforms = vec![Affine::IDENTITY];
+    let mut geometries: Vec<Geometry<f64>> = vec![];
+    for event in parser {
+        let (name, tag_type, attributes) = match event {
+            Event::Tag(name, tag_type, attributes) => (name, tag_type, attributes),
+            Event::Error(err) => return Err(SvgCreationError::ParseError(err.to_string())),
+            _ => continue,
+        };
+        if tag_type == TagType::End {
+            if name == "g" {
+                transforms.pop();
+            }
+            continue;
+        }
+        let parent = *transforms.last().unwrap_or(&Affine::IDENTITY);
+        let transform = match attributes.get("transform") {
+            Some(transform) => parent * parse_transform(transform)?,
+            None => parent,
+        };
+        let number = |key: &str| -> Result<f64, SvgCreationError> {
+            match attributes.get(key) {
+                None => Ok(0.0),
+                Some(value) => value.trim().trim_end_matches("px").parse::<f64>().or(Err(
+                    SvgCreationError::ParseError(format!("Bad {}: {}", key, value)),
+                )),
+            }
+        };
+        let points = |key: &str| -> Result<Vec<kurbo::Point>, SvgCreationError> {
+            let values = attributes
+                .get(key)
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .or(Err(SvgCreationError::ParseError(format!("Bad {}", key))))?;
+            Ok(values
+                .chunks_exact(2)
+                .map(|xy| kurbo::Point::new(xy[0], xy[1]))
+                .collect())
+        };
+        let (mut path, closed) = match name {
+            "g" => {
+                if tag_type == TagType::Start {
+                    transforms.push(transform);
+                }
+                continue;
+            }
+            "path" => {
+                let data = attributes
+                    .get("d")
+                    .map(|d| d.to_string())
+                    .unwrap_or_default();
+                let path = BezPath::from_svg(&data)
+                    .map_err(|err| SvgCreationError::ParseError(err.to_string()))?;
+                (path, false)
+            }
+            "line" => {
+                let mut path = BezPath::new();
+                path.move_to((number("x1")?, number("y1")?));
+                path.line_to((number("x2")?, number("y2")?));
+                (path, false)
+            }
+            "polyline" | "polygon" => {
+                let mut path = BezPath::new();
+                for (i, point) in points("points")?.into_iter().enumerate() {
+                    if i == 0 {
+                        path.move_to(point);
+                    } else {
+                        path.line_to(point);
+                    }
+                }
+                if name == "polygon" {
+                    path.close_path();
+                }
+                (path, name == "polygon")
+            }
+            "rect" => {
+                let (x, y) = (number("x")?, number("y")?);
+                let rect = kurbo::Rect::new(x, y, x + number("width")?, y + number("height")?);
+                (rect.to_path(accuracy), true)
+            }
+            "circle" => {
+                let circle = kurbo::Circle::new((number("cx")?, number("cy")?), number("r")?);
+                (circle.to_path(accuracy), true)
+            }
+            _ => continue,
+        };
+        path.apply_affine(transform);
+        let mut lines: Vec<LineString<f64>> = vec![];
+        path.flatten(accuracy, |el| match el {
+            PathEl::MoveTo(pos) => lines.push(LineString::new(vec![coord! {x: pos.x, y: pos.y}])),
+            PathEl::LineTo(pos) => {
+                if let Some(line) = lines.last_mut() {
+                    line.0.push(coord! {x: pos.x, y: pos.y});
+                }
+            }
+            PathEl::ClosePath => {
+                if let Some(line) = lines.last_mut() {
+                    line.close();
+                }
+            }
+            _ => {}
+        });
+        lines.retain(|line| line.0.len() > 1);
+        if closed && !lines.is_empty() {
+            let exterior = lines.remove(0);
+            geometries.push(Geometry::Polygon(Polygon::new(exterior, lines)));
+        } else if !lines.is_empty() {
+            geometries.push(Geometry::MultiLineString(MultiLineString::new(lines)));
+        }
+    }
+    Ok(GeometryCollection::new_from(geometries))
 }
 
 pub trait ToSvg<T>
@@ -336,6 +723,109 @@ mod test {
     use std::str::FromStr;
     use wkt::Wkt;
 
+    #[test]
+    fn test_page_presets() {
+        let a4 = Arrangement::<f64>::a4_portrait();
+        assert!(matches!(a4, Arrangement::FitCenterMargin(margin, _, false) if margin == 10.0));
+        let viewbox = a4.viewbox();
+        assert_eq!((viewbox.width(), viewbox.height()), (210.0, 297.0));
+        let viewbox = Arrangement::<f64>::a3_landscape().viewbox();
+        assert_eq!((viewbox.width(), viewbox.height()), (420.0, 297.0));
+        let viewbox = Arrangement::<f64>::letter_portrait().viewbox();
+        assert!((viewbox.width() - 215.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_background() {
+        let arrangement = Arrangement::<f64>::a4_landscape();
+        let document = arrangement
+            .create_svg_document_with_background("#223")
+            .unwrap()
+            .add(Path::new().set("d", "M0,0 L10,10"));
+        let svg = document.to_string();
+        let first = svg.split('>').nth(1).unwrap().trim();
+        assert!(first.starts_with("<rect"));
+        assert!(first.contains("fill=\"#223\""));
+        assert!(first.contains("width=\"297\""));
+        assert!(svg.find("<rect").unwrap() < svg.find("<path").unwrap());
+    }
+
+    #[test]
+    fn test_registration_marks() {
+        let arrangement = Arrangement::<f64>::a4_portrait();
+        let document = arrangement.create_svg_document().unwrap();
+        let svg = arrangement
+            .with_registration_marks(document, 5.0)
+            .to_string();
+        // Every prefixed attribute needs its namespace declared on the root, or
+        // strict XML readers (inkscape included) refuse the whole file.
+        let mut declared = vec![];
+        let mut used = vec![];
+        for event in svg::read(&svg).unwrap() {
+            if let Event::Tag(name, TagType::Start | TagType::Empty, attributes) = event {
+                for (prefix, rest) in attributes.keys().filter_map(|key| key.split_once(':')) {
+                    match prefix {
+                        "xmlns" if name == "svg" => declared.push(rest.to_string()),
+                        _ => used.push(prefix.to_string()),
+                    }
+                }
+            }
+        }
+        assert!(used.contains(&"inkscape".to_string()));
+        assert!(used.iter().all(|prefix| declared.contains(prefix)));
+
+        let marks = from_svg_str(&svg, 0.1).unwrap();
+        assert_eq!(marks.0.len(), 4);
+        // Bottom right corner, pointing back into the page
+        let corner = MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 205.0, y: 297.0},
+            coord! {x: 210.0, y: 297.0},
+            coord! {x: 210.0, y: 292.0},
+        ])]);
+        assert!(marks.0.contains(&Geometry::MultiLineString(corner)));
+    }
+
+    #[test]
+    fn test_from_svg_str() {
+        let mls = MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 0.0f64, y: 0.0f64},
+            coord! {x: 10.0f64, y: 0.0f64},
+            coord! {x: 10.0f64, y: 5.0f64},
+        ])]);
+        let path = Path::new().set("d", mls.to_path_data());
+        let document =
+            Document::new().add(Group::new().set("transform", "translate(100, 0)").add(path));
+        let geometry = from_svg_str(&document.to_string(), 0.1).unwrap();
+        assert_eq!(geometry.0.len(), 1);
+        match &geometry.0[0] {
+            Geometry::MultiLineString(lines) => assert_eq!(
+                lines.0[0].0,
+                vec![
+                    coord! {x: 100.0f64, y: 0.0f64},
+                    coord! {x: 110.0f64, y: 0.0f64},
+                    coord! {x: 110.0f64, y: 5.0f64},
+                ]
+            ),
+            other => panic!("Expected a MultiLineString, got {:?}", other),
+        }
+
+        let shapes = from_svg_str(
+            r#"<svg><rect x="0" y="0" width="10" height="10" transform="scale(2)"/>
+            <circle cx="5" cy="5" r="5"/><line x1="0" y1="0" x2="1" y2="1"/></svg>"#,
+            0.1,
+        )
+        .unwrap();
+        assert_eq!(shapes.0.len(), 3);
+        assert!(matches!(&shapes.0[0], Geometry::Polygon(rect)
+            if rect.bounding_rect().unwrap().max() == coord! {x: 20.0, y: 20.0}));
+        assert!(matches!(&shapes.0[1], Geometry::Polygon(_)));
+        assert!(from_svg_str(
+            r#"<svg><path transform="wibble(1)" d="M0,0 L1,1"/></svg>"#,
+            0.1
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_load_wkt() {
         let _geoms: Polygon::<f64> = Polygon::try_from(
@@ -413,6 +903,35 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_arrange_fit_center_rotate() {
+        let mls = MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 0.0f64, y: 0.0f64},
+            coord! {x: 100.0f64, y: 0.0f64},
+            coord! {x: 100.0f64, y: 10.0f64},
+        ])]);
+        let page = Rect::new(coord! {x:0f64, y:0f64}, coord! {x:50f64, y:200f64});
+        let brect = mls
+            .arrange(&Arrangement::FitCenterRotate(page, false))
+            .unwrap()
+            .bounding_rect()
+            .unwrap();
+        assert!(brect.width() < brect.height());
+        assert!((brect.height() - 200.0).abs() < 1e-9);
+        assert!((brect.center().x - 25.0).abs() < 1e-9);
+        assert!((brect.center().y - 100.0).abs() < 1e-9);
+
+        // Already fits best the way it is, so it's left alone.
+        let page = Rect::new(coord! {x:0f64, y:0f64}, coord! {x:200f64, y:50f64});
+        let brect = mls
+            .arrange(&Arrangement::FitCenterRotate(page, true))
+            .unwrap()
+            .bounding_rect()
+            .unwrap();
+        assert!(brect.width() > brect.height());
+        assert!((brect.width() - 200.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_arrange_fit_center_invert() {
         let mls = MultiLineString::new(vec![LineString::new(vec![
@@ -0,0 +1,327 @@
+use crate::geo_types::boolean::BooleanOp;
+use crate::geo_types::flatten::FlattenPolygons;
+use crate::geo_types::hatch::{Hatch, Hatches};
+use crate::geo_types::simplify::SimplifyRings;
+use ::image::GrayImage;
+use geo_types::{
+    coord, Geometry, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Rect,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+/// How many tries per point stippling gets before it gives up on a very pale image.
+const STIPPLE_ATTEMPTS: usize = 10000;
+
+/// Turns a grayscale image into hatching, so you can plot photos. The image gets
+/// stretched over `bounds`, and each pixel is sorted into one of `levels` tone bands
+/// (0 is the lightest). Every band's pixels get merged into one mask, which is then
+/// hatched with `pattern` at `angle`, with the darker bands hatched tighter. The
+/// spacings are in mm, so they don't change with the image resolution: the lightest
+/// inked band gets `max_spacing`, the darkest gets `min_spacing`, and the ones in
+/// between are spread evenly. Somewhere around your pen width is a good minimum.
+///
+/// You get one MultiLineString per band, lightest first. Band 0 is the paper showing
+/// through, so it's always empty. Feed them to `Context::geometry` (maybe with
+/// different pens per band) and you're off.
+pub fn image_to_hatched_layers(
+    img: &GrayImage,
+    bounds: Rect<f64>,
+    levels: usize,
+    pattern: Hatches,
+    angle: f64,
+    min_spacing: f64,
+    max_spacing: f64,
+) -> Vec<MultiLineString<f64>> {
+    let mut layers = vec![MultiLineString::new(vec![]); levels];
+    let (width, height) = img.dimensions();
+    if levels < 2 || width == 0 || height == 0 || min_spacing <= 0.0 || max_spacing <= 0.0 {
+        return layers;
+    }
+    let cell_width = bounds.width() / width as f64;
+    let cell_height = bounds.height() / height as f64;
+    let band = |luma: u8| {
+        let darkness = 1.0 - luma as f64 / 255.0;
+        ((darkness * levels as f64) as usize).min(levels - 1)
+    };
+
+    // Runs of same-band pixels along each row become one rect, so the union has a
+    // bit less work to do.
+    let mut masks = vec![MultiPolygon::new(vec![]); levels];
+    for y in 0..height {
+        let mut x = 0;
+        while x < width {
+            let run_band = band(img.get_pixel(x, y)[0]);
+            let start = x;
+            while x < width && band(img.get_pixel(x, y)[0]) == run_band {
+                x += 1;
+            }
+            let min = coord! {
+                x: bounds.min().x + start as f64 * cell_width,
+                y: bounds.min().y + y as f64 * cell_height,
+            };
+            let max = coord! {
+                x: bounds.min().x + x as f64 * cell_width,
+                y: min.y + cell_height,
+            };
+            masks[run_band].0.push(Rect::new(min, max).to_polygon());
+        }
+    }
+
+    let spacing = |level: usize| {
+        if levels > 2 {
+            max_spacing + (min_spacing - max_spacing) * (level - 1) as f64 / (levels - 2) as f64
+        } else {
+            min_spacing
+        }
+    };
+    for (level, mask) in masks.into_iter().enumerate().skip(1) {
+        if mask.0.is_empty() {
+            continue;
+        }
+        let mask = Geometry::MultiPolygon(mask.clone())
+            .unary_union()
+            .and_then(|merged| merged.flatten_polys())
+            .unwrap_or(mask);
+        if let Ok(lines) = mask.hatch(pattern.clone(), angle, spacing(level), 0.0) {
+            layers[level] = lines;
+        }
+    }
+    layers
+}
+
+/// Stipples a grayscale image: scatters `count` points over `bounds` (with the image
+/// stretched over it), with darker pixels getting proportionally more of them. It's
+/// plain rejection sampling, so a pure white image gets no points at all, and a very
+/// pale one might come up short. Drop the result into `Context::geometry` and every
+/// point becomes a little pen-sized circle.
+pub fn image_to_stipple(
+    img: &GrayImage,
+    bounds: Rect<f64>,
+    count: usize,
+    seed: u64,
+) -> MultiPoint<f64> {
+    let mut points = vec![];
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 || bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+        return MultiPoint::new(points);
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _attempt in 0..count.saturating_mul(STIPPLE_ATTEMPTS) {
+        if points.len() >= count {
+            break;
+        }
+        let (u, v): (f64, f64) = (rng.gen(), rng.gen());
+        let x = ((u * width as f64) as u32).min(width - 1);
+        let y = ((v * height as f64) as u32).min(height - 1);
+        let darkness = 1.0 - img.get_pixel(x, y)[0] as f64 / 255.0;
+        if rng.gen::<f64>() < darkness {
+            points.push(Point::new(
+                bounds.min().x + u * bounds.width(),
+                bounds.min().y + v * bounds.height(),
+            ));
+        }
+    }
+    MultiPoint::new(points)
+}
+
+/// Traces the outlines of the dark bits of an image, for line art. Anything darker
+/// than `threshold` counts as ink, and marching squares walks around the edges of the
+/// ink between pixel centers. Everything is closed (the image edge counts as paper),
+/// stretched over `bounds`, and simplified to within `accuracy` so the pixel
+/// staircases don't turn into thousands of tiny pen moves.
+pub fn image_to_contours(
+    img: &GrayImage,
+    bounds: Rect<f64>,
+    threshold: u8,
+    accuracy: f64,
+) -> MultiLineString<f64> {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return MultiLineString::new(vec![]);
+    }
+    let (width, height) = (width as i64, height as i64);
+    let ink = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && x < width
+            && y < height
+            && img.get_pixel(x as u32, y as u32)[0] < threshold
+    };
+
+    // Points are on a doubled grid, so pixel centers and the midpoints between them
+    // are all whole numbers and match up exactly from cell to cell.
+    let mut segments: Vec<((i64, i64), (i64, i64))> = vec![];
+    for y in -1..height {
+        for x in -1..width {
+            let (a, b, c, d) = (ink(x, y), ink(x + 1, y), ink(x + 1, y + 1), ink(x, y + 1));
+            let top = (2 * x + 2, 2 * y + 1);
+            let right = (2 * x + 3, 2 * y + 2);
+            let bottom = (2 * x + 2, 2 * y + 3);
+            let left = (2 * x + 1, 2 * y + 2);
+            let mut crossings = vec![];
+            if a != b {
+                crossings.push(top);
+            }
+            if b != c {
+                crossings.push(right);
+            }
+            if c != d {
+                crossings.push(bottom);
+            }
+            if d != a {
+                crossings.push(left);
+            }
+            match crossings.len() {
+                2 => segments.push((crossings[0], crossings[1])),
+                // Saddle. Keep the diagonal that's ink joined up.
+                4 if a => {
+                    segments.push((top, right));
+                    segments.push((bottom, left));
+                }
+                4 => {
+                    segments.push((top, left));
+                    segments.push((bottom, right));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut ends: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, (start, end)) in segments.iter().enumerate() {
+        ends.entry(*start).or_default().push(i);
+        ends.entry(*end).or_default().push(i);
+    }
+    let cell_width = bounds.width() / width as f64;
+    let cell_height = bounds.height() / height as f64;
+    let to_bounds = |(x, y): (i64, i64)| {
+        coord! {
+            x: bounds.min().x + x as f64 / 2.0 * cell_width,
+            y: bounds.min().y + y as f64 / 2.0 * cell_height,
+        }
+    };
+    let mut used = vec![false; segments.len()];
+    let mut rings = vec![];
+    for first in 0..segments.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let (start, mut at) = segments[first];
+        let mut ring = vec![to_bounds(start), to_bounds(at)];
+        while at != start {
+            let next = ends[&at].iter().copied().find(|&i| !used[i]);
+            let next = match next {
+                Some(next) => next,
+                None => break,
+            };
+            used[next] = true;
+            at = if segments[next].0 == at {
+                segments[next].1
+            } else {
+                segments[next].0
+            };
+            ring.push(to_bounds(at));
+        }
+        rings.push(LineString::new(ring));
+    }
+    MultiLineString::new(rings).simplify_preserving_rings(&accuracy)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::image::Luma;
+    use geo_types::Coordinate;
+
+    #[test]
+    fn test_stipple() {
+        let img = GrayImage::from_fn(20, 10, |x, _y| if x < 10 { Luma([0]) } else { Luma([255]) });
+        let bounds = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 200.0, y: 100.0});
+        let points = image_to_stipple(&img, bounds, 500, 1);
+        assert_eq!(points.0.len(), 500);
+        let left = points.0.iter().filter(|point| point.x() < 100.0).count();
+        assert!(left > 490);
+        assert_eq!(image_to_stipple(&img, bounds, 500, 1), points);
+
+        let blank = GrayImage::from_pixel(4, 4, Luma([255]));
+        assert!(image_to_stipple(&blank, bounds, 10, 1).0.is_empty());
+    }
+
+    #[test]
+    fn test_contours() {
+        let img = GrayImage::from_fn(40, 40, |x, y| {
+            let (dx, dy) = (x as f64 - 19.5, y as f64 - 19.5);
+            if (dx * dx + dy * dy).sqrt() < 12.0 {
+                Luma([0])
+            } else {
+                Luma([255])
+            }
+        });
+        let bounds = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 40.0, y: 40.0});
+        let contours = image_to_contours(&img, bounds, 128, 0.5);
+        assert_eq!(contours.0.len(), 1);
+        let ring = &contours.0[0];
+        assert!(ring.is_closed());
+        // Simplified, but still round-ish and about the right size.
+        assert!(ring.0.len() > 8 && ring.0.len() < 100);
+        for c in &ring.0 {
+            let radius = ((c.x - 20.0).powi(2) + (c.y - 20.0).powi(2)).sqrt();
+            assert!(radius > 10.0 && radius < 13.5);
+        }
+        assert!(image_to_contours(&GrayImage::new(0, 0), bounds, 128, 0.5)
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        let img = GrayImage::from_fn(2, 2, |x, y| {
+            if (x + y) % 2 == 0 {
+                Luma([0])
+            } else {
+                Luma([255])
+            }
+        });
+        let bounds = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 100.0});
+        let layers = image_to_hatched_layers(&img, bounds, 4, Hatches::line(), 45.0, 0.5, 2.0);
+        assert_eq!(layers.len(), 4);
+        assert!(layers[0].0.is_empty());
+        assert!(layers[1].0.is_empty() && layers[2].0.is_empty());
+        let dark = &layers[3];
+        assert!(!dark.0.is_empty());
+        // All the hatching is in the black squares, top left and bottom right.
+        let in_square = |c: Coordinate<f64>, min: f64| {
+            let inside = |v: f64| v >= min - 1e-6 && v <= min + 50.0 + 1e-6;
+            inside(c.x) && inside(c.y)
+        };
+        let middles: Vec<Coordinate<f64>> = dark
+            .0
+            .iter()
+            .flat_map(|line| line.lines())
+            .map(|segment| (segment.start + segment.end) / 2.0)
+            .collect();
+        assert!(middles
+            .iter()
+            .all(|&c| in_square(c, 0.0) || in_square(c, 50.0)));
+        assert!(middles.iter().any(|&c| in_square(c, 0.0)));
+        assert!(middles.iter().any(|&c| in_square(c, 50.0)));
+    }
+
+    #[test]
+    fn test_tone_bands() {
+        // White, then light, mid and dark grey columns, one per band.
+        let img = GrayImage::from_fn(4, 1, |x, _y| Luma([[255, 150, 90, 0][x as usize]]));
+        let bounds = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 80.0, y: 20.0});
+        let layers = image_to_hatched_layers(&img, bounds, 4, Hatches::line(), 0.0, 1.0, 3.0);
+        assert!(layers[0].0.is_empty());
+        for layer in &layers[1..] {
+            assert!(!layer.0.is_empty());
+        }
+        // Spacing is in mm: 3, 2 and 1 across a 20mm column, whatever the pixel size.
+        let counts: Vec<usize> = layers.iter().map(|layer| layer.0.len()).collect();
+        assert!(counts[1] < counts[2] && counts[2] < counts[3]);
+        assert!(counts[3] >= 15);
+    }
+}
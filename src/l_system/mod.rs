@@ -3,7 +3,12 @@
 //! more details, and examples.
 
 use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
 use embed_doc_image::embed_doc_image;
+use crate::errors::LSystemError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 /// # LSystem
 ///
@@ -50,19 +55,95 @@ pub struct LSystem{
     pub rules: HashMap<char, String>,
 }
 
+/// # ContextLSystem
+///
+/// An [`LSystem`] with context sensitive rules on top, which are checked (in order)
+/// before the plain rules. The first one that matches wins. Build one with
+/// [`LSystem::with_context_rules`].
+///
+/// # Example
+///
+/// ```rust
+/// use aoer_plotty_rs::l_system::{ContextRule, LSystem};
+/// use std::collections::HashMap;
+///
+/// // A signal that walks along a row of Bs, one step per generation.
+/// let signal = LSystem {
+///     axiom: "ABBB".to_string(),
+///     rules: HashMap::from([('A', "B".to_string())]),
+/// }.with_context_rules(vec![ContextRule {
+///     left: Some('A'),
+///     symbol: 'B',
+///     right: None,
+///     production: "A".to_string(),
+/// }]);
+/// assert_eq!(signal.expand(2), "BBAB");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ContextLSystem {
+    pub system: LSystem,
+    pub context_rules: Vec<ContextRule>,
+}
+
+/// # ContextRule
+///
+/// A context sensitive production, written `left < symbol > right -> production` in
+/// the textbooks. It only fires when `symbol` has `left` right before it and `right`
+/// right after it in the current string; leave either one as None to not care.
+/// Neighbours are literally the next character over, so brackets count too.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContextRule {
+    pub left: Option<char>,
+    pub symbol: char,
+    pub right: Option<char>,
+    pub production: String,
+}
+
+impl ContextRule {
+    fn matches(&self, left: Option<char>, symbol: char, right: Option<char>) -> bool {
+        self.symbol == symbol
+            && self.left.map_or(true, |l| left == Some(l))
+            && self.right.map_or(true, |r| right == Some(r))
+    }
+}
+
 impl LSystem{
 
-    fn recur(&self, state: String, order: u32)->String{
-        let new_state = state.chars().map(|c|{
-            match self.rules.get(&c){
-                Some(replacement) => replacement.clone(),
-                None => String::from(c)
+    /// #with_context_rules
+    ///
+    /// Adds context sensitive rules, giving you a [`ContextLSystem`] which expands
+    /// the same way, but checks those first.
+    pub fn with_context_rules(self, context_rules: Vec<ContextRule>) -> ContextLSystem {
+        ContextLSystem { system: self, context_rules }
+    }
+
+    /// One generation. Context rules first, then the plain ones, otherwise the
+    /// symbol stays put.
+    fn step(&self, state: &str, context_rules: &[ContextRule]) -> String {
+        let chars: Vec<char> = state.chars().collect();
+        let mut new_state = String::with_capacity(state.len() * 2);
+        for (i, c) in chars.iter().enumerate() {
+            let left = if i > 0 { Some(chars[i - 1]) } else { None };
+            let right = chars.get(i + 1).cloned();
+            let replacement = context_rules
+                .iter()
+                .find(|rule| rule.matches(left, *c, right))
+                .map(|rule| &rule.production)
+                .or_else(|| self.rules.get(c));
+            match replacement {
+                Some(replacement) => new_state.push_str(replacement),
+                None => new_state.push(*c),
             }
-        }).collect();
+        }
+        new_state
+    }
+
+    fn recur(&self, state: String, order: u32, context_rules: &[ContextRule])->String{
+        let new_state = self.step(&state, context_rules);
         if order == 0{
             state
         }else{
-            self.recur(new_state, order-1)
+            self.recur(new_state, order-1, context_rules)
         }
     }
 
@@ -72,16 +153,276 @@ impl LSystem{
     /// representing the state of the L-system. Useful with
     /// [`crate::turtle::TurtleTrait::walk_lpath`]
     pub fn expand(&self, order: u32) -> String{
-        self.recur(self.axiom.clone(), order)
+        self.recur(self.axiom.clone(), order, &[])
+    }
+
+    /// #expand_bounded
+    ///
+    /// Same as expand, but gives up with [`LSystemError::TooLong`] as soon as the
+    /// string gets longer than `max_len` symbols, instead of eating all your RAM. The
+    /// error says which order it managed to finish. Nice for interactive stuff.
+    pub fn expand_bounded(&self, order: u32, max_len: usize) -> Result<String, LSystemError> {
+        self.expand_bounded_with(order, max_len, &[])
+    }
+
+    fn expand_bounded_with(&self, order: u32, max_len: usize, context_rules: &[ContextRule])
+        -> Result<String, LSystemError> {
+        let mut state = self.axiom.clone();
+        for reached in 0..=order {
+            let length = state.chars().count();
+            if length > max_len {
+                return Err(LSystemError::TooLong { order: reached.saturating_sub(1), length });
+            }
+            if reached < order {
+                state = self.step(&state, context_rules);
+            }
+        }
+        Ok(state)
     }
 
 }
 
+impl ContextLSystem {
+    /// #expand
+    ///
+    /// Same as [`LSystem::expand`], with the context rules.
+    pub fn expand(&self, order: u32) -> String {
+        self.system.recur(self.system.axiom.clone(), order, &self.context_rules)
+    }
+
+    /// #expand_bounded
+    ///
+    /// Same as [`LSystem::expand_bounded`], with the context rules.
+    pub fn expand_bounded(&self, order: u32, max_len: usize) -> Result<String, LSystemError> {
+        self.system.expand_bounded_with(order, max_len, &self.context_rules)
+    }
+}
+
+/// # StochasticLSystem
+///
+/// Like [`crate::l_system::LSystem`], but each symbol can have a few alternative
+/// productions, each with a weight. Every time a symbol gets replaced, one of them is
+/// picked at random (weighted), which makes for much more natural looking plants.
+/// The randomness comes from a seed, so the same seed always grows the same plant.
+///
+/// # Example
+///
+/// ```rust
+/// use aoer_plotty_rs::l_system::StochasticLSystem;
+/// use std::collections::HashMap;
+///
+/// let weed = StochasticLSystem {
+///     axiom: "F".to_string(),
+///     rules: HashMap::from([('F', vec![
+///         (1.0, "F[+F]F[-F]F".to_string()),
+///         (1.0, "F[+F]F".to_string()),
+///         (1.0, "F[-F]F".to_string()),
+///     ])]),
+/// };
+/// let path = weed.expand_stochastic(4, 1234);
+/// ```
+#[derive(Clone, Debug)]
+pub struct StochasticLSystem {
+    pub axiom: String,
+    pub rules: HashMap<char, Vec<(f64, String)>>,
+}
+
+impl StochasticLSystem {
+    /// #expand_stochastic
+    ///
+    /// Expands the L-system by "order" iterations, picking productions with an RNG
+    /// seeded from `seed`. Symbols with no rules (or only zero weights) are left as is.
+    pub fn expand_stochastic(&self, order: u32, seed: u64) -> String {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = self.axiom.clone();
+        for _ in 0..order {
+            let mut new_state = String::with_capacity(state.len() * 2);
+            for c in state.chars() {
+                match self.pick(c, &mut rng) {
+                    Some(replacement) => new_state.push_str(replacement),
+                    None => new_state.push(c),
+                }
+            }
+            state = new_state;
+        }
+        state
+    }
+
+    fn pick(&self, c: char, rng: &mut StdRng) -> Option<&String> {
+        let productions = self.rules.get(&c)?;
+        let total: f64 = productions.iter().map(|(weight, _)| weight.max(0.0)).sum();
+        if !total.is_finite() || total <= 0.0 {
+            return None;
+        }
+        let mut choice = rng.gen_range(0.0..total);
+        for (weight, production) in productions {
+            if choice < weight.max(0.0) {
+                return Some(production);
+            }
+            choice -= weight.max(0.0);
+        }
+        // Rounding can leave us just past the end
+        productions.iter().rev().find(|(weight, _)| *weight > 0.0).map(|(_, p)| p)
+    }
+}
+/// A parametric L-system symbol: the character, and its parameters. `('F', vec![10.0])`
+/// is what the textbooks write as F(10).
+pub type ParametricSymbol = (char, Vec<f64>);
+
+/// A production for a [`crate::l_system::ParametricLSystem`]. Gets the parameters of
+/// the symbol it's replacing, and returns what to replace it with.
+pub type ParametricRule = Arc<dyn Fn(&[f64]) -> Vec<ParametricSymbol> + Send + Sync>;
+
+/// # ParametricLSystem
+///
+/// An L-system where every symbol carries some numbers, and the rules are closures
+/// that compute the replacement from them. That's how you get branches that get
+/// shorter (or thinner) the further out they go. Walk the result with
+/// [`crate::turtle::TurtleTrait::walk_parametric`].
+///
+/// # Example
+///
+/// ```rust
+/// use aoer_plotty_rs::l_system::ParametricLSystem;
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+///
+/// // Every generation, F(d) grows a branch off to the side that's 60% as long.
+/// let branch = ParametricLSystem {
+///     axiom: vec![('F', vec![100.0])],
+///     rules: HashMap::from([(
+///         'F',
+///         Arc::new(|p: &[f64]| {
+///             vec![
+///                 ('F', vec![p[0] * 0.6]),
+///                 ('[', vec![]),
+///                 ('+', vec![0.5]),
+///                 ('F', vec![p[0] * 0.6]),
+///                 (']', vec![]),
+///             ]
+///         }) as _,
+///     )]),
+/// };
+/// assert_eq!(branch.expand(2).len(), 13);
+/// ```
+#[derive(Clone)]
+pub struct ParametricLSystem {
+    pub axiom: Vec<ParametricSymbol>,
+    pub rules: HashMap<char, ParametricRule>,
+}
+
+impl Debug for ParametricLSystem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParametricLSystem")
+            .field("axiom", &self.axiom)
+            .field("rules", &self.rules.keys().collect::<Vec<&char>>())
+            .finish()
+    }
+}
+
+impl ParametricLSystem {
+    /// #expand
+    ///
+    /// Expands the L-system by the requested "order" of iterations. Symbols without a
+    /// rule are copied over as they are.
+    pub fn expand(&self, order: u32) -> Vec<ParametricSymbol> {
+        let mut state = self.axiom.clone();
+        for _ in 0..order {
+            state = state
+                .into_iter()
+                .flat_map(|(c, params)| match self.rules.get(&c) {
+                    Some(rule) => rule(&params),
+                    None => vec![(c, params)],
+                })
+                .collect();
+        }
+        state
+    }
+}
 
 #[cfg(test)]
 mod test{
     use super::*;
 
+    #[test]
+    fn test_expand_bounded() {
+        let doubler = LSystem {
+            axiom: "A".to_string(),
+            rules: HashMap::from([('A', "AA".to_string())]),
+        };
+        assert_eq!(doubler.expand_bounded(3, 8), Ok("AAAAAAAA".to_string()));
+        assert_eq!(doubler.expand_bounded(3, 8), Ok(doubler.expand(3)));
+        assert_eq!(
+            doubler.expand_bounded(20, 1000),
+            Err(LSystemError::TooLong { order: 9, length: 1024 })
+        );
+    }
+
+    #[test]
+    fn test_expand_context() {
+        // B after an A turns into C, any other B turns into D
+        let plain = LSystem {
+            axiom: "ABBAB".to_string(),
+            rules: HashMap::from([('B', "D".to_string())]),
+        };
+        let system = plain.clone().with_context_rules(vec![ContextRule {
+            left: Some('A'),
+            symbol: 'B',
+            right: None,
+            production: "C".to_string(),
+        }]);
+        assert_eq!(system.expand(1), "ACDAC");
+        assert_eq!(plain.expand(1), "ADDAD");
+        assert_eq!(system.expand_bounded(1, 10), Ok("ACDAC".to_string()));
+        let right = LSystem {
+            axiom: "BAB".to_string(),
+            rules: HashMap::new(),
+        }
+        .with_context_rules(vec![ContextRule {
+            left: None,
+            symbol: 'B',
+            right: Some('A'),
+            production: "X".to_string(),
+        }]);
+        assert_eq!(right.expand(1), "XAB");
+    }
+
+    #[test]
+    fn test_expand_parametric() {
+        let system = ParametricLSystem {
+            axiom: vec![('F', vec![8.0])],
+            rules: HashMap::from([(
+                'F',
+                Arc::new(|p: &[f64]| {
+                    vec![('F', vec![p[0] / 2.0]), ('+', vec![1.0]), ('F', vec![p[0] / 2.0])]
+                }) as ParametricRule,
+            )]),
+        };
+        let expanded = system.expand(2);
+        assert_eq!(expanded.len(), 7);
+        assert_eq!(expanded[0], ('F', vec![2.0]));
+        assert_eq!(expanded[1], ('+', vec![1.0]));
+        assert_eq!(system.expand(0), system.axiom);
+    }
+
+    #[test]
+    fn test_expand_stochastic() {
+        let system = StochasticLSystem {
+            axiom: "F".to_string(),
+            rules: HashMap::from([('F', vec![
+                (1.0, "F[+F]F".to_string()),
+                (1.0, "F[-F]F".to_string()),
+                (1.0, "FF".to_string()),
+                (0.0, "never".to_string()),
+            ])]),
+        };
+        let first = system.expand_stochastic(4, 42);
+        assert_eq!(first, system.expand_stochastic(4, 42));
+        assert_ne!(first, system.expand_stochastic(4, 43));
+        assert!(!first.contains("never"));
+        assert_eq!(system.expand_stochastic(0, 42), "F");
+    }
+
     #[test]
     fn test_expand_simple(){
         let system = LSystem {
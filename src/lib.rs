@@ -111,6 +111,12 @@ pub mod errors;
 /// Elements : Reusable art components, usually designed for use with Context
 pub mod elements;
 
+/// Point fields : Ways of scattering points over an area, for stippling and friends
+pub mod point_field;
+
+/// Images : Turning photos and other raster art into plottable lines
+pub mod image;
+
 /// Workbench : Tooling to turn your 'sketches' into interactive UI based tools
 /// that can be used to tweak a design and generate SVG output (and eventually
 /// even to directly plot the result).
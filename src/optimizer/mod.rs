@@ -2,28 +2,111 @@ use std::collections::HashMap;
 
 use geo::prelude::EuclideanDistance;
 use geo_types::{Coordinate, LineString, MultiLineString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rstar::{PointDistance, RTree, RTreeObject, AABB};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum OptimizationStrategy {
     Greedy,
+    /// Starts from the greedy result, then randomly flips runs of lines around
+    /// for `iterations` rounds, occasionally accepting a worse order early on
+    /// to escape local minima. Slow, but good for "render overnight, plot
+    /// perfect" jobs. The same seed always gives the same result.
+    SimulatedAnnealing {
+        iterations: usize,
+        seed: u64,
+    },
+    /// Starts from the greedy result, then keeps reversing runs of lines
+    /// whenever that shortens the travel, until nothing helps anymore or we've
+    /// done `max_iterations` full passes. Untangles most of the crossing jumps.
+    TwoOpt {
+        max_iterations: usize,
+    },
+}
+
+/// Total pen-up distance: the gap between the end of each line and the start
+/// of the next one.
+fn travel(lines: &[LineString<f64>]) -> f64 {
+    lines
+        .windows(2)
+        .filter_map(|pair| match (pair[0].0.last(), pair[1].0.first()) {
+            (Some(end), Some(start)) => Some(end.euclidean_distance(start)),
+            _ => None,
+        })
+        .sum()
+}
+
+/// How much the travel changes if the run of lines i..=j gets reversed (order
+/// and direction). Only the hops at either end of the run change.
+fn reversal_delta(lines: &[LineString<f64>], i: usize, j: usize) -> f64 {
+    let hop = |from: Option<&Coordinate<f64>>, to: Option<&Coordinate<f64>>| -> f64 {
+        match (from, to) {
+            (Some(end), Some(start)) => end.euclidean_distance(start),
+            _ => 0.,
+        }
+    };
+    let before = if i > 0 { lines[i - 1].0.last() } else { None };
+    let after = lines.get(j + 1).and_then(|line| line.0.first());
+    let old = hop(before, lines[i].0.first()) + hop(lines[j].0.last(), after);
+    // Once reversed, the run starts at line j's end, and finishes at line i's start.
+    let new = hop(before, lines[j].0.last()) + hop(lines[i].0.first(), after);
+    new - old
+}
+
+/// Reverses the run of lines i..=j, both their order and their direction.
+fn reverse_run(lines: &mut [LineString<f64>], i: usize, j: usize) {
+    lines[i..=j].reverse();
+    lines[i..=j].iter_mut().for_each(|line| line.0.reverse());
+}
+
+/// What an optimization pass actually bought you, in pen-up travel (mm, or
+/// whatever your units are).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizationReport {
+    pub before: f64,
+    pub after: f64,
+    pub line_count: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 /// Optimization strategy utility class.
 pub struct Optimizer {
     max_keepdown: f64,
+    merge_tolerance: f64,
     strategy: OptimizationStrategy,
+    start: Option<Coordinate<f64>>,
 }
 
 impl Optimizer {
+    /// The merge tolerance starts out the same as max_keepdown; see
+    /// [`Optimizer::merge_tolerance`] if you want them to differ.
     pub fn new(max_keepdown: f64, strategy: OptimizationStrategy) -> Optimizer {
         Optimizer {
             max_keepdown,
+            merge_tolerance: max_keepdown,
             strategy,
+            start: None,
         }
     }
 
+    /// Where the pen starts (usually home). With this set, the first line drawn
+    /// is the one with an endpoint closest to here, flipped around if needed.
+    /// Otherwise we just start with whatever line happens to be first.
+    pub fn with_start(&mut self, start: Coordinate<f64>) -> &mut Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// How far apart two endpoints can be and still get joined into a single
+    /// stroke by merge/connect. This is separate from max_keepdown (the pen lift
+    /// radius used when ordering lines), so you can merge fills aggressively
+    /// while keeping travel tight.
+    pub fn merge_tolerance(&mut self, tolerance: f64) -> &mut Self {
+        self.merge_tolerance = tolerance;
+        self
+    }
+
     pub fn build_rtree_from_hashmap(
         &self,
         hashmap: &HashMap<usize, LineString<f64>>,
@@ -49,11 +132,14 @@ impl Optimizer {
                 true,
             ));
         }
+        // HashMap order changes from run to run, and so would the way ties in the
+        // tree get broken. Sort so the same lines always optimize the same way.
+        linerefs.sort_by_key(|lineref| lineref.line_id);
 
         RTree::bulk_load(linerefs)
     }
 
-    /// Merges lines who have endpoints at most pen_width*pi.sqrt() apart
+    /// Merges lines who have endpoints at most merge_tolerance apart
     pub fn merge(&self, mls: &MultiLineString<f64>) -> MultiLineString<f64> {
         let mut lines_out = MultiLineString::new(vec![]); // Just one empty line in it.
         let mut current_line: LineString<f64> = LineString::new(vec![]);
@@ -69,7 +155,7 @@ impl Optimizer {
                 .last()
                 .unwrap()
                 .euclidean_distance(source_start)
-                <= self.max_keepdown
+                <= self.merge_tolerance
             {
                 let mut tmpline = source_line.0.clone();
                 current_line.0.append(&mut tmpline);
@@ -84,10 +170,198 @@ impl Optimizer {
         lines_out
     }
 
+    /// Chains lines whose endpoints are at most merge_tolerance apart into longer
+    /// lines, flipping them around as needed. Unlike merge, the input order and
+    /// direction don't matter, which is handy for messy imported drawings. It's
+    /// O(n^2), so don't throw a million hatch lines at it.
+    pub fn connect(&self, mls: &MultiLineString<f64>) -> MultiLineString<f64> {
+        let mut remaining: Vec<LineString<f64>> = mls
+            .0
+            .iter()
+            .filter(|line| line.0.len() > 1)
+            .cloned()
+            .collect();
+        let mut lines_out = MultiLineString::new(vec![]);
+        while !remaining.is_empty() {
+            let mut chain = vec![remaining.remove(0)];
+            let mut flipped = false;
+            loop {
+                let tail = *chain.last().unwrap().0.last().unwrap();
+                let nearest = remaining
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, line)| {
+                        [
+                            (i, false, line.0.first().unwrap().euclidean_distance(&tail)),
+                            (i, true, line.0.last().unwrap().euclidean_distance(&tail)),
+                        ]
+                    })
+                    .filter(|(_i, _rev, distance)| *distance <= self.merge_tolerance)
+                    .min_by(|a, b| a.2.total_cmp(&b.2));
+                match nearest {
+                    Some((i, reverse, _distance)) => {
+                        let mut line = remaining.remove(i);
+                        if reverse {
+                            line.0.reverse();
+                        }
+                        chain.push(line);
+                    }
+                    None if !flipped => {
+                        // Nothing more off the tail, so try growing off the head.
+                        chain.reverse();
+                        chain.iter_mut().for_each(|line| line.0.reverse());
+                        flipped = true;
+                    }
+                    None => break,
+                }
+            }
+            for mut line in self.merge(&MultiLineString::new(chain)) {
+                line.0.dedup();
+                lines_out.0.push(line);
+            }
+        }
+        lines_out
+    }
+
+    /// Total pen-up travel for the lines in the order given: the sum of the gaps
+    /// between the end of each line and the start of the next.
+    pub fn travel_distance(&self, mls: &MultiLineString<f64>) -> f64 {
+        travel(&mls.0)
+    }
+
+    /// Same as [`Optimizer::optimize`], but also tells you how much travel it saved.
+    pub fn optimize_with_report(
+        &self,
+        mls: &MultiLineString<f64>,
+    ) -> (MultiLineString<f64>, OptimizationReport) {
+        let optimized = self.optimize(mls);
+        let report = OptimizationReport {
+            before: self.travel_distance(mls),
+            after: self.travel_distance(&optimized),
+            line_count: optimized.0.len(),
+        };
+        (optimized, report)
+    }
+
     /// Optimizes lines by finding the nearest neighbor to each endpoint
-    /// using an rtree as a spatial index. Fast, but just greedy for now.
+    /// using an rtree as a spatial index, and then refining that according
+    /// to the strategy. Closed rings get spun around afterwards so they start
+    /// wherever the pen already is.
     pub fn optimize(&self, mls: &MultiLineString<f64>) -> MultiLineString<f64> {
-        assert_eq!(self.strategy, OptimizationStrategy::Greedy);
+        let greedy = self.greedy(mls);
+        let ordered = match self.strategy {
+            OptimizationStrategy::Greedy => greedy,
+            OptimizationStrategy::SimulatedAnnealing { iterations, seed } => {
+                Self::anneal(greedy, iterations, seed)
+            }
+            OptimizationStrategy::TwoOpt { max_iterations } => {
+                Self::two_opt(greedy, max_iterations)
+            }
+        };
+        self.rotate_rings(ordered)
+    }
+
+    /// A closed ring (first and last points exactly equal) can be drawn starting
+    /// from any vertex, so pick the one that makes the hop in from the previous
+    /// line plus the hop out to the next one the shortest. Rings that only nearly
+    /// close are left alone, since rotating them would draw in the gap.
+    fn rotate_rings(&self, mut mls: MultiLineString<f64>) -> MultiLineString<f64> {
+        let mut pen = self.start;
+        for i in 0..mls.0.len() {
+            let next = mls.0.get(i + 1).and_then(|line| line.0.first()).copied();
+            let line = &mut mls.0[i];
+            let is_ring = line.0.len() > 3 && line.is_closed();
+            if let (Some(incoming), true) = (pen, is_ring) {
+                let cost = |start: &Coordinate<f64>, end: &Coordinate<f64>| {
+                    incoming.euclidean_distance(start)
+                        + next.map_or(0., |next| end.euclidean_distance(&next))
+                };
+                let current = cost(&line.0[0], line.0.last().unwrap());
+                let vertices = &line.0[..line.0.len() - 1];
+                let (best, best_cost) = vertices
+                    .iter()
+                    .enumerate()
+                    .map(|(k, vertex)| (k, cost(vertex, vertex)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .unwrap();
+                if best_cost < current - 1.0e-9 {
+                    let mut rotated = vertices.to_vec();
+                    rotated.rotate_left(best);
+                    rotated.push(rotated[0]);
+                    line.0 = rotated;
+                }
+            }
+            pen = line.0.last().copied();
+        }
+        mls
+    }
+
+    /// Anneals the visiting order. Each move reverses a run of lines (order
+    /// and direction), which only changes the two travel hops at either end of
+    /// the run, so each step is cheap to score. Keeps the best order seen, so
+    /// it's never worse than what went in.
+    fn anneal(mls: MultiLineString<f64>, iterations: usize, seed: u64) -> MultiLineString<f64> {
+        let mut lines: Vec<LineString<f64>> = mls.0;
+        let count = lines.len();
+        if count < 2 || iterations == 0 {
+            return MultiLineString::new(lines);
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut current = travel(&lines);
+        let mut best = current;
+        let mut best_lines = lines.clone();
+        // Start hot enough to accept a typical hop's worth of badness, and cool
+        // down to basically greedy by the end.
+        let start_temp = (current / (count - 1) as f64).max(f64::EPSILON);
+        let cooling = (1.0e-3f64).powf(1.0 / iterations as f64);
+        let mut temp = start_temp;
+        for _ in 0..iterations {
+            let i = rng.gen_range(0..count);
+            let j = rng.gen_range(0..count);
+            let (i, j) = (i.min(j), i.max(j));
+            if i == j {
+                temp *= cooling;
+                continue;
+            }
+            let delta = reversal_delta(&lines, i, j);
+            if delta < 0. || rng.gen::<f64>() < (-delta / temp).exp() {
+                reverse_run(&mut lines, i, j);
+                current += delta;
+                if current < best - 1.0e-9 {
+                    best = current;
+                    best_lines = lines.clone();
+                }
+            }
+            temp *= cooling;
+        }
+        MultiLineString::new(best_lines)
+    }
+
+    /// Classic 2-opt, but over lines instead of points. Each pass tries reversing
+    /// every run of lines and keeps any that shorten the travel. Stops when a pass
+    /// finds nothing, or after max_iterations passes. O(n^2) per pass.
+    fn two_opt(mls: MultiLineString<f64>, max_iterations: usize) -> MultiLineString<f64> {
+        let mut lines: Vec<LineString<f64>> = mls.0;
+        let count = lines.len();
+        for _ in 0..max_iterations {
+            let mut improved = false;
+            for i in 0..count {
+                for j in (i + 1)..count {
+                    if reversal_delta(&lines, i, j) < -1.0e-9 {
+                        reverse_run(&mut lines, i, j);
+                        improved = true;
+                    }
+                }
+            }
+            if !improved {
+                break;
+            }
+        }
+        MultiLineString::new(lines)
+    }
+
+    /// Nearest neighbor ordering, the starting point for everything else.
+    fn greedy(&self, mls: &MultiLineString<f64>) -> MultiLineString<f64> {
         let mut lines_out = MultiLineString::new(vec![]);
         if mls.0.len() == 0 {
             return lines_out;
@@ -101,7 +375,28 @@ impl Optimizer {
             }));
         let rtree = self.build_rtree_from_hashmap(&lines_hash);
 
-        while lines_hash.len() > 0 {
+        if let Some(start) = self.start {
+            let nearest = lines_hash
+                .iter()
+                .filter(|(_k, line)| line.0.len() > 1)
+                .map(|(k, line)| {
+                    let to_first = line.0.first().unwrap().euclidean_distance(&start);
+                    let to_last = line.0.last().unwrap().euclidean_distance(&start);
+                    (*k, to_first.min(to_last), to_last < to_first)
+                })
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+            match nearest {
+                Some((k, _distance, reverse)) => {
+                    let mut line = lines_hash.remove(&k).unwrap();
+                    if reverse {
+                        line.0.reverse();
+                    }
+                    lines_out.0.push(line);
+                }
+                None => return MultiLineString::new(vec![]),
+            }
+        }
+        while lines_out.0.is_empty() && lines_hash.len() > 0 {
             if let Some(tmpline) = lines_hash.remove(&0) {
                 if tmpline.0.len() > 1 {
                     lines_out.0.push(tmpline.clone());
@@ -369,4 +664,140 @@ mod tests {
         }
         println!("OPT TRAVEL: {}", distance_opt);
     }
+
+    #[test]
+    fn test_merge_tolerance() {
+        let lines: MultiLineString<f64> = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0}]),
+            LineString::new(vec![coord! {x: 11.0, y: 0.0}, coord! {x: 20.0, y: 0.0}]),
+        ]);
+        let tight = Optimizer::new(0.1, OptimizationStrategy::Greedy);
+        assert_eq!(tight.merge(&lines).0.len(), 2);
+        let mut loose = Optimizer::new(0.1, OptimizationStrategy::Greedy);
+        loose.merge_tolerance(2.0);
+        assert_eq!(loose.merge(&lines).0.len(), 1);
+        // Lift radius is unchanged, so the travel ordering is exactly the same
+        let scrambled: MultiLineString<f64> = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y:20.0}, coord! {x:0.0, y:0.0}]),
+            LineString::new(vec![coord! {x: 20.0, y:20.5}, coord! {x:40.0, y:20.0}]),
+            LineString::new(vec![coord! {x: 0.0, y:0.0}, coord! {x:20.0, y:20.0}]),
+            LineString::new(vec![coord! {x:40.0, y:20.0}, coord! {x:40.5,y:40.5}]),
+        ]);
+        assert_eq!(loose.optimize(&scrambled), tight.optimize(&scrambled));
+    }
+
+    #[test]
+    fn test_optimize_with_report() {
+        let lines: MultiLineString<f64> = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0}]),
+            LineString::new(vec![coord! {x: 50.0, y: 0.0}, coord! {x: 60.0, y: 0.0}]),
+            LineString::new(vec![coord! {x: 10.0, y: 0.0}, coord! {x: 20.0, y: 0.0}]),
+        ]);
+        let opt = Optimizer::new(0.7, OptimizationStrategy::Greedy);
+        assert!((opt.travel_distance(&lines) - 90.0).abs() < 1.0e-9);
+        let (out, report) = opt.optimize_with_report(&lines);
+        assert_eq!(report.line_count, 3);
+        assert!((report.before - 90.0).abs() < 1.0e-9);
+        assert!((report.after - 30.0).abs() < 1.0e-9);
+        assert!((report.after - opt.travel_distance(&out)).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn test_with_start() {
+        let lines: MultiLineString<f64> = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 0.0}]),
+            LineString::new(vec![coord! {x: 50.0, y: 50.0}, coord! {x: 100.0, y: 100.0}]),
+        ]);
+        let mut opt = Optimizer::new(0.7, OptimizationStrategy::Greedy);
+        assert_eq!(opt.optimize(&lines).0[0], lines.0[0]);
+        opt.with_start(coord! {x: 110.0, y: 110.0});
+        let out = opt.optimize(&lines);
+        assert_eq!(
+            out.0[0].0,
+            vec![coord! {x: 100.0, y: 100.0}, coord! {x: 50.0, y: 50.0}]
+        );
+        assert_eq!(out.0[1], lines.0[0]);
+    }
+
+    #[test]
+    fn test_ring_rotation() {
+        let lines: MultiLineString<f64> = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 30.0, y: 30.0}, coord! {x: 11.0, y: 11.0}]),
+            LineString::new(vec![
+                coord! {x: 0.0, y: 0.0},
+                coord! {x: 10.0, y: 0.0},
+                coord! {x: 10.0, y: 10.0},
+                coord! {x: 0.0, y: 10.0},
+                coord! {x: 0.0, y: 0.0},
+            ]),
+        ]);
+        let out = Optimizer::new(0.7, OptimizationStrategy::Greedy).optimize(&lines);
+        let ring = &out.0[1];
+        assert_eq!(ring.0.len(), 5);
+        assert_eq!(ring.0[0], coord! {x: 10.0, y: 10.0});
+        assert_eq!(ring.0[4], coord! {x: 10.0, y: 10.0});
+        assert_eq!(ring.0[1], coord! {x: 0.0, y: 10.0});
+
+        // Nearly closed isn't closed, so it gets drawn as-is rather than having
+        // the gap filled in.
+        let open = LineString::new(vec![
+            coord! {x: 0.0, y: 0.5},
+            coord! {x: 0.0, y: 10.0},
+            coord! {x: 10.0, y: 10.0},
+            coord! {x: 10.0, y: 0.0},
+            coord! {x: 0.0, y: 0.0},
+        ]);
+        let lines = MultiLineString::new(vec![lines.0[0].clone(), open.clone()]);
+        let out = Optimizer::new(0.7, OptimizationStrategy::Greedy).optimize(&lines);
+        let mut reversed = open.0.clone();
+        reversed.reverse();
+        assert!(out.0[1].0 == open.0 || out.0[1].0 == reversed);
+    }
+
+    #[test]
+    fn test_two_opt() {
+        let lines: MultiLineString<f64> = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y:20.0}, coord! {x:0.0, y:0.0}]),
+            LineString::new(vec![coord! {x: 0.0, y:0.0}, coord! {x:20.0, y:20.0}]),
+            LineString::new(vec![coord! {x: 20.0, y:20.5}, coord! {x:40.0, y:20.0}]),
+            LineString::new(vec![coord! {x: 20.0, y:0.5}, coord! {x:20.0, y:20.0}]),
+            LineString::new(vec![coord! {x:40.0, y:20.0}, coord! {x:40.5,y:40.5}]),
+            LineString::new(vec![coord! {x:0.0, y:0.0}, coord! {x:40.5,y:20.5}]),
+        ]);
+        let greedy = Optimizer::new(0.7, OptimizationStrategy::Greedy).optimize(&lines);
+        let two_opt = Optimizer::new(
+            0.7,
+            OptimizationStrategy::TwoOpt {
+                max_iterations: 100,
+            },
+        )
+        .optimize(&lines);
+        assert_eq!(two_opt.0.len(), lines.0.len());
+        assert!(travel(&two_opt.0) <= travel(&greedy.0) + 1.0e-9);
+    }
+
+    #[test]
+    fn test_simulated_annealing() {
+        let lines: MultiLineString<f64> = MultiLineString::new(vec![
+            LineString::new(vec![coord! {x: 0.0, y:20.0}, coord! {x:0.0, y:0.0}]),
+            LineString::new(vec![coord! {x: 20.0, y:20.5}, coord! {x:40.0, y:20.0}]),
+            LineString::new(vec![coord! {x: 0.0, y:0.0}, coord! {x:20.0, y:20.0}]),
+            LineString::new(vec![coord! {x: 20.0, y:0.5}, coord! {x:20.0, y:20.0}]),
+            LineString::new(vec![coord! {x:40.0, y:20.0}, coord! {x:40.5,y:40.5}]),
+            LineString::new(vec![coord! {x:0.0, y:0.0}, coord! {x:40.5,y:20.5}]),
+            LineString::new(vec![coord! {x:5.0, y:35.0}, coord! {x:30.0,y:35.0}]),
+        ]);
+        let greedy = Optimizer::new(0.7, OptimizationStrategy::Greedy).optimize(&lines);
+        let annealer = Optimizer::new(
+            0.7,
+            OptimizationStrategy::SimulatedAnnealing {
+                iterations: 5000,
+                seed: 42,
+            },
+        );
+        let annealed = annealer.optimize(&lines);
+        assert_eq!(annealed.0.len(), lines.0.len());
+        assert!(travel(&annealed.0) <= travel(&greedy.0) + 1.0e-9);
+        assert_eq!(annealed, annealer.optimize(&lines));
+    }
 }
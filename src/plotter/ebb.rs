@@ -0,0 +1,162 @@
+//! EiBotBoard (AxiDraw and friends) backend. These don't speak GCode at all, so
+//! instead of posting a program we drive the pen servo and steppers directly
+//! with `SP` and `SM` commands.
+use crate::plotter::{PlotterConnectionError, PlotterTransport};
+use geo_types::MultiLineString;
+
+/// AxiDraw default: 2032 steps/inch at 16x microstepping, which is 80 steps/mm.
+pub const DEFAULT_STEPS_PER_MM: f64 = 80.0;
+
+/// Longest single SM move the EBB will accept, in ms.
+const MAX_MOVE_MS: u64 = 16_777_215;
+
+pub struct EbbPlotter<T: PlotterTransport> {
+    transport: T,
+    speed: f64,
+    pen_delay: u32,
+    steps_per_mm: f64,
+    position: (i64, i64),
+}
+
+impl<T: PlotterTransport> EbbPlotter<T> {
+    /// Wraps a transport (usually a serial [`crate::plotter::PlotterConnection`]).
+    /// Assumes the carriage is sitting at home, 0,0.
+    pub fn new(transport: T) -> EbbPlotter<T> {
+        EbbPlotter {
+            transport,
+            speed: 25.0,
+            pen_delay: 200,
+            steps_per_mm: DEFAULT_STEPS_PER_MM,
+            position: (0, 0),
+        }
+    }
+
+    /// Drawing and travel speed in mm/s
+    pub fn speed(&mut self, mm_per_second: f64) -> &mut Self {
+        self.speed = mm_per_second;
+        self
+    }
+
+    /// How long (ms) the EBB waits after moving the pen before the next move, so
+    /// the servo is all the way up/down before the carriage goes anywhere.
+    pub fn pen_delay(&mut self, ms: u32) -> &mut Self {
+        self.pen_delay = ms;
+        self
+    }
+
+    /// Where we think the carriage is, in steps from home.
+    pub fn position(&self) -> (i64, i64) {
+        self.position
+    }
+
+    /// Hand the transport back, for when you're done.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Sends one command and waits for the EBB to say OK.
+    fn command(&mut self, cmd: &str) -> Result<(), PlotterConnectionError> {
+        self.transport.write_line(cmd)?;
+        self.transport.flush()?;
+        let mut response = String::new();
+        if self.transport.read_line(&mut response)? == 0 {
+            return Err(PlotterConnectionError::DeviceError(
+                "Connection closed while waiting for OK".to_string(),
+            ));
+        }
+        if response.trim() == "OK" {
+            Ok(())
+        } else {
+            Err(PlotterConnectionError::ControllerError(
+                response.trim().to_string(),
+            ))
+        }
+    }
+
+    /// Turns the steppers on at 16x microstepping
+    pub fn enable_motors(&mut self) -> Result<(), PlotterConnectionError> {
+        self.command("EM,1,1")
+    }
+
+    /// Turns the steppers off, so you can push the carriage around by hand.
+    pub fn disable_motors(&mut self) -> Result<(), PlotterConnectionError> {
+        self.command("EM,0,0")
+    }
+
+    pub fn pen_up(&mut self) -> Result<(), PlotterConnectionError> {
+        self.command(&format!("SP,1,{}", self.pen_delay))
+    }
+
+    pub fn pen_down(&mut self) -> Result<(), PlotterConnectionError> {
+        self.command(&format!("SP,0,{}", self.pen_delay))
+    }
+
+    /// Moves to an absolute position in steps, at the current speed. The AxiDraw
+    /// is a CoreXY style machine, so the motors each get x+y and x-y.
+    pub fn move_to_steps(&mut self, x: i64, y: i64) -> Result<(), PlotterConnectionError> {
+        let (dx, dy) = (x - self.position.0, y - self.position.1);
+        if dx == 0 && dy == 0 {
+            return Ok(());
+        }
+        let steps_per_second = (self.speed * self.steps_per_mm).max(1.0);
+        let distance = ((dx * dx + dy * dy) as f64).sqrt();
+        let duration = (distance / steps_per_second * 1000.0).ceil() as u64;
+        let duration = duration.clamp(1, MAX_MOVE_MS);
+        self.command(&format!("SM,{},{},{}", duration, dx + dy, dx - dy))?;
+        self.position = (x, y);
+        Ok(())
+    }
+
+    /// Plots lines given in mm. The pen goes up before every travel move and only
+    /// comes down once we're at the start of the next line, then finishes up.
+    pub fn plot_multilinestring(
+        &mut self,
+        lines: &MultiLineString<f64>,
+        steps_per_mm: f64,
+    ) -> Result<(), PlotterConnectionError> {
+        self.steps_per_mm = steps_per_mm;
+        let to_steps = |v: f64| (v * steps_per_mm).round() as i64;
+        self.enable_motors()?;
+        self.pen_up()?;
+        for line in lines.0.iter().filter(|line| line.0.len() > 1) {
+            let start = line.0[0];
+            self.move_to_steps(to_steps(start.x), to_steps(start.y))?;
+            self.pen_down()?;
+            for point in line.0.iter().skip(1) {
+                self.move_to_steps(to_steps(point.x), to_steps(point.y))?;
+            }
+            self.pen_up()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::plotter::MockTransport;
+    use geo_types::{coord, LineString};
+
+    #[test]
+    fn test_plot_multilinestring() {
+        let mut ebb = EbbPlotter::new(MockTransport::new(&["OK"; 6]));
+        ebb.speed(10.0).pen_delay(100);
+        let lines = MultiLineString::new(vec![LineString::new(vec![
+            coord! {x: 1.0, y: 0.0},
+            coord! {x: 1.0, y: 1.0},
+        ])]);
+        ebb.plot_multilinestring(&lines, 80.0).unwrap();
+        assert_eq!(ebb.position(), (80, 80));
+        assert_eq!(
+            ebb.into_inner().written(),
+            &[
+                "EM,1,1",
+                "SP,1,100",
+                "SM,100,80,80",
+                "SP,0,100",
+                "SM,100,80,-80",
+                "SP,1,100"
+            ]
+        );
+    }
+}
@@ -6,6 +6,7 @@ pub enum PlotterConnectionError {
     IOError(i32),
     DeviceError(String),
     ParseError(String),
+    ControllerError(String),
     UnknownError,
 }
 
@@ -21,6 +22,12 @@ impl From<ParseIntError> for PlotterConnectionError {
     }
 }
 
+impl From<std::io::Error> for PlotterConnectionError {
+    fn from(error: std::io::Error) -> Self {
+        PlotterConnectionError::DeviceError(error.to_string())
+    }
+}
+
 impl From<serialport::Error> for PlotterConnectionError {
     fn from(error: serialport::Error) -> Self {
         PlotterConnectionError::DeviceError(error.to_string())
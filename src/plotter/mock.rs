@@ -0,0 +1,71 @@
+use crate::plotter::PlotterTransport;
+use std::collections::VecDeque;
+
+/// A fake plotter for tests. Remembers every line written to it, and answers
+/// read_line from a queue of canned responses (so you can script the `ok`s).
+/// Once the queue runs dry, reads look like a closed connection.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    written: Vec<String>,
+    responses: VecDeque<String>,
+}
+
+impl MockTransport {
+    pub fn new(responses: &[&str]) -> MockTransport {
+        MockTransport {
+            written: vec![],
+            responses: responses.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Queue up another response.
+    pub fn respond(&mut self, response: &str) -> &mut Self {
+        self.responses.push_back(response.to_string());
+        self
+    }
+
+    /// Everything written so far, one entry per write_line.
+    pub fn written(&self) -> &[String] {
+        &self.written
+    }
+}
+
+impl PlotterTransport for MockTransport {
+    fn write_line(&mut self, buf: &str) -> std::io::Result<()> {
+        self.written.push(buf.to_string());
+        Ok(())
+    }
+
+    fn read_line(&mut self, buf: &mut String) -> std::io::Result<usize> {
+        match self.responses.pop_front() {
+            Some(response) => {
+                let line = response + "\n";
+                buf.push_str(&line);
+                Ok(line.len())
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mock_transport() {
+        let mut mock = MockTransport::new(&["ok"]);
+        mock.respond("error:1");
+        mock.write_line("G28").unwrap();
+        let mut buf = String::new();
+        assert_eq!(mock.read_line(&mut buf).unwrap(), 3);
+        mock.read_line(&mut buf).unwrap();
+        assert_eq!(buf, "ok\nerror:1\n");
+        assert_eq!(mock.read_line(&mut buf).unwrap(), 0);
+        assert_eq!(mock.written(), &["G28".to_string()]);
+    }
+}
@@ -1,30 +1,98 @@
 use serialport;
 use std::io::Write;
 use std::io::{BufRead, BufReader, BufWriter};
+use std::net::TcpStream;
 use std::ops::DerefMut;
 use std::time::Duration;
 
 pub mod error;
 pub use error::PlotterConnectionError;
 
+/// Fake transport, for testing things that talk to plotters
+pub mod mock;
+pub use mock::MockTransport;
+
+/// EiBotBoard (AxiDraw) protocol backend
+pub mod ebb;
+
 const DEFAULT_TIMEOUT: u64 = 30000;
 
 pub trait PlotterTransport {
     fn write_line(&mut self, buf: &str) -> std::io::Result<()>;
     fn read_line(&mut self, buf: &mut String) -> std::io::Result<usize>;
     fn flush(&mut self) -> std::io::Result<()>;
+
+    /// Streams a whole program, GRBL style: send a line, wait for the controller to
+    /// say `ok` (or `error`) before sending the next. Blank and comment-only lines
+    /// are skipped, and anything else the controller chatters back (status, echo)
+    /// is ignored. Calls on_progress with (sent, total) after each acknowledged line.
+    fn stream_program(
+        &mut self,
+        program: &[String],
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<(), PlotterConnectionError>
+    where
+        Self: Sized,
+    {
+        let lines: Vec<&str> = program
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with(';') && !line.starts_with('('))
+            .collect();
+        let total = lines.len();
+        for (i, line) in lines.iter().enumerate() {
+            self.write_line(line)?;
+            self.flush()?;
+            loop {
+                let mut response = String::new();
+                if self.read_line(&mut response)? == 0 {
+                    return Err(PlotterConnectionError::DeviceError(
+                        "Connection closed while waiting for ok".to_string(),
+                    ));
+                }
+                let response = response.trim();
+                if response.starts_with("ok") {
+                    break;
+                } else if response.starts_with("error") {
+                    return Err(PlotterConnectionError::ControllerError(
+                        response.to_string(),
+                    ));
+                }
+            }
+            on_progress(i + 1, total);
+        }
+        Ok(())
+    }
 }
 
 pub enum PlotterConnection {
     SerialReadWrite(Box<dyn BufRead>, Box<dyn Write>),
+    TcpReadWrite(Box<dyn BufRead>, Box<dyn Write>),
 }
 
 impl PlotterConnection {
     /// Given a URI in the form of serial:///dev/ttySomethingOrOther@115200,
     /// open up a serial connection on the /dev/ttySomethingOrOther at 115200 bps.
+    /// Also does tcp://host:port, for controllers that expose a raw socket
+    /// (GRBL on an ESP32, Marlin over telnet, etc).
     pub fn from_uri(uri: &str) -> Result<PlotterConnection, PlotterConnectionError> {
         let url = url::Url::parse(uri)?;
-        if url.scheme() == "serial" {
+        if url.scheme() == "tcp" {
+            let host = url.host_str().ok_or(PlotterConnectionError::ParseError(
+                "Missing host".to_string(),
+            ))?;
+            let port = url.port().ok_or(PlotterConnectionError::ParseError(
+                "Missing port".to_string(),
+            ))?;
+            let stream = TcpStream::connect((host, port))?;
+            stream.set_read_timeout(Some(Duration::from_millis(DEFAULT_TIMEOUT)))?;
+            let reader = BufReader::new(stream.try_clone()?);
+            let writer = BufWriter::new(stream);
+            Ok(PlotterConnection::TcpReadWrite(
+                Box::new(reader),
+                Box::new(writer),
+            ))
+        } else if url.scheme() == "serial" {
             let parts: Vec<&str> = url.path().split("@").collect();
             if parts.len() == 2 {
                 let path = parts[0].to_string();
@@ -50,7 +118,8 @@ impl PlotterConnection {
 impl PlotterTransport for PlotterConnection {
     fn write_line(&mut self, buf: &str) -> std::io::Result<()> {
         match self {
-            PlotterConnection::SerialReadWrite(_, ref mut bwrite) => bwrite
+            PlotterConnection::SerialReadWrite(_, ref mut bwrite)
+            | PlotterConnection::TcpReadWrite(_, ref mut bwrite) => bwrite
                 .deref_mut()
                 .write_all((buf.to_owned() + "\n").as_bytes()),
         }
@@ -58,24 +127,76 @@ impl PlotterTransport for PlotterConnection {
 
     fn read_line(&mut self, buf: &mut String) -> std::io::Result<usize> {
         match self {
-            PlotterConnection::SerialReadWrite(ref mut bread, _) => {
-                bread.deref_mut().read_line(buf)
-            }
+            PlotterConnection::SerialReadWrite(ref mut bread, _)
+            | PlotterConnection::TcpReadWrite(ref mut bread, _) => bread.deref_mut().read_line(buf),
         }
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
         match self {
-            PlotterConnection::SerialReadWrite(_, ref mut bwrite) => bwrite.deref_mut().flush(),
+            PlotterConnection::SerialReadWrite(_, ref mut bwrite)
+            | PlotterConnection::TcpReadWrite(_, ref mut bwrite) => bwrite.deref_mut().flush(),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    // use super::*;
+    use super::*;
+    use std::net::TcpListener;
     // use std::time::Duration;
 
+    #[test]
+    fn test_stream_program() {
+        let program: Vec<String> = vec!["G28", "", "; comment", "G0 X1 Y1", "(also comment)", "M2"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut mock = MockTransport::new(&["echo", "ok", "ok", "[MSG:status]", "ok"]);
+        let mut progress = vec![];
+        mock.stream_program(&program, |sent, total| progress.push((sent, total)))
+            .unwrap();
+        assert_eq!(mock.written(), &["G28", "G0 X1 Y1", "M2"]);
+        assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+
+        let mut mock = MockTransport::new(&["ok", "error:20", "ok"]);
+        let result = mock.stream_program(&program, |_, _| {});
+        assert!(matches!(
+            result,
+            Err(PlotterConnectionError::ControllerError(msg)) if msg == "error:20"
+        ));
+        assert_eq!(mock.written().len(), 2);
+
+        let mut mock = MockTransport::new(&["ok"]);
+        assert!(matches!(
+            mock.stream_program(&program, |_, _| {}),
+            Err(PlotterConnectionError::DeviceError(_))
+        ));
+    }
+
+    #[test]
+    fn test_tcp_uri() {
+        assert!(matches!(
+            PlotterConnection::from_uri("tcp://localhost"),
+            Err(PlotterConnectionError::ParseError(_))
+        ));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut pc = PlotterConnection::from_uri(&format!("tcp://127.0.0.1:{}", port)).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        pc.write_line("G28").unwrap();
+        pc.flush().unwrap();
+        let mut received = String::new();
+        BufReader::new(server.try_clone().unwrap())
+            .read_line(&mut received)
+            .unwrap();
+        assert_eq!(received, "G28\n");
+        server.write_all(b"ok\n").unwrap();
+        let mut reply = String::new();
+        pc.read_line(&mut reply).unwrap();
+        assert_eq!(reply, "ok\n");
+    }
+
     /*
     #[tests]
     fn test_from_url(){
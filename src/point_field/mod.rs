@@ -0,0 +1,679 @@
+use crate::geo_types::hatch::gt_flatten_mlines;
+use geo::bounding_rect::BoundingRect;
+use geo::centroid::Centroid;
+use geo::contains::Contains;
+use geo::intersects::Intersects;
+use geo_types::{
+    coord, Coordinate, Geometry, LineString, MultiLineString, MultiPoint, MultiPolygon, Point,
+    Polygon, Rect,
+};
+use geos::Geom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::error::Error;
+use std::f64::consts::PI;
+
+/// A field of points scattered over some bounds, for stippling and the like. You
+/// just iterate it to get the points.
+pub trait PointField: Iterator<Item = Point<f64>> {
+    /// The area the points get scattered over.
+    fn bounds(&self) -> Rect<f64>;
+
+    /// Only the points that land inside `poly`. Fields that never run out of points
+    /// stay that way when clipped, so `take(n)` what you need. And if the polygon
+    /// misses the field entirely, asking an endless field for a point never returns.
+    fn clip_to(self, poly: Polygon<f64>) -> ClippedPointField<Self>
+    where
+        Self: Sized,
+    {
+        ClippedPointField { field: self, poly }
+    }
+}
+
+/// A [`PointField`] filtered down to a polygon. See [`PointField::clip_to`].
+#[derive(Debug, Clone)]
+pub struct ClippedPointField<F: PointField> {
+    field: F,
+    poly: Polygon<f64>,
+}
+
+impl<F: PointField> Iterator for ClippedPointField<F> {
+    type Item = Point<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let poly = &self.poly;
+        self.field.find(|point| poly.contains(point))
+    }
+}
+
+impl<F: PointField> PointField for ClippedPointField<F> {
+    /// Whatever's left of the inner field's bounds once it's clipped to the polygon.
+    fn bounds(&self) -> Rect<f64> {
+        let inner = self.field.bounds();
+        match self.poly.bounding_rect() {
+            Some(clip) => Rect::new(
+                coord! {x: inner.min().x.max(clip.min().x), y: inner.min().y.max(clip.min().y)},
+                coord! {x: inner.max().x.min(clip.max().x), y: inner.max().y.min(clip.max().y)},
+            ),
+            None => inner,
+        }
+    }
+}
+
+/// How many candidates we try around an active point before giving up on it.
+/// 30 is what Bridson's paper uses.
+const POISSON_ATTEMPTS: usize = 30;
+
+/// Blue noise! Poisson disk sampling via Bridson's algorithm, so no two points are
+/// closer than `radius`, but there are no big gaps either (just about everything in
+/// the bounds ends up within 2x radius of a point). Much more organic looking than random points.
+/// Points are generated as you iterate, so it's cheap to just `take` a few.
+///
+/// ```rust
+/// use aoer_plotty_rs::point_field::PoissonDiskField;
+/// use geo_types::{coord, Rect};
+///
+/// let points: Vec<_> = PoissonDiskField::builder()
+///     .bounds(Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 100.0}))
+///     .radius(5.0)
+///     .seed(42)
+///     .build()
+///     .collect();
+/// assert!(points.len() > 100);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoissonDiskField {
+    bounds: Rect<f64>,
+    radius: f64,
+    rng: StdRng,
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    grid: Vec<Option<usize>>,
+    points: Vec<Point<f64>>,
+    active: Vec<usize>,
+}
+
+/// Builder for a [`PoissonDiskField`]. Defaults to a 100x100 field with radius 1
+/// and seed 0.
+#[derive(Debug, Clone)]
+pub struct PoissonDiskFieldBuilder {
+    bounds: Rect<f64>,
+    radius: f64,
+    seed: u64,
+}
+
+impl PoissonDiskFieldBuilder {
+    pub fn bounds(mut self, bounds: Rect<f64>) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Minimum distance between any two points
+    pub fn radius(mut self, radius: f64) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Same seed, same points.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn build(self) -> PoissonDiskField {
+        let cell_size = self.radius / 2.0_f64.sqrt();
+        // A zero/negative radius or empty bounds just gets you no points.
+        let (columns, rows) =
+            if self.radius > 0.0 && self.bounds.width() > 0.0 && self.bounds.height() > 0.0 {
+                (
+                    (self.bounds.width() / cell_size).ceil() as usize,
+                    (self.bounds.height() / cell_size).ceil() as usize,
+                )
+            } else {
+                (0, 0)
+            };
+        PoissonDiskField {
+            bounds: self.bounds,
+            radius: self.radius,
+            rng: StdRng::seed_from_u64(self.seed),
+            cell_size,
+            columns,
+            rows,
+            grid: vec![None; columns * rows],
+            points: vec![],
+            active: vec![],
+        }
+    }
+}
+
+impl PoissonDiskField {
+    pub fn builder() -> PoissonDiskFieldBuilder {
+        PoissonDiskFieldBuilder {
+            bounds: Rect::new((0.0, 0.0), (100.0, 100.0)),
+            radius: 1.0,
+            seed: 0,
+        }
+    }
+
+    fn cell(&self, point: &Point<f64>) -> (usize, usize) {
+        let column = ((point.x() - self.bounds.min().x) / self.cell_size) as usize;
+        let row = ((point.y() - self.bounds.min().y) / self.cell_size) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    /// Inside the bounds, and nothing already in the grid is too close?
+    fn fits(&self, point: &Point<f64>) -> bool {
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        if point.x() < min.x || point.x() > max.x || point.y() < min.y || point.y() > max.y {
+            return false;
+        }
+        let (column, row) = self.cell(point);
+        // Cells are radius/sqrt(2) across, so anything too close is at most 2 away.
+        for r in row.saturating_sub(2)..(row + 3).min(self.rows) {
+            for c in column.saturating_sub(2)..(column + 3).min(self.columns) {
+                if let Some(other) = self.grid[r * self.columns + c] {
+                    let other = self.points[other];
+                    let (dx, dy) = (other.x() - point.x(), other.y() - point.y());
+                    if (dx * dx + dy * dy).sqrt() < self.radius {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn insert(&mut self, point: Point<f64>) -> Point<f64> {
+        let (column, row) = self.cell(&point);
+        self.grid[row * self.columns + column] = Some(self.points.len());
+        self.active.push(self.points.len());
+        self.points.push(point);
+        point
+    }
+}
+
+impl Iterator for PoissonDiskField {
+    type Item = Point<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.grid.is_empty() {
+            return None;
+        }
+        if self.points.is_empty() {
+            let (min, max) = (self.bounds.min(), self.bounds.max());
+            let first = Point::new(
+                self.rng.gen_range(min.x..max.x),
+                self.rng.gen_range(min.y..max.y),
+            );
+            return Some(self.insert(first));
+        }
+        while !self.active.is_empty() {
+            let index = self.rng.gen_range(0..self.active.len());
+            let around = self.points[self.active[index]];
+            for _attempt in 0..POISSON_ATTEMPTS {
+                let distance = self.rng.gen_range(self.radius..2.0 * self.radius);
+                let angle = self.rng.gen_range(0.0..2.0 * PI);
+                let candidate = Point::new(
+                    around.x() + distance * angle.cos(),
+                    around.y() + distance * angle.sin(),
+                );
+                if self.fits(&candidate) {
+                    return Some(self.insert(candidate));
+                }
+            }
+            // Nothing fits around this one any more, so it's done.
+            self.active.swap_remove(index);
+        }
+        None
+    }
+}
+
+impl PointField for PoissonDiskField {
+    fn bounds(&self) -> Rect<f64> {
+        self.bounds
+    }
+}
+
+/// A grid of points, each nudged a random amount within its own cell. Somewhere
+/// between a boring grid and messy random points. With a `jitter` of 0 you get the
+/// cell centers exactly, and at 1 a point can end up anywhere in its cell (so it
+/// moves up to half a `spacing` each way). Points come a row at a time.
+#[derive(Debug, Clone)]
+pub struct JitteredGridField {
+    bounds: Rect<f64>,
+    spacing: f64,
+    jitter: f64,
+    rng: StdRng,
+    columns: usize,
+    rows: usize,
+    index: usize,
+}
+
+/// Builder for a [`JitteredGridField`]. Defaults to a 100x100 field with spacing 1,
+/// half a cell of jitter and seed 0.
+#[derive(Debug, Clone)]
+pub struct JitteredGridFieldBuilder {
+    bounds: Rect<f64>,
+    spacing: f64,
+    jitter: f64,
+    seed: u64,
+}
+
+impl JitteredGridFieldBuilder {
+    pub fn bounds(mut self, bounds: Rect<f64>) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Size of each grid cell
+    pub fn spacing(mut self, spacing: f64) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// How far (as a fraction of a cell, 0..1) a point can wander from the center.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Same seed, same points.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn build(self) -> JitteredGridField {
+        let (columns, rows) = if self.spacing > 0.0 {
+            (
+                (self.bounds.width() / self.spacing).floor() as usize,
+                (self.bounds.height() / self.spacing).floor() as usize,
+            )
+        } else {
+            (0, 0)
+        };
+        JitteredGridField {
+            bounds: self.bounds,
+            spacing: self.spacing,
+            jitter: self.jitter,
+            rng: StdRng::seed_from_u64(self.seed),
+            columns,
+            rows,
+            index: 0,
+        }
+    }
+}
+
+impl JitteredGridField {
+    pub fn builder() -> JitteredGridFieldBuilder {
+        JitteredGridFieldBuilder {
+            bounds: Rect::new((0.0, 0.0), (100.0, 100.0)),
+            spacing: 1.0,
+            jitter: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+impl Iterator for JitteredGridField {
+    type Item = Point<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.columns * self.rows {
+            return None;
+        }
+        let (column, row) = (self.index % self.columns, self.index / self.columns);
+        self.index += 1;
+        let wander = self.jitter * self.spacing;
+        let (dx, dy) = (
+            self.rng.gen_range(-0.5..=0.5) * wander,
+            self.rng.gen_range(-0.5..=0.5) * wander,
+        );
+        Some(Point::new(
+            self.bounds.min().x + (column as f64 + 0.5) * self.spacing + dx,
+            self.bounds.min().y + (row as f64 + 0.5) * self.spacing + dy,
+        ))
+    }
+}
+
+impl PointField for JitteredGridField {
+    fn bounds(&self) -> Rect<f64> {
+        self.bounds
+    }
+}
+
+/// A Voronoi diagram, clipped to the bounds of the field it came from. `cells[i]` is
+/// the cell around `sites[i]` (empty if GEOS merged that site into another one).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voronoi {
+    pub sites: Vec<Point<f64>>,
+    pub cells: Vec<Polygon<f64>>,
+}
+
+impl Voronoi {
+    /// Builds the diagram for `sites` using GEOS, clipping every cell to `bounds`.
+    pub fn new(sites: Vec<Point<f64>>, bounds: &Rect<f64>) -> Result<Voronoi, Box<dyn Error>> {
+        let mut cells = vec![Polygon::new(LineString::new(vec![]), vec![]); sites.len()];
+        if sites.is_empty() {
+            return Ok(Voronoi { sites, cells });
+        }
+        let envelope = geos::Geometry::try_from(bounds.to_polygon())?;
+        let raw_cells = geos::Geometry::try_from(&MultiPoint::new(sites.clone()))?.voronoi(
+            Some(&envelope),
+            0.0,
+            false,
+        )?;
+        let raw_cells = match Geometry::<f64>::try_from(raw_cells)? {
+            Geometry::GeometryCollection(collection) => collection.0,
+            other => vec![other],
+        };
+        for cell in raw_cells {
+            let cell = match cell {
+                Geometry::Polygon(cell) => cell,
+                _ => continue,
+            };
+            // The cells are convex, so clipping one to the bounds leaves one polygon.
+            let clipped = geos::Geometry::try_from(&cell)?.intersection(&envelope)?;
+            let clipped = match Geometry::<f64>::try_from(clipped)? {
+                Geometry::Polygon(clipped) => clipped,
+                _ => continue,
+            };
+            // GEOS doesn't keep the cells in site order, so go find the owner.
+            if let Some(i) = sites.iter().position(|site| clipped.intersects(site)) {
+                cells[i] = clipped;
+            }
+        }
+        Ok(Voronoi { sites, cells })
+    }
+}
+
+/// Turns any [`PointField`] into a Voronoi diagram.
+pub trait FieldToVoronoi: PointField + Sized {
+    /// Voronoi diagram of the next `point_count` points from the field.
+    fn to_voronoi(&mut self, point_count: usize) -> Result<Voronoi, Box<dyn Error>> {
+        let sites: Vec<Point<f64>> = self.by_ref().take(point_count).collect();
+        Voronoi::new(sites, &self.bounds())
+    }
+
+    /// Lloyd relaxation: like to_voronoi, but then `iterations` times over, every
+    /// site gets moved to the centroid of its cell and the diagram is rebuilt. The
+    /// cells even out into a nice (centroidal) honeycomb-ish pattern pretty quickly.
+    fn to_voronoi_relaxed(
+        &mut self,
+        point_count: usize,
+        iterations: usize,
+    ) -> Result<Voronoi, Box<dyn Error>> {
+        let bounds = self.bounds();
+        let mut voronoi = self.to_voronoi(point_count)?;
+        for _iteration in 0..iterations {
+            let sites = voronoi
+                .sites
+                .iter()
+                .zip(voronoi.cells.iter())
+                .map(|(site, cell)| match cell.centroid() {
+                    Some(centroid) => Point::new(
+                        centroid.x().clamp(bounds.min().x, bounds.max().x),
+                        centroid.y().clamp(bounds.min().y, bounds.max().y),
+                    ),
+                    None => *site,
+                })
+                .collect();
+            voronoi = Voronoi::new(sites, &bounds)?;
+        }
+        Ok(voronoi)
+    }
+}
+
+impl<F: PointField> FieldToVoronoi for F {}
+
+/// Turns any [`PointField`] into a Delaunay triangulation, for meshes and wireframes.
+pub trait FieldToDelaunay: PointField + Sized {
+    /// The triangle edges between the next `point_count` points from the field. Each
+    /// edge only shows up once, even though most of them belong to two triangles.
+    fn to_delaunay(&mut self, point_count: usize) -> Result<MultiLineString<f64>, Box<dyn Error>> {
+        let sites: Vec<Point<f64>> = self.by_ref().take(point_count).collect();
+        if sites.len() < 2 {
+            return Ok(MultiLineString::new(vec![]));
+        }
+        let edges =
+            geos::Geometry::try_from(&MultiPoint::new(sites))?.delaunay_triangulation(0.0, true)?;
+        Ok(gt_flatten_mlines(
+            Geometry::try_from(edges)?,
+            MultiLineString::new(vec![]),
+        ))
+    }
+}
+
+impl<F: PointField> FieldToDelaunay for F {}
+
+/// The cells of a [`Voronoi`] diagram as polygons, skipping any empty ones.
+pub fn voronoi_to_multipolygon(v: &Voronoi) -> MultiPolygon<f64> {
+    MultiPolygon::new(
+        v.cells
+            .iter()
+            .filter(|cell| cell.exterior().0.len() > 3)
+            .cloned()
+            .collect(),
+    )
+}
+
+/// The edges of a [`Voronoi`] diagram, ready to plot. Neighbouring cells share
+/// edges, so we walk each cell's ring and only keep the edges we haven't already
+/// drawn; runs of new edges stay joined up so the pen isn't lifting all the time.
+pub fn voronoi_to_multilinestring(v: &Voronoi) -> MultiLineString<f64> {
+    // Shared vertices come out of GEOS a hair different for each cell, so snap them.
+    let key = |c: &Coordinate<f64>| ((c.x * 1e3).round() as i64, (c.y * 1e3).round() as i64);
+    let mut seen = HashSet::new();
+    let mut lines = vec![];
+    for cell in &v.cells {
+        let mut run: Vec<Coordinate<f64>> = vec![];
+        for edge in cell.exterior().lines() {
+            let (start, end) = (key(&edge.start), key(&edge.end));
+            if start == end {
+                continue;
+            }
+            if seen.insert((start.min(end), start.max(end))) {
+                if run.is_empty() {
+                    run.push(edge.start);
+                }
+                run.push(edge.end);
+            } else if run.len() > 1 {
+                lines.push(LineString::new(std::mem::take(&mut run)));
+            }
+        }
+        if run.len() > 1 {
+            lines.push(LineString::new(run));
+        }
+    }
+    MultiLineString::new(lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use geo::area::Area;
+    use geo_types::coord;
+
+    fn area_variance(voronoi: &Voronoi) -> f64 {
+        let areas: Vec<f64> = voronoi
+            .cells
+            .iter()
+            .map(|cell| cell.unsigned_area())
+            .collect();
+        let mean = areas.iter().sum::<f64>() / areas.len() as f64;
+        areas.iter().map(|area| (area - mean).powi(2)).sum::<f64>() / areas.len() as f64
+    }
+
+    #[test]
+    fn test_jittered_grid_field() {
+        let bounds = Rect::new(coord! {x: 10.0, y: 20.0}, coord! {x: 40.0, y: 40.0});
+        let grid = |jitter| {
+            JitteredGridField::builder()
+                .bounds(bounds)
+                .spacing(10.0)
+                .jitter(jitter)
+                .seed(5)
+                .build()
+        };
+        let points: Vec<Point<f64>> = grid(0.0).collect();
+        assert_eq!(points.len(), 6);
+        assert_eq!(points[0], Point::new(15.0, 25.0));
+        assert_eq!(points[2], Point::new(35.0, 25.0));
+        assert_eq!(points[5], Point::new(35.0, 35.0));
+
+        for (jittered, center) in grid(1.0).zip(points.iter()) {
+            assert!((jittered.x() - center.x()).abs() <= 5.0);
+            assert!((jittered.y() - center.y()).abs() <= 5.0);
+        }
+        assert_ne!(grid(1.0).collect::<Vec<_>>(), points);
+    }
+
+    #[test]
+    fn test_clip_to() {
+        let bounds = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 100.0});
+        let square = Rect::new(coord! {x: 40.0, y: 40.0}, coord! {x: 60.0, y: 60.0});
+        let field = JitteredGridField::builder()
+            .bounds(bounds)
+            .spacing(2.0)
+            .jitter(1.0)
+            .seed(9)
+            .build()
+            .clip_to(square.to_polygon());
+        assert_eq!(field.bounds(), square);
+        let points: Vec<Point<f64>> = field.collect();
+        // About 100 of the 2500 grid points should make it.
+        assert!(points.len() > 80 && points.len() < 120);
+        for point in points {
+            assert!(point.x() > 40.0 && point.x() < 60.0 && point.y() > 40.0 && point.y() < 60.0);
+        }
+    }
+
+    /// Just hands back the points it was given.
+    struct FixedField(Rect<f64>, Vec<Point<f64>>);
+
+    impl Iterator for FixedField {
+        type Item = Point<f64>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.1.pop()
+        }
+    }
+
+    impl PointField for FixedField {
+        fn bounds(&self) -> Rect<f64> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_delaunay() {
+        let bounds = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 10.0, y: 10.0});
+        let corners = bounds.to_polygon().exterior().points().take(4).collect();
+        let edges = FixedField(bounds, corners).to_delaunay(4).unwrap();
+        let key = |c: &Coordinate<f64>| ((c.x * 1e3).round() as i64, (c.y * 1e3).round() as i64);
+        let unique: HashSet<_> = edges
+            .0
+            .iter()
+            .flat_map(|line| line.lines())
+            .map(|edge| {
+                let (start, end) = (key(&edge.start), key(&edge.end));
+                (start.min(end), start.max(end))
+            })
+            .collect();
+        assert_eq!(unique.len(), 5);
+        assert_eq!(
+            edges
+                .0
+                .iter()
+                .map(|line| line.lines().count())
+                .sum::<usize>(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_voronoi_to_geometry() {
+        let bounds = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 50.0, y: 50.0});
+        let voronoi = PoissonDiskField::builder()
+            .bounds(bounds)
+            .radius(8.0)
+            .seed(11)
+            .build()
+            .to_voronoi(10)
+            .unwrap();
+        assert_eq!(voronoi_to_multipolygon(&voronoi).0.len(), 10);
+        let edges = voronoi_to_multilinestring(&voronoi);
+        assert!(!edges.0.is_empty());
+        let key = |c: &Coordinate<f64>| ((c.x * 1e3).round() as i64, (c.y * 1e3).round() as i64);
+        let mut seen = HashSet::new();
+        for edge in edges.0.iter().flat_map(|line| line.lines()) {
+            let (start, end) = (key(&edge.start), key(&edge.end));
+            assert!(
+                seen.insert((start.min(end), start.max(end))),
+                "Duplicate edge"
+            );
+        }
+    }
+
+    #[test]
+    fn test_voronoi_relaxed() {
+        let bounds = Rect::new(coord! {x: 0.0, y: 0.0}, coord! {x: 100.0, y: 100.0});
+        let field = || {
+            PoissonDiskField::builder()
+                .bounds(bounds)
+                .radius(5.0)
+                .seed(3)
+                .build()
+        };
+        // The first few poisson points are all bunched up around the first one, so
+        // the raw cells are wildly uneven.
+        let raw = field().to_voronoi(20).unwrap();
+        let relaxed = field().to_voronoi_relaxed(20, 10).unwrap();
+        assert_eq!(raw.cells.len(), 20);
+        assert_eq!(relaxed.sites.len(), 20);
+        let total: f64 = relaxed.cells.iter().map(|cell| cell.unsigned_area()).sum();
+        assert!((total - 10000.0).abs() < 1e-6);
+        for site in &relaxed.sites {
+            assert!(site.x() >= 0.0 && site.x() <= 100.0 && site.y() >= 0.0 && site.y() <= 100.0);
+        }
+        assert!(area_variance(&relaxed) < area_variance(&raw));
+    }
+
+    #[test]
+    fn test_poisson_disk_field() {
+        let bounds = Rect::new(coord! {x: 10.0, y: 10.0}, coord! {x: 110.0, y: 60.0});
+        let field = PoissonDiskField::builder()
+            .bounds(bounds)
+            .radius(4.0)
+            .seed(7)
+            .build();
+        assert_eq!(field.bounds(), bounds);
+        let points: Vec<Point<f64>> = field.collect();
+        for (i, a) in points.iter().enumerate() {
+            assert!(a.x() >= 10.0 && a.x() <= 110.0 && a.y() >= 10.0 && a.y() <= 60.0);
+            for b in points.iter().skip(i + 1) {
+                let (dx, dy) = (a.x() - b.x(), a.y() - b.y());
+                assert!((dx * dx + dy * dy).sqrt() >= 4.0);
+            }
+        }
+        // And it covers the whole thing, without any big holes.
+        for x in 0..=20 {
+            for y in 0..=10 {
+                let (px, py) = (10.0 + x as f64 * 5.0, 10.0 + y as f64 * 5.0);
+                assert!(points
+                    .iter()
+                    .any(|p| ((p.x() - px).powi(2) + (p.y() - py).powi(2)).sqrt() <= 12.0));
+            }
+        }
+        let again: Vec<Point<f64>> = PoissonDiskField::builder()
+            .bounds(bounds)
+            .radius(4.0)
+            .seed(7)
+            .build()
+            .collect();
+        assert_eq!(points, again);
+        assert_eq!(PoissonDiskField::builder().radius(0.0).build().count(), 0);
+    }
+}
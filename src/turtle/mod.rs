@@ -1,4 +1,6 @@
-use geo_types::{LineString, MultiLineString, Point, Polygon};
+use geo::area::Area;
+use geo::contains::Contains;
+use geo_types::{LineString, MultiLineString, MultiPolygon, Point, Polygon};
 
 /// # Turtle Module
 ///
@@ -12,6 +14,9 @@ pub struct Turtle {
     start: Option<Point<f64>>,
     heading: f64,
     pen: bool,
+    color: String,
+    pen_width: f64,
+    line_styles: Vec<(String, f64)>,
 }
 
 /// Helper function to convert degrees to radians
@@ -61,6 +66,33 @@ pub trait TurtleTrait {
     /// Turn right @angle radians
     fn right(self, angle: f64) -> Self;
 
+    /// #goto
+    ///
+    /// Move straight to @x,@y, drawing a line there if the pen is down. Doesn't
+    /// change the heading.
+    fn goto(self, x: f64, y: f64) -> Self;
+
+    /// #set_heading
+    ///
+    /// Point the turtle at @radians, counterclockwise from the +x axis.
+    fn set_heading(self, radians: f64) -> Self;
+
+    /// #heading
+    ///
+    /// Which way the turtle is pointing, in radians.
+    fn heading(&self) -> f64;
+
+    /// #set_pen_width
+    ///
+    /// Sets the pen width for everything drawn from here on. If the pen's down,
+    /// the current line is split here so the new width starts at this point.
+    fn set_pen_width(self, width: f64) -> Self;
+
+    /// #set_color
+    ///
+    /// Same as [`crate::turtle::TurtleTrait::set_pen_width`], but for the color.
+    fn set_color(self, color: &str) -> Self;
+
     /// #pen_up
     ///
     /// Lift the pen and discard the closing state
@@ -95,17 +127,39 @@ pub trait TurtleTrait {
     /// push/pop, and turns.
     fn walk_lpath(self, lpath: &String, angle: f64, distance: f64) -> Self;
 
+    /// # walk_parametric
+    ///
+    /// Walks the output of [`crate::l_system::ParametricLSystem::expand`]. `F(d)` (or any
+    /// other letter with a parameter) moves forward d, `+(a)` and `-(a)` turn right and
+    /// left by a radians, `!(w)` sets the pen width, and `[`/`]` push and pop. Anything
+    /// missing its parameter is skipped.
+    fn walk_parametric(self, path: &[(char, Vec<f64>)]) -> Self;
+
     /// # to_multiline
     ///
     /// Takes the lines recorded in the Turtle state and returns a [`geo_types::MultiLineString`]
     fn to_multiline(&mut self) -> MultiLineString<f64>;
 
+    /// # to_layers
+    ///
+    /// Like [`crate::turtle::TurtleTrait::to_multiline`], but grouped up into
+    /// (color, pen width, lines), one group per run of lines drawn with the same
+    /// pen. Feed each one to a Context with matching stroke and pen.
+    fn to_layers(&self) -> Vec<(String, f64, MultiLineString<f64>)>;
+
     /// # to_polygon
     ///
     /// Returns a [`geo_types::Polygon`] from the Turtle state. May return an error for turtles
     /// which have self-intersecting lines, or zero-volume polygons.
     fn to_polygon(&mut self) -> Result<Polygon<f64>, geo_types::Error>;
-    // fn to_multipolygon(self) -> Result<MultiPolygon<f64>, geo_types::Error>;
+
+    /// # to_multipolygon
+    ///
+    /// Closes every recorded line and returns them as a [`geo_types::MultiPolygon`].
+    /// Loops inside other loops become holes, and loops inside holes become islands
+    /// again, and so on. Lines with fewer than 3 points can't be a loop, so they're
+    /// dropped. Errors if there's nothing left.
+    fn to_multipolygon(&mut self) -> Result<MultiPolygon<f64>, geo_types::Error>;
 }
 
 
@@ -118,6 +172,9 @@ impl TurtleTrait for Turtle {
             start: None,
             heading: 0.0,
             pen: false,
+            color: "black".to_string(),
+            pen_width: 0.5,
+            line_styles: vec![],
         }
     }
 
@@ -134,6 +191,36 @@ impl TurtleTrait for Turtle {
         self
     }
 
+    fn goto(mut self, x: f64, y: f64) -> Self {
+        let pos = Point::new(x, y);
+        if self.pen {
+            self.lines.last_mut()
+                .expect("Turtle moving without an active line!")
+                .push(pos)
+        }
+        self.position = pos;
+        self
+    }
+
+    fn set_heading(mut self, radians: f64) -> Self {
+        self.heading = radians;
+        self
+    }
+
+    fn heading(&self) -> f64 {
+        self.heading
+    }
+
+    fn set_pen_width(mut self, width: f64) -> Self {
+        self.pen_width = width;
+        self.restyle()
+    }
+
+    fn set_color(mut self, color: &str) -> Self {
+        self.color = color.to_string();
+        self.restyle()
+    }
+
     fn left(mut self, angle: f64) -> Self {
         self.heading = self.heading + angle;
         self
@@ -155,6 +242,7 @@ impl TurtleTrait for Turtle {
             self.pen = true;
             self.start = Some(self.position.clone());
             self.lines.push(vec![self.position.clone()]);
+            self.line_styles.push((self.color.clone(), self.pen_width));
             self
         }
     }
@@ -181,14 +269,39 @@ impl TurtleTrait for Turtle {
 
     fn pop(mut self) -> Self {
         match self.stack.pop() {
-            Some(t) => Turtle {
-                lines: self.lines,
-                ..t
-            },
+            Some(t) => {
+                let restored = Turtle {
+                    lines: self.lines,
+                    line_styles: self.line_styles,
+                    ..t
+                };
+                // The branch may have drawn with a different pen, and whatever we
+                // draw next should be back in the one we pushed.
+                let current = (restored.color.clone(), restored.pen_width);
+                match restored.line_styles.last() {
+                    Some(style) if *style != current => restored.restyle(),
+                    _ => restored,
+                }
+            }
             None => self
         }
     }
 
+    fn walk_parametric(mut self, path: &[(char, Vec<f64>)]) -> Self {
+        for (c, params) in path {
+            self = match (c, params.first()) {
+                ('[', _) => self.push(),
+                (']', _) => self.pop(),
+                ('-', Some(angle)) => self.left(*angle),
+                ('+', Some(angle)) => self.right(*angle),
+                ('!', Some(width)) => self.set_pen_width(*width),
+                (c, Some(distance)) if c.is_alphabetic() => self.fwd(*distance),
+                _ => self,
+            }
+        }
+        self
+    }
+
     fn to_multiline(&mut self) -> MultiLineString<f64> {
         // MultiLineString::new(vec![])
         self.lines.iter().map(|line| {
@@ -196,6 +309,22 @@ impl TurtleTrait for Turtle {
         }).collect()
     }
 
+    fn to_layers(&self) -> Vec<(String, f64, MultiLineString<f64>)> {
+        let mut layers: Vec<(String, f64, MultiLineString<f64>)> = vec![];
+        for (line, (color, width)) in self.lines.iter().zip(self.line_styles.iter()) {
+            let line = LineString::from(line.clone());
+            match layers.last_mut() {
+                Some((last_color, last_width, lines))
+                    if last_color == color && last_width == width =>
+                {
+                    lines.0.push(line)
+                }
+                _ => layers.push((color.clone(), *width, MultiLineString::new(vec![line]))),
+            }
+        }
+        layers
+    }
+
     fn to_polygon(&mut self) -> Result<Polygon<f64>, geo_types::Error> {
         match self.lines.len() {
             1 => Ok(Polygon::new(LineString::from(self.lines[0].clone()), vec![])),
@@ -206,9 +335,53 @@ impl TurtleTrait for Turtle {
         }
     }
 
-    // fn to_multipolygon(self) -> Result<MultiPolygon<f64>, geo_types::Error> {
-    //
-    // }
+    fn to_multipolygon(&mut self) -> Result<MultiPolygon<f64>, geo_types::Error> {
+        let mut rings: Vec<Polygon<f64>> = self
+            .lines
+            .iter()
+            .filter(|line| line.len() >= 3)
+            .map(|line| {
+                let mut ring = LineString::from(line.clone());
+                ring.close();
+                Polygon::new(ring, vec![])
+            })
+            .collect();
+        if rings.is_empty() {
+            return Err(geo_types::Error::MismatchedGeometry {
+                expected: "At least one closed line",
+                found: "No lines with 3 or more points",
+            });
+        }
+        // Biggest first, so anything containing a ring has already been seen.
+        rings.sort_by(|a, b| {
+            b.unsigned_area()
+                .partial_cmp(&a.unsigned_area())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let mut polygons: Vec<Polygon<f64>> = vec![];
+        // For each ring, which polygon it's the shell of (if it is one).
+        let mut shell_of: Vec<Option<usize>> = vec![];
+        for i in 0..rings.len() {
+            let parents: Vec<usize> = (0..i)
+                .filter(|j| rings[*j].contains(rings[i].exterior()))
+                .collect();
+            // Odd depth means it's a hole in the smallest ring around it, which is a shell.
+            match parents.last() {
+                Some(parent) if parents.len() % 2 == 1 => {
+                    if let Some(poly) = shell_of[*parent] {
+                        polygons[poly].interiors_push(rings[i].exterior().clone());
+                    }
+                    shell_of.push(None);
+                }
+                _ => {
+                    polygons.push(rings[i].clone());
+                    shell_of.push(Some(polygons.len() - 1));
+                }
+            }
+        }
+        Ok(MultiPolygon::new(polygons))
+    }
+
     fn walk_lpath(mut self, lpath: &String, angle: f64, distance: f64) -> Self {
         for c in lpath.chars() {
             self = match c {
@@ -223,6 +396,25 @@ impl TurtleTrait for Turtle {
     }
 }
 
+impl Turtle {
+    /// The pen changed, so if we're mid-line, start a new one from here with the
+    /// new style. A line that hasn't gone anywhere yet just gets restyled.
+    fn restyle(mut self) -> Self {
+        if !self.pen {
+            return self;
+        }
+        let style = (self.color.clone(), self.pen_width);
+        match (self.lines.last(), self.line_styles.last_mut()) {
+            (Some(line), Some(last_style)) if line.len() < 2 => *last_style = style,
+            _ => {
+                self.lines.push(vec![self.position.clone()]);
+                self.line_styles.push(style);
+            }
+        }
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -270,6 +462,121 @@ mod tests {
         assert_eq!(t.pen, false);
     }
 
+    #[test]
+    fn test_to_multipolygon() {
+        let square = |t: Turtle, size: f64| {
+            t.pen_down()
+                .fwd(size)
+                .right(degrees(90.0))
+                .fwd(size)
+                .right(degrees(90.0))
+                .fwd(size)
+                .right(degrees(90.0))
+                .close()
+                .pen_up()
+        };
+        let t = square(Turtle::new(), 100.0);
+        // Wander over into the middle for the hole, with the pen up
+        let t = t
+            .right(degrees(90.0))
+            .fwd(25.0)
+            .right(degrees(90.0))
+            .fwd(25.0)
+            .left(degrees(90.0));
+        let mut t = square(t, 50.0);
+        let polys = t.to_multipolygon().unwrap();
+        assert_eq!(polys.0.len(), 1);
+        assert_eq!(polys.0[0].interiors().len(), 1);
+        assert_eq!(polys.0[0].exterior().0.len(), 5);
+        assert!(Turtle::new().to_multipolygon().is_err());
+    }
+
+    #[test]
+    fn test_goto() {
+        let mut t = Turtle::new()
+            .pen_down()
+            .goto(10.0, 0.0)
+            .goto(10.0, 10.0);
+        let lines = t.to_multiline();
+        assert_eq!(lines.0.len(), 1);
+        assert_eq!(lines.0[0].0.len(), 3);
+        assert!(Point::from(lines.0[0].0[2]).distance(&Point::new(10.0, 10.0)) < 0.0001);
+
+        // Pen up just moves, and the heading comes back with pop
+        let t = Turtle::new()
+            .set_heading(degrees(90.0))
+            .push()
+            .set_heading(0.0)
+            .goto(5.0, 5.0)
+            .pop();
+        assert_eq!(t.lines.len(), 0);
+        assert!((t.heading() - degrees(90.0)).abs() < 0.0001);
+        assert!(t.position == Point::new(0.0f64, 0.0f64));
+    }
+
+    #[test]
+    fn test_to_layers() {
+        let t = Turtle::new()
+            .set_pen_width(0.3)
+            .pen_down()
+            .fwd(10.0)
+            .fwd(10.0)
+            .set_color("red")
+            .fwd(10.0);
+        let layers = t.to_layers();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].0, "black");
+        assert_eq!(layers[0].1, 0.3);
+        assert_eq!(layers[0].2 .0[0].0.len(), 3);
+        assert_eq!(layers[1].0, "red");
+        // The red line picks up where the black one stopped
+        assert_eq!(layers[1].2 .0[0].0[0].x, 20.0);
+    }
+
+    #[test]
+    fn test_pop_restyles() {
+        let t = Turtle::new()
+            .pen_down()
+            .fwd(10.0)
+            .push()
+            .set_color("red")
+            .set_pen_width(1.0)
+            .left(degrees(90.0))
+            .fwd(5.0)
+            .pop()
+            .fwd(10.0);
+        let layers = t.to_layers();
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[1].0, "red");
+        assert_eq!(layers[1].1, 1.0);
+        // Back on the trunk in the original pen, from where the branch started.
+        assert_eq!(layers[2].0, "black");
+        assert_eq!(layers[2].1, 0.5);
+        assert_eq!(layers[2].2 .0[0].0[0].x, 10.0);
+        assert_eq!(layers[2].2 .0[0].0[1].x, 20.0);
+    }
+
+    #[test]
+    fn test_walk_parametric() {
+        let path = vec![
+            ('!', vec![1.0]),
+            ('F', vec![10.0]),
+            ('[', vec![]),
+            ('+', vec![degrees(90.0)]),
+            ('F', vec![5.0]),
+            (']', vec![]),
+            ('!', vec![0.5]),
+            ('F', vec![2.0]),
+            ('F', vec![]),
+        ];
+        let t = Turtle::new().pen_down().walk_parametric(&path);
+        assert!(t.position.distance(&Point::new(12.0, 0.0)) < 0.0001);
+        let layers = t.to_layers();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].1, 1.0);
+        assert_eq!(layers[1].1, 0.5);
+    }
+
     #[test]
     fn test_simple_box() {
         let t = Turtle::new()